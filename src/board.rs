@@ -13,22 +13,13 @@ fn move_bit(board: u64, from: Tile, to: Tile) -> u64 {
     (board & !from_bit) | to_bit
 }
 
-/// Return a bitboard with all the bits in the same sector as the given bit
-/// set to 1.
-fn sector_bits(board: u64, sector: Sector) -> u64 {
-    // Get the sector bits
-    let mut result = 0;
-    let sector = sector.get_index();
-    let rank = sector / 4;
-    let file = sector % 4;
-    for i in 0..2 {
-        for j in 0..2 {
-            result |= 1 << ((rank * 2 + i) * 8 + file * 2 + j);
-        }
-    }
-
-    // Remove the bits that are not on the board
-    result & board
+/// Get a pseudorandom Zobrist key for the given index.
+/// The keys are derived with SplitMix64, so they're the same on every run.
+fn zobrist_key(index: u64) -> u64 {
+    let mut z = index.wrapping_add(1).wrapping_mul(0x9E37_79B9_7F4A_7C15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
 }
 
 /// Is the path from a source tile to a target tile blocked?
@@ -38,7 +29,7 @@ fn is_blocked(board: u64, from: Tile, to: Tile) -> bool {
     // Check if the path is blocked
     
     let mut tile = from;
-    for _ in 0..8 {
+    for _ in 0..Board::SIZE {
         tile.step_towards(to);
         if tile == from {
             continue;
@@ -60,7 +51,10 @@ fn visible_pieces(all_pieces: u64, origin: Tile, vision: u64) -> u64 {
     // Get the rank and file of the origin
     // Check all the bits in the attack and remove the ones that are not visible
     let mut result = 0;
-    let mut visible_attack_bits = vision;
+    // The rank, file, and diagonal tiles a sliding piece sees include its own
+    // tile, but a piece never attacks the tile it stands on. Otherwise, the
+    // tiles of the rooks, bishops, and queens would show up in the attacked tiles.
+    let mut visible_attack_bits = vision & !origin.to_bit();
 
     // For each attack bit, check if its path is blocked
     while visible_attack_bits != 0 {
@@ -84,6 +78,7 @@ fn visible_pieces(all_pieces: u64, origin: Tile, vision: u64) -> u64 {
 }
 
 #[derive(Copy, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Board {
     white_pawns: u64,
     white_knights: u64,
@@ -101,6 +96,10 @@ pub struct Board {
     castling_rights: CastlingRights,
     current_turn: Color,
     winner: Option<Color>,
+    /// Has a player claimed a draw?
+    drawn: bool,
+    /// The number of plies since the last capture, pawn move, or purchase.
+    plies_since_progress: u16,
 }
 
 impl Default for Board {
@@ -122,11 +121,13 @@ impl Default for Board {
             castling_rights: CastlingRights::default(),
             current_turn: Color::default(),
             winner: None,
+            drawn: false,
+            plies_since_progress: 0,
         };
 
         // Spawn the white pieces
-        for file in 'a'..='h' {
-            result.spawn_white_pawn(Tile::new(Rank::PAWN_STARTER_WHITE, File::from_char(file)));
+        for file in 0..Self::SIZE {
+            result.spawn_white_pawn(Tile::new(Rank::PAWN_STARTER_WHITE, File::from_index(file)));
         }
         result.spawn_white_knight(Tile::new(Rank::BACK_RANK_WHITE, File::B));
         result.spawn_white_knight(Tile::new(Rank::BACK_RANK_WHITE, File::G));
@@ -138,8 +139,8 @@ impl Default for Board {
         result.spawn_white_king(Tile::new(Rank::BACK_RANK_WHITE, File::E));
 
         // Spawn the black pieces
-        for file in 'a'..='h' {
-            result.spawn_black_pawn(Tile::new(Rank::PAWN_STARTER_BLACK, File::from_char(file)));
+        for file in 0..Self::SIZE {
+            result.spawn_black_pawn(Tile::new(Rank::PAWN_STARTER_BLACK, File::from_index(file)));
         }
         result.spawn_black_knight(Tile::new(Rank::BACK_RANK_BLACK, File::B));
         result.spawn_black_knight(Tile::new(Rank::BACK_RANK_BLACK, File::G));
@@ -155,6 +156,15 @@ impl Default for Board {
 }
 
 impl Board {
+    /// The number of ranks and files on the board.
+    pub const SIZE: u8 = 8;
+    /// The number of tiles on the board.
+    pub const TILES: usize = Self::SIZE as usize * Self::SIZE as usize;
+    /// The number of plies without progress before either player may claim a draw, like the fifty-move rule.
+    pub const PROGRESS_DRAW_PLIES: u16 = 100;
+    /// The number of plies without progress before the game is drawn, like the seventy-five-move rule.
+    pub const PROGRESS_FORCED_DRAW_PLIES: u16 = 150;
+
     /// An empty board with no pieces on it.
     pub fn empty() -> Self {
         Self {
@@ -174,14 +184,257 @@ impl Board {
             castling_rights: CastlingRights::none(),
             current_turn: Color::default(),
             winner: None,
+            drawn: false,
+            plies_since_progress: 0,
         }
     }
 
+    /// The number of plies since the last capture, pawn move, or purchase.
+    pub fn plies_since_progress(&self) -> u16 {
+        self.plies_since_progress
+    }
+
+    /// The halfmove clock, as written in FEN, for the fifty-move rule.
+    /// This is the number of plies since the last capture or pawn move.
+    /// Purchases and deployments also reset the clock, since they change
+    /// the material on the board just like a capture does.
+    /// Reserving a piece, castling, and passing don't.
+    pub fn halfmove_clock(&self) -> u16 {
+        self.plies_since_progress
+    }
+
+    /// Has the game gone fifty moves by each player without any progress,
+    /// so the player to move may claim a draw?
+    /// See `halfmove_clock` for what counts as progress.
+    pub fn is_fifty_move_draw(&self) -> bool {
+        self.halfmove_clock() >= Self::PROGRESS_DRAW_PLIES
+    }
+
+    /// Has the game gone seventy-five moves by each player without any progress,
+    /// so it's drawn whether or not a player claims it?
+    pub fn is_seventy_five_move_draw(&self) -> bool {
+        self.halfmove_clock() >= Self::PROGRESS_FORCED_DRAW_PLIES
+    }
+
+    /// Has a player claimed a draw?
+    pub fn is_drawn(&self) -> bool {
+        self.drawn
+    }
+
+    /// Is the game over, because a player won or claimed a draw?
+    fn is_finished(&self) -> bool {
+        self.winner.is_some() || self.drawn
+    }
+
+    /// Count a ply, resetting the count if the ply made progress.
+    fn count_ply(&mut self, is_progress: bool) {
+        self.plies_since_progress = if is_progress {
+            0
+        } else {
+            self.plies_since_progress.saturating_add(1)
+        };
+    }
+
+    /// Count a turn of several actions as a single ply, where `plies` is the
+    /// count from before the turn. The turn made progress if any action did.
+    pub(crate) fn count_turn(&mut self, plies: u16, is_progress: bool) {
+        self.plies_since_progress = plies;
+        self.count_ply(is_progress);
+    }
+
     /// Set the turn of who's allowed to play the next move.
     pub fn set_turn(&mut self, color: Color) {
         self.current_turn = color;
     }
 
+    /// Write this board as a position in Forsyth-Edwards Notation.
+    /// The board doesn't count moves, so the fullmove number is always 1.
+    /// If the game has been won, the result is written after the usual
+    /// six fields, as `1-0` or `0-1`, so `from_fen` can load it back.
+    pub fn to_fen(&self) -> String {
+        let mut result = String::new();
+        for rank in (0..Self::SIZE).rev().map(Rank::from_index) {
+            let mut empty = 0;
+            for file in (0..Self::SIZE).map(File::from_index) {
+                match self.get_piece(Tile::new(rank, file)) {
+                    Some(piece) => {
+                        if empty > 0 {
+                            result.push_str(&empty.to_string());
+                            empty = 0;
+                        }
+                        result.push(piece.to_fen_char());
+                    },
+                    None => empty += 1,
+                }
+            }
+            if empty > 0 {
+                result.push_str(&empty.to_string());
+            }
+            if rank != Rank::from_index(0) {
+                result.push('/');
+            }
+        }
+
+        result.push_str(match self.current_turn {
+            Color::White => " w ",
+            Color::Black => " b ",
+        });
+
+        let rights = self.castling_rights;
+        let castling: String = [
+            (rights.white_king_side, 'K'),
+            (rights.white_queen_side, 'Q'),
+            (rights.black_king_side, 'k'),
+            (rights.black_queen_side, 'q'),
+        ].into_iter().filter(|(allowed, _)| *allowed).map(|(_, c)| c).collect();
+        result.push_str(if castling.is_empty() { "-" } else { &castling });
+
+        match self.en_passant {
+            Some(tile) => result.push_str(&format!(" {tile}")),
+            None => result.push_str(" -"),
+        }
+        result.push_str(&format!(" {} 1", self.plies_since_progress));
+
+        match self.winner {
+            Some(Color::White) => result.push_str(" 1-0"),
+            Some(Color::Black) => result.push_str(" 0-1"),
+            None if self.drawn => result.push_str(" 1/2-1/2"),
+            None => {},
+        }
+        result
+    }
+
+    /// Load a board from a position in Forsyth-Edwards Notation.
+    /// Kings can be bought, so a player may have any number of them, or none.
+    /// The halfmove clock and fullmove number are optional.
+    /// The halfmove clock is kept, but the fullmove number is ignored.
+    /// A seventh field, written by `to_fen`, gives the winner of the game,
+    /// or `1/2-1/2` if a player claimed a draw.
+    pub fn from_fen(fen: &str) -> Result<Self, FenError> {
+        let fields: Vec<&str> = fen.split_whitespace().collect();
+        if ![4, 6, 7].contains(&fields.len()) {
+            return Err(FenError::WrongFieldCount);
+        }
+
+        // Place the pieces, from the black back rank down to the white back rank
+        let mut result = Self::empty();
+        let ranks: Vec<&str> = fields[0].split('/').collect();
+        if ranks.len() != Self::SIZE as usize {
+            return Err(FenError::InvalidPiecePlacement);
+        }
+        for (i, pieces) in ranks.into_iter().enumerate() {
+            let rank = Rank::from_index(Self::SIZE - 1 - i as u8);
+            let mut file = 0;
+            for c in pieces.chars() {
+                if let Some(skip) = c.to_digit(10) {
+                    // A skip can't be empty or run past the edge of the board
+                    if skip == 0 || file as u32 + skip > Self::SIZE as u32 {
+                        return Err(FenError::InvalidPiecePlacement);
+                    }
+                    file += skip as u8;
+                    continue;
+                }
+                if file >= Self::SIZE {
+                    return Err(FenError::InvalidPiecePlacement);
+                }
+                let piece = PieceType::from_str(&c.to_ascii_uppercase().to_string()).map_err(|_| FenError::InvalidPiecePlacement)?;
+                result.current_turn = if c.is_ascii_uppercase() { Color::White } else { Color::Black };
+                result.spawn(piece, Tile::new(rank, File::from_index(file)));
+                file += 1;
+            }
+            if file != Self::SIZE {
+                return Err(FenError::InvalidPiecePlacement);
+            }
+        }
+
+        result.current_turn = match fields[1] {
+            "w" => Color::White,
+            "b" => Color::Black,
+            _ => return Err(FenError::InvalidActiveColor),
+        };
+
+        if fields[2] != "-" {
+            for c in fields[2].chars() {
+                match c {
+                    'K' => result.castling_rights.white_king_side = true,
+                    'Q' => result.castling_rights.white_queen_side = true,
+                    'k' => result.castling_rights.black_king_side = true,
+                    'q' => result.castling_rights.black_queen_side = true,
+                    _ => return Err(FenError::InvalidCastling),
+                }
+            }
+        }
+
+        if fields[3] != "-" {
+            result.en_passant = Some(Tile::from_str(fields[3]).map_err(|_| FenError::InvalidEnPassant)?);
+        }
+
+        if let Some(halfmove_clock) = fields.get(4) {
+            result.plies_since_progress = halfmove_clock.parse().map_err(|_| FenError::InvalidHalfmoveClock)?;
+        }
+
+        result.winner = match fields.get(6) {
+            None => None,
+            Some(&"1-0") => Some(Color::White),
+            Some(&"0-1") => Some(Color::Black),
+            Some(&"1/2-1/2") => None,
+            Some(_) => return Err(FenError::InvalidWinner),
+        };
+        result.drawn = fields.get(6) == Some(&"1/2-1/2");
+
+        if result.sanity_check().is_err() {
+            return Err(FenError::InvalidPosition);
+        }
+        Ok(result)
+    }
+
+    /// Get the piece on every tile of the board, indexed by `Tile::index`,
+    /// so a1 is first and h8 is last.
+    pub fn to_array(&self) -> [Option<Piece>; Self::TILES] {
+        let mut result = [None; Self::TILES];
+        for (tile, piece) in self.iter_pieces() {
+            result[tile.index() as usize] = Some(piece);
+        }
+        result
+    }
+
+    /// Create a board with the given pieces, indexed like `to_array`.
+    /// White moves first, and castling is allowed for every king and rook
+    /// still on their starting tiles.
+    pub fn from_array(pieces: &[Option<Piece>; Self::TILES]) -> Self {
+        let mut result = Self::empty();
+        for tile in Tile::all() {
+            if let Some(piece) = pieces[tile.index() as usize] {
+                result.current_turn = piece.get_color();
+                result.spawn(piece.get_type(), tile);
+            }
+        }
+        result.current_turn = Color::White;
+
+        let on_start = |piece: PieceType, color: Color, tile: Tile| pieces[tile.index() as usize] == Some(Piece::new(piece, color));
+        result.castling_rights = CastlingRights::default();
+        for color in [Color::White, Color::Black] {
+            let king_on_start = on_start(PieceType::King, color, Tile::king_start_position(color));
+            for side in [CastlingSide::King, CastlingSide::Queen] {
+                if !king_on_start || !on_start(PieceType::Rook, color, Tile::rook_start_position(color, side)) {
+                    result.castling_rights.disable_castling_color_and_side(color, side);
+                }
+            }
+        }
+        result
+    }
+
+    /// Load a board from a FEN position, then play the given UCI moves on it.
+    /// This returns the index of the first move that couldn't be played, if any.
+    pub fn from_fen_and_moves(fen: &str, moves: &[&str]) -> Result<Self, FenError> {
+        let mut result = Self::from_fen(fen)?;
+        for (i, player_move) in moves.iter().enumerate() {
+            let player_move = Move::from_uci(player_move).ok_or(FenError::InvalidMove(i))?;
+            result.apply(player_move).map_err(|_| FenError::InvalidMove(i))?;
+        }
+        Ok(result)
+    }
+
     /// Perform a sanity check on the board.
     /// Confirm there are no overlapping pieces.
     pub fn sanity_check(&self) -> Result<(), ()> {
@@ -244,7 +497,7 @@ impl Board {
         }
 
         // Check if king is off square, and if we still have castling rights
-        if self.white_king != Tile::king_start_position(Color::White).to_bit() {
+        if self.white_king & Tile::king_start_position(Color::White).to_bit() == 0 {
             if self.castling_rights.can_castle(Tile::king_start_position(Color::White), Tile::new(Rank::BACK_RANK_WHITE, File::H)) {
                 error!("White king is off square, but still has castling rights");
                 return Err(());
@@ -254,7 +507,7 @@ impl Board {
                 return Err(());
             }
         }
-        if self.black_king != Tile::king_start_position(Color::Black).to_bit() {
+        if self.black_king & Tile::king_start_position(Color::Black).to_bit() == 0 {
             if self.castling_rights.can_castle(Tile::king_start_position(Color::Black), Tile::new(Rank::BACK_RANK_BLACK, File::H)) {
                 error!("Black king is off square, but still has castling rights");
                 return Err(());
@@ -386,16 +639,16 @@ impl Board {
     }
 
     /// For all the sectors on the board, return true if the given color controls
-    /// the sector. Controlling a sector is determined by having the most points
-    /// in the sector.
+    /// the sector under the given market's rules. Controlling a sector is determined
+    /// by having the most value in the sector, priced by the market.
     #[inline]
-    pub(crate) fn get_controlled_sectors(&self, color: Color) -> [bool; Sector::NUM_SECTORS] {
+    pub(crate) fn get_controlled_sectors(&self, color: Color, market: &Market) -> [bool; Sector::NUM_SECTORS] {
         // The result of who controls what sector
         let mut result = [false; Sector::NUM_SECTORS];
         // For each sector, check if the given color controls it
         for sector in 0..Sector::NUM_SECTORS {
             // If the given color controls the sector, set the result to trueS
-            if self.controls_sector(Sector::from_index(sector), color) {
+            if market.who_controls_sector(self, Sector::from_index(sector)) == Some(color) {
                 info!("{:?} controls sector {}", color, sector);
                 result[sector] = true;
             }
@@ -403,21 +656,18 @@ impl Board {
         result
     }
 
-    /// Does the given color control the given sector?
-    #[inline]
-    pub fn controls_sector(&self, sector: Sector, color: Color) -> bool {
-        self.who_controls_sector(sector) == Some(color)
-    }
-
-    /// Which color controls the given sector?
-    pub fn who_controls_sector(&self, sector: Sector) -> Option<Color> {
-        // Whoa has the majority point value in the sector?
-        let (white_sector_value, black_sector_value) = self.get_sector_values(sector);
+    /// Which color controls the given sector, if control requires more than
+    /// `weight` times the other color's value in the sector, priced by the market?
+    pub(crate) fn who_controls_sector_in_market(&self, sector: Sector, weight: f64, market: &Market) -> Option<Color> {
+        // Whoa has the majority value in the sector?
+        let white_sector_value = self.sector_value(sector, Color::White, market).get_amount() as f64;
+        let black_sector_value = self.sector_value(sector, Color::Black, market).get_amount() as f64;
+        debug!("Sector {} has a value of {} for white and {} for black", sector, white_sector_value, black_sector_value);
 
-        if white_sector_value > black_sector_value {
+        if white_sector_value > black_sector_value * weight {
             debug!("White controls sector {}", sector);
             Some(Color::White)
-        } else if black_sector_value > white_sector_value {
+        } else if black_sector_value > white_sector_value * weight {
             debug!("Black controls sector {}", sector);
             Some(Color::Black)
         } else {
@@ -425,42 +675,27 @@ impl Board {
         }
     }
 
-    /// Get the value for a given player's sector on the board.
-    fn get_sector_values(&self, sector: Sector) -> (Currency, Currency) {
-        // Create a new board, where all the pieces that aren't in the sector are masked out
-        let mut board = *self;
-        let sector_bits = sector_bits(self.all_pieces_as_bits(), sector);
-        let other_sector_bits = !sector_bits;
-        board.white_pawns &= sector_bits;
-        board.white_knights &= sector_bits;
-        board.white_bishops &= sector_bits;
-        board.white_rooks &= sector_bits;
-        board.white_queens &= sector_bits;
-        board.white_king &= sector_bits;
-        board.black_pawns &= sector_bits;
-        board.black_knights &= sector_bits;
-        board.black_bishops &= sector_bits;
-        board.black_rooks &= sector_bits;
-        board.black_queens &= sector_bits;
-        board.black_king &= sector_bits;
-
-        // Get the values of the sector, for white and black.
-        let mut white_value = Currency::zero();
-        let mut black_value = Currency::zero();
-        for tile in Tile::all() {
-            if other_sector_bits & tile.to_bit() != 0 {
-                continue;
-            }
-            if let Some(piece) = board.get_piece(tile) {
-                let value = Currency::penny() * piece.get_type().get_value();
-                match piece.get_color() {
-                    Color::White => white_value += value,
-                    Color::Black => black_value += value,
-                } 
-            }
-        }
-        debug!("Sector {} has a value of {} for white and {} for black", sector, white_value, black_value);
-        (white_value, black_value)
+    /// Get the given color's pieces in the given sector.
+    pub fn pieces_in_sector(&self, sector: Sector, color: Color) -> Vec<(Tile, Piece)> {
+        Tile::in_sector(sector)
+            .filter_map(|tile| self.get_piece(tile).map(|piece| (tile, piece)))
+            .filter(|(_, piece)| piece.get_color() == color)
+            .collect()
+    }
+
+    /// Get the given color's least valuable piece in the given sector, priced by the market.
+    /// This is the cheapest piece to sacrifice while contesting the sector.
+    pub fn least_valuable_piece_on(&self, sector: Sector, color: Color, market: &Market) -> Option<(Tile, Piece)> {
+        self.pieces_in_sector(sector, color)
+            .into_iter()
+            .min_by_key(|(_, piece)| market.get_piece_value(piece.get_type()))
+    }
+
+    /// Get the market value of the given color's pieces in the given sector.
+    pub fn sector_value(&self, sector: Sector, color: Color, market: &Market) -> Currency {
+        self.pieces_in_sector(sector, color)
+            .into_iter()
+            .fold(Currency::zero(), |total, (_, piece)| total + market.get_piece_value(piece.get_type()))
     }
 
     /// Get the king bits of the given color
@@ -472,8 +707,100 @@ impl Board {
         }
     }
 
+    /// Get the tile of the given color's king.
+    /// If the player has bought more kings, this is the first one.
+    pub fn king_tile(&self, color: Color) -> Option<Tile> {
+        let king_bits = self.get_king_bits(color);
+        if king_bits == 0 {
+            None
+        } else {
+            Some(Tile::from_bit(king_bits & king_bits.wrapping_neg()))
+        }
+    }
+
+    /// Get the tiles of all the given color's kings.
+    pub fn king_tiles(&self, color: Color) -> TileSet {
+        TileSet(self.get_king_bits(color))
+    }
+
+    /// Get the tiles of all the given pieces on the board.
+    pub fn find_pieces(&self, piece: Piece) -> TileSet {
+        TileSet(self.get_piece_bits(piece))
+    }
+
+    /// Iterate over the pieces on the board and their tiles, from a1 to h8.
+    /// This walks the bitboards, so only occupied tiles are visited,
+    /// which is faster than calling `get_piece` on every tile.
+    pub fn iter_pieces(&self) -> impl Iterator<Item = (Tile, Piece)> + '_ {
+        self.iter_bits(self.white_pieces_as_bits() | self.black_pieces_as_bits())
+    }
+
+    /// Iterate over the given color's pieces and their tiles, from a1 to h8.
+    pub fn iter_pieces_of(&self, color: Color) -> impl Iterator<Item = (Tile, Piece)> + '_ {
+        self.iter_bits(self.get_color_bits(color))
+    }
+
+    /// Iterate over the pieces on the tiles in the given bitboard, lowest bit first.
+    fn iter_bits(&self, mut bits: u64) -> impl Iterator<Item = (Tile, Piece)> + '_ {
+        core::iter::from_fn(move || {
+            if bits == 0 {
+                return None;
+            }
+            let tile = Tile::from_nth(bits.trailing_zeros() as u8);
+            bits &= bits - 1;
+            Some((tile, self.get_piece(tile)?))
+        })
+    }
+
+    /// Get the tile of one of the given pieces, if there are any on the board.
+    /// If there are several, this is the one on the lowest tile, starting from a1.
+    pub fn find_piece(&self, piece: Piece) -> Option<Tile> {
+        let bits = self.get_piece_bits(piece);
+        if bits == 0 {
+            None
+        } else {
+            Some(Tile::from_bit(bits & bits.wrapping_neg()))
+        }
+    }
+
+    /// Count the given color's bishops on light squares, or on dark squares.
+    pub fn bishops_on_color(&self, color: Color, light: bool) -> usize {
+        self.find_pieces(Piece::new(PieceType::Bishop, color))
+            .into_iter()
+            .filter(|tile| tile.is_light_square() == light)
+            .count()
+    }
+
+    /// Get the bitboard of all the pieces of the given color.
+    #[inline]
+    fn get_color_bits(&self, color: Color) -> u64 {
+        match color {
+            Color::White => self.white_pieces_as_bits(),
+            Color::Black => self.black_pieces_as_bits(),
+        }
+    }
+
+    /// Get the bitboard for the given piece.
+    #[inline]
+    fn get_piece_bits(&self, piece: Piece) -> u64 {
+        match (piece.get_type(), piece.get_color()) {
+            (PieceType::Pawn, Color::White) => self.white_pawns,
+            (PieceType::Knight, Color::White) => self.white_knights,
+            (PieceType::Bishop, Color::White) => self.white_bishops,
+            (PieceType::Rook, Color::White) => self.white_rooks,
+            (PieceType::Queen, Color::White) => self.white_queens,
+            (PieceType::King, Color::White) => self.white_king,
+            (PieceType::Pawn, Color::Black) => self.black_pawns,
+            (PieceType::Knight, Color::Black) => self.black_knights,
+            (PieceType::Bishop, Color::Black) => self.black_bishops,
+            (PieceType::Rook, Color::Black) => self.black_rooks,
+            (PieceType::Queen, Color::Black) => self.black_queens,
+            (PieceType::King, Color::Black) => self.black_king,
+        }
+    }
+
     /// Return 1 for all the bits of the board that white is attacking.
-    fn white_attacking_bits(&self) -> u64 {
+    pub fn white_attacking_bits(&self) -> u64 {
         let all_pieces_as_bits = self.all_pieces_as_bits();
 
         // Get the white pawn attacking bits
@@ -678,6 +1005,44 @@ impl Board {
             | black_king_attacking_bits
     }
 
+    /// Get the board as seen by the given color in a fog of war game.
+    /// Enemy pieces are only visible if they stand on a tile the player is attacking.
+    ///
+    /// This is a transform for display and engine input only: the enemy's castling
+    /// rights are dropped, and check or castling logic should not be run on the result.
+    pub fn visible_to(&self, color: Color) -> Self {
+        let visible = self.get_attacking_bits(color);
+        let mut result = *self;
+        match color {
+            Color::White => {
+                result.black_pawns &= visible;
+                result.black_knights &= visible;
+                result.black_bishops &= visible;
+                result.black_rooks &= visible;
+                result.black_queens &= visible;
+                result.black_king &= visible;
+            },
+            Color::Black => {
+                result.white_pawns &= visible;
+                result.white_knights &= visible;
+                result.white_bishops &= visible;
+                result.white_rooks &= visible;
+                result.white_queens &= visible;
+                result.white_king &= visible;
+            },
+        }
+
+        // Don't leak the enemy's castling rights or a hidden en passant pawn
+        result.castling_rights.disable_castling_color(!color);
+        if let Some(en_passant) = result.en_passant {
+            let pawn_tile = en_passant.advance(en_passant.get_player_side(), 1);
+            if !result.has_piece_on(pawn_tile) {
+                result.en_passant = None;
+            }
+        }
+        result
+    }
+
     #[inline]
     fn get_attacking_bits(&self, color: Color) -> u64 {
         match color {
@@ -686,8 +1051,118 @@ impl Board {
         }
     }
 
+    /// Get the tiles attacked by the given color's pieces.
+    /// Sliding pieces are blocked by the first piece in their way,
+    /// but the blocking piece's tile is still attacked.
+    pub fn attacks_by(&self, color: Color) -> TileSet {
+        TileSet::from_bits(self.get_attacking_bits(color))
+    }
+
+    /// Get the tiles with a piece on them.
+    pub fn occupied(&self) -> TileSet {
+        TileSet::from_bits(self.all_pieces_as_bits())
+    }
+
+    /// Get the tiles with one of the given color's pieces on them.
+    pub fn occupied_by(&self, color: Color) -> TileSet {
+        TileSet::from_bits(self.get_color_bits(color))
+    }
+
+    /// Is the given tile attacked by any of the given color's pieces?
+    pub fn is_attacked(&self, tile: Tile, by: Color) -> bool {
+        (self.get_attacking_bits(by) & tile.to_bit()) != 0
+    }
+
+    /// Would the given color recapture on the given tile?
+    /// The tile is treated as if it's occupied by one of that color's pieces,
+    /// so only pieces that could take back on it count: pawns count their diagonals.
+    pub fn is_defended(&self, tile: Tile, by: Color) -> bool {
+        let mut board = *self;
+        board.current_turn = by;
+        // A knight never attacks its own tile, so it won't defend itself
+        board.spawn(PieceType::Knight, tile);
+        board.is_attacked(tile, by)
+    }
+
+    /// The tiles attacked by the piece on the given tile, stopping at blockers.
+    /// An empty tile attacks nothing.
+    pub fn piece_attacks_from(&self, tile: Tile) -> TileSet {
+        let Some(piece) = self.get_piece(tile) else {
+            return TileSet::default();
+        };
+        // A rook's rank and file include its own tile, but it never attacks itself
+        let vision = tile.attacking_bits(piece.get_type(), piece.get_color()) & !tile.to_bit();
+        // Knights jump, so nothing blocks their attacks
+        if piece.get_type() == PieceType::Knight {
+            TileSet(vision)
+        } else {
+            TileSet(visible_pieces(self.all_pieces_as_bits(), tile, vision))
+        }
+    }
+
+    /// The tiles of the given color's pieces that attack the given tile.
+    /// Unlike `attacks_by`, this finds where the attacks come from.
+    /// Sliding pieces behind a blocker don't attack past it, even if
+    /// the blocker is pinned and can't legally move.
+    pub fn attackers_of(&self, tile: Tile, color: Color) -> TileSet {
+        let mut result = TileSet::default();
+        for from in self.occupied_by(color) {
+            if self.piece_attacks_from(from).contains(tile) {
+                result.insert(from);
+            }
+        }
+        result
+    }
+
+    /// The tile of the given color's least valuable piece attacking the given tile.
+    fn least_valuable_attacker(&self, tile: Tile, color: Color, market: &Market) -> Option<Tile> {
+        self.attackers_of(tile, color)
+            .into_iter()
+            .filter_map(|from| self.get_piece(from).map(|piece| (from, piece)))
+            .min_by_key(|(_, piece)| market.get_piece_value(piece.get_type()))
+            .map(|(from, _)| from)
+    }
+
+    /// Is the piece on the given tile hanging? A piece is hanging if the
+    /// opponent can win material by capturing it, after all the recaptures,
+    /// with the pieces priced by the market.
+    /// Empty tiles are never hanging.
+    pub fn is_hanging(&self, tile: Tile, market: &Market) -> bool {
+        match self.get_piece(tile) {
+            Some(piece) => self.see(tile, !piece.get_color(), market).is_surplus(),
+            None => false,
+        }
+    }
+
+    /// Static exchange evaluation: how much material can the given color win
+    /// by capturing the piece on the given tile?
+    /// Both sides recapture with their least valuable attacker, and either
+    /// side may stop capturing when it's no longer profitable, so this is
+    /// never negative. The pieces are priced by the market.
+    pub fn see(&self, tile: Tile, by: Color, market: &Market) -> Currency {
+        let target = match self.get_piece(tile) {
+            Some(piece) if piece.get_color() != by => piece,
+            _ => return Currency::zero(),
+        };
+
+        // Play out the exchange, recording the value of each captured piece
+        let mut board = *self;
+        let mut gains = Vec::new();
+        let mut victim_value = market.get_piece_value(target.get_type());
+        let mut side = by;
+        while let Some(from) = board.least_valuable_attacker(tile, side, market) {
+            gains.push(victim_value);
+            victim_value = board.get_piece(from).map(|piece| market.get_piece_value(piece.get_type())).unwrap_or_default();
+            board.move_piece(from, tile);
+            side = !side;
+        }
+
+        // Each side only captures if it comes out ahead of stopping
+        gains.into_iter().rev().fold(Currency::zero(), |score, gain| (gain - score).max(Currency::zero()))
+    }
+
     /// Is this move a castling?
-    fn is_castling_move(&self, from: Tile, to: Tile) -> bool {
+    pub(crate) fn is_castling_move(&self, from: Tile, to: Tile) -> bool {
         let src_piece = self.get_piece(from);
 
         if let Some(src_piece) = src_piece {
@@ -707,7 +1182,20 @@ impl Board {
         let enemy_attacking_bits = self.get_attacking_bits(!color);
 
         // Check if the king is in check
-        (king_bits & enemy_attacking_bits) != 0
+        let result = (king_bits & enemy_attacking_bits) != 0;
+        if result && log::log_enabled!(log::Level::Debug) {
+            for from in self.checkers(color) {
+                debug!("{color:?} is in check from {:?} on {from}", self.get_piece(from));
+            }
+        }
+        result
+    }
+
+    /// The tiles of the opponent's pieces that put the player's kings in check.
+    pub fn checkers(&self, color: Color) -> TileSet {
+        self.king_tiles(color)
+            .into_iter()
+            .fold(TileSet::default(), |result, king| result | self.attackers_of(king, !color))
     }
 
     /// Is the player in checkmate?
@@ -720,23 +1208,22 @@ impl Board {
         }
 
         // Check if the player can move out of check
-        for tile in Tile::all() {
-            if let Some(piece) = self.get_piece(tile) {
-                if piece.get_color() == color {
-                    for to in tile.get_moves(piece) {
-                        if self.is_legal_piece_move(tile, to) {
-                            info!("{:?} can move from {:?} to {:?} to get out of check", color, tile, to);
-                            return false;
-                        }
-                    }
-                }
-            }
+        if self.has_any_legal_move(color) {
+            info!("{:?} can move to get out of check", color);
+            return false;
         }
 
         info!("{:?} is in checkmate", color);
         true
     }
 
+    /// Would the given move stalemate the opponent?
+    /// Illegal moves never stalemate anyone.
+    pub fn results_in_stalemate(&self, player_move: &Move) -> bool {
+        let mut board = *self;
+        board.apply(player_move.clone()).is_ok() && board.is_stalemate()
+    }
+
     /// Is the board in a state of stalemate?
     pub fn is_stalemate(&self) -> bool {
         info!("Checking if the board is in stalemate");
@@ -746,29 +1233,117 @@ impl Board {
             return false;
         }
 
-        // Check if the player can move out of check
-        for tile in Tile::all() {
-            if let Some(piece) = self.get_piece(tile) {
-                if piece.get_color() == self.current_turn {
-                    for to in tile.get_moves(piece) {
-                        if self.is_legal_piece_move(tile, to) {
-                            info!("The board is not in stalemate because {:?} can move from {:?} to {:?} to get out of check", self.current_turn, tile, to);
-                            return false;
-                        }
-                    }
-                }
-            }
+        // Check if the player can make any move
+        if self.has_any_legal_move(self.current_turn) {
+            info!("The board is not in stalemate because {:?} can move", self.current_turn);
+            return false;
         }
 
         info!("The board is in stalemate");
         true
     }
 
+    /// Call `visit` with every legal piece move for the given color, until it returns false.
+    /// Castling is visited as the king's tile and the rook's tile.
+    /// This walks the bitboards directly, so it doesn't allocate.
+    fn visit_legal_piece_moves(&self, color: Color, mut visit: impl FnMut(&Self, Tile, Tile) -> bool) {
+        let mut board = *self;
+        board.current_turn = color;
+
+        let mut pieces = board.get_color_bits(color);
+        while pieces != 0 {
+            let bit = pieces & pieces.wrapping_neg();
+            pieces ^= bit;
+            let from = Tile::from_bit(bit);
+            let piece = match board.get_piece(from) {
+                Some(piece) => piece,
+                None => continue,
+            };
+
+            let mut targets = from.candidate_move_bits(piece);
+            while targets != 0 {
+                let target_bit = targets & targets.wrapping_neg();
+                targets ^= target_bit;
+                let to = Tile::from_bit(target_bit);
+                if board.is_legal_piece_move_in_place(from, to) && !visit(&board, from, to) {
+                    return;
+                }
+            }
+        }
+
+        let king = Tile::king_start_position(color);
+        for side in [CastlingSide::King, CastlingSide::Queen] {
+            let rook = Tile::rook_start_position(color, side);
+            if board.can_castle(king, rook) && !visit(&board, king, rook) {
+                return;
+            }
+        }
+    }
+
+    /// Does the given color have any legal piece move?
+    pub fn has_any_legal_move(&self, color: Color) -> bool {
+        let mut result = false;
+        self.visit_legal_piece_moves(color, |_, _, _| {
+            result = true;
+            false
+        });
+        result
+    }
+
+    /// Count the legal piece moves for the given color, without allocating them.
+    /// Each promotion choice counts as a separate move, like in `Move::legal_moves`.
+    pub fn count_legal_moves(&self, color: Color) -> usize {
+        let mut result = 0;
+        self.visit_legal_piece_moves(color, |board, from, to| {
+            result += if board.is_valid_promotion(from, to) {
+                PieceType::PROMOTIONS.len()
+            } else {
+                1
+            };
+            true
+        });
+        result
+    }
+
+    /// Would playing the given move checkmate the opponent?
+    pub fn gives_checkmate(&self, player_move: &Move) -> bool {
+        let color = self.current_turn;
+        let mut copy = *self;
+        if !copy.is_legal_move(player_move) || copy.apply(player_move.clone()).is_err() {
+            return false;
+        }
+        copy.is_in_checkmate(!color)
+    }
+
+    /// Get all the moves for the given color that checkmate the opponent immediately.
+    pub fn checkmate_in_one_moves(&self, color: Color) -> Vec<Move> {
+        let mut board = *self;
+        board.set_turn(color);
+        Move::legal_moves(&board)
+            .into_iter()
+            .filter(|player_move| board.gives_checkmate(player_move))
+            .collect()
+    }
+
+    /// Get the legal board moves for the player whose turn it is.
+    /// This is the same as `Move::legal_moves`, and doesn't include purchases.
+    pub fn legal_moves(&self) -> Vec<Move> {
+        Move::legal_moves(self)
+    }
+
     /// Is a move legal? This will return if the move can be played.
     /// 
     /// This is the public interface used to check if a move can be applied to the board.
+    /// The board knows nothing about the market, so it allows purchases and deployments
+    /// onto any empty tile. The sectors a player may buy in are checked by
+    /// `StateCapitalistBoard::is_legal_move`, which should be used to play real games.
     pub fn is_legal_move(&self, player_move: &Move) -> bool {
         trace!("Checking if move {:?} is legal for player {:?}", player_move, self.whose_turn());
+        // No moves can be played once the game is over
+        if self.is_finished() {
+            return false;
+        }
+
         // Check if the move is a castling move
         match player_move {
             Move::Castling(side) => {
@@ -784,8 +1359,8 @@ impl Board {
                 self.is_legal_piece_move(*from, *to)
             }
 
-            Move::PieceTo { piece, to, .. } => {
-                if let Some(from) = self.get_eligible_piece(*piece, *to) {
+            Move::PieceTo { piece, hint, to, .. } => {
+                if let Some(from) = self.get_eligible_piece(*piece, *hint, *to) {
                     // Get the eligible piece
                     trace!("Eligible piece found for {:?} at {}", piece, from);
                     self.is_legal_piece_move(from, *to)
@@ -798,6 +1373,7 @@ impl Board {
             // Assume that all purchase moves are illegal
             Move::Pass => false,
             Move::Resign => true,
+            Move::ClaimDraw => self.is_fifty_move_draw(),
 
             Move::Many(moves) if !moves.is_empty() => {
                 let mut copy = self.clone();
@@ -820,27 +1396,106 @@ impl Board {
                 true
             }
             Move::Many(_) => false,
-            Move::Purchase { to, .. } => {
-                !self.has_piece_on(*to) && !self.is_in_check(self.whose_turn())
+            // Reserving a piece leaves the board as it is, so it can't get a player out of check
+            Move::Reserve(_) => !self.is_in_check(self.whose_turn()),
+            Move::Purchase { piece, to } | Move::Deploy { piece, to } => {
+                // The sectors a piece may be purchased in depend on the market,
+                // like whether controlled sectors count, so they are checked by the economy layer.
+                !self.has_piece_on(*to)
+                    && !self.is_in_check(self.whose_turn())
+                    && !self.is_in_check_after_purchase(self.whose_turn(), *piece, *to)
             }
         }
     }
 
     /// Would the player be in check after moving a piece from one tile to another?
-    fn is_in_check_after_move(&self, color: Color, from: Tile, to: Tile) -> bool {
-        // Move the piece
+    /// The move is made and taken back on the board itself, so it's left as it was.
+    fn is_in_check_after_move(&mut self, color: Color, from: Tile, to: Tile) -> bool {
+        let Some(undo) = self.make_move(from, to) else {
+            return self.is_in_check(color);
+        };
+        let is_in_check = self.is_in_check(color);
+        self.unmake_move(undo);
+        is_in_check
+    }
+
+    /// Move a piece from one tile to another, capturing whatever it lands on,
+    /// and return what's needed to take the move back with `unmake_move`.
+    /// Pawns capturing en passant also remove the pawn they pass.
+    /// 
+    /// Unlike `apply`, this doesn't check the move is legal, castle, promote,
+    /// or change whose turn it is. It's meant for trying out moves, like
+    /// checking whether a king would be left in check.
+    /// This returns None, and leaves the board alone, if there's no piece on `from`.
+    pub fn make_move(&mut self, from: Tile, to: Tile) -> Option<MoveUndo> {
+        let moved = self.get_piece(from)?;
+        let captured = if self.is_en_passant_capture(from, to) {
+            let tile = to.advance(moved.get_color(), -1);
+            self.get_piece(tile).map(|piece| (tile, piece))
+        } else {
+            self.get_piece(to).map(|piece| (to, piece))
+        };
+        let undo = MoveUndo {
+            from,
+            to,
+            captured,
+            en_passant: self.en_passant,
+        };
+
+        if let Some((tile, _)) = captured {
+            self.remove_piece(tile);
+        }
+        self.move_piece(from, to);
+        self.en_passant = None;
+        Some(undo)
+    }
+
+    /// Take back a move made with `make_move`, restoring any captured piece.
+    /// Moves must be taken back in the opposite order they were made.
+    pub fn unmake_move(&mut self, undo: MoveUndo) {
+        self.move_piece(undo.to, undo.from);
+        if let Some((tile, piece)) = undo.captured {
+            self.put_piece(tile, piece);
+        }
+        self.en_passant = undo.en_passant;
+    }
+
+    /// Place a piece of either color on a tile.
+    fn put_piece(&mut self, tile: Tile, piece: Piece) {
+        let turn = self.current_turn;
+        self.current_turn = piece.get_color();
+        self.spawn(piece.get_type(), tile);
+        self.current_turn = turn;
+    }
+
+    /// Would the player be in check after purchasing a piece onto a tile?
+    /// Adding a friendly piece can only block enemy attacks, so this can only
+    /// happen when purchasing a king onto an attacked tile.
+    pub(crate) fn is_in_check_after_purchase(&self, color: Color, piece: PieceType, to: Tile) -> bool {
+        if piece != PieceType::King {
+            return false;
+        }
         let mut copy = *self;
-        copy.move_piece(from, to);
-        copy.current_turn = self.current_turn;
+        copy.current_turn = color;
+        copy.spawn(piece, to);
         copy.is_in_check(color)
     }
 
-    /// Is a piece move legal? This is a private interface used to check internally
-    /// if a move of a piece from one tile to another is legal.
+    /// Is a piece move legal? This checks if a move of a piece from one tile
+    /// to another is legal.
     /// 
     /// Castling is encoded by passing the king's tile as the `from` tile, and the
-    /// rook's tile as the `to` tile.
+    /// rook's tile as the `to` tile, not the king's destination. Prefer
+    /// `Move::Castling` or `try_castle`, which can't mix up the two.
     pub fn is_legal_piece_move(&self, from: Tile, to: Tile) -> bool {
+        let mut board = *self;
+        board.is_legal_piece_move_in_place(from, to)
+    }
+
+    /// Is a piece move legal? Like `is_legal_piece_move`, but this tries the
+    /// move on the board itself and takes it back, so checking many moves
+    /// doesn't copy the board for each one. The board is left as it was.
+    pub(crate) fn is_legal_piece_move_in_place(&mut self, from: Tile, to: Tile) -> bool {
         info!("Checking if piece move from {from} to {to} is legal");
         if INSERT_SANITY_CHECKS {
             if self.sanity_check().is_err() {
@@ -857,9 +1512,12 @@ impl Board {
             (Some(src_piece), Some(dst_piece)) => {
                 // Check if the piece is moving to a square occupied by a friendly piece
                 if src_piece.get_color() == dst_piece.get_color() {
-                    // Check if is castling
-                    if self.is_castling_move(from, to) && self.can_castle(from, to) {
-                        // Can we castle?
+                    // Only a king moving onto its own rook is legal, since that's how castling
+                    // is encoded. `can_castle` already checks that this is a castling move.
+                    if src_piece.get_type() == PieceType::King
+                        && src_piece.get_color() == self.current_turn
+                        && self.can_castle(from, to)
+                    {
                         return true;
                     }
                     debug!("Piece {:?} is moving to a square occupied by a friendly piece", src_piece);
@@ -940,7 +1598,7 @@ impl Board {
     }
     
     /// Is this a pawn capturing to the en passant tile?
-    fn is_en_passant_capture(&self, from: Tile, to: Tile) -> bool {
+    pub(crate) fn is_en_passant_capture(&self, from: Tile, to: Tile) -> bool {
         // Check if the move is an en passant (only possibility since no capture)
         if let Some(en_passant) = self.en_passant {
             // If the move is an en passant, then the destination must be the en passant tile
@@ -984,8 +1642,9 @@ impl Board {
         // Check if the move is a pawn double move
         if let Some(piece) = self.get_piece(from) {
             if piece.get_type() == PieceType::Pawn {
-                if from.get_rank() == Rank::PAWN_STARTER_WHITE || from.get_rank() == Rank::PAWN_STARTER_BLACK {
-                    if to.get_rank() == Rank::PAWN_STARTER_WHITE + 2 || to.get_rank() == Rank::PAWN_STARTER_BLACK - 2 {
+                let start = Rank::pawn_start(piece.get_color());
+                if from.get_rank() == start {
+                    if to.get_rank() == start.advance(piece.get_color(), 2) {
                         info!("Possible next-turn-en-passant detected, marking the tile behind the pawn as capturable");
                         // Set the en passant tile
                         self.en_passant = Some(from.advance(self.current_turn, 1));
@@ -998,7 +1657,31 @@ impl Board {
     }
 
     /// Perform a move on the board.
+    /// If the move checkmates the opponent, the mover is marked as the winner,
+    /// and no more moves can be applied once the game has a winner or is drawn.
     pub fn apply(&mut self, player_move: Move) -> Result<(), ()> {
+        self.apply_without_mate_check(player_move)?;
+
+        // A player with no way out of check has lost
+        if self.winner.is_none() && self.is_in_checkmate(self.current_turn) {
+            self.set_winner(!self.current_turn);
+        }
+        Ok(())
+    }
+
+    /// Perform a move on the board, without looking for checkmate afterwards.
+    /// The economy decides checkmate for itself, since a player can buy their
+    /// way out of check, so it skips the board's search for a way out.
+    pub(crate) fn apply_without_mate_check(&mut self, player_move: Move) -> Result<(), ()> {
+        if self.is_finished() {
+            warn!("Cannot apply {player_move:?} after the game is over");
+            return Err(());
+        }
+        self.perform_move(player_move)
+    }
+
+    /// Perform a move on the board, without checking if it ends the game.
+    fn perform_move(&mut self, player_move: Move) -> Result<(), ()> {
         info!("Applying move {:?}", player_move);
         if INSERT_SANITY_CHECKS {
             assert!(self.sanity_check().is_ok());
@@ -1007,8 +1690,8 @@ impl Board {
             Move::FromTo { from, to, promotion } => {
                 self.perform_move_from_to(from, to, promotion)
             }
-            Move::PieceTo { piece, to, promotion } => {
-                let from = self.get_eligible_piece(piece, to).ok_or(())?;
+            Move::PieceTo { piece, hint, to, promotion } => {
+                let from = self.get_eligible_piece(piece, hint, to).ok_or(())?;
                 self.perform_move_from_to(from, to, promotion)
             }
             Move::Castling(side) => {
@@ -1018,10 +1701,14 @@ impl Board {
             }
             Move::Many(moves) if !moves.is_empty() => {
                 let turn = self.current_turn;
+                let plies = self.plies_since_progress;
+                let mut is_progress = false;
                 for player_move in moves {
                     self.current_turn = turn;
-                    self.apply(player_move)?
+                    self.perform_move(player_move)?;
+                    is_progress |= self.plies_since_progress == 0;
                 }
+                self.count_turn(plies, is_progress);
                 self.current_turn = !turn;
                 Ok(())
             }
@@ -1033,43 +1720,146 @@ impl Board {
                 self.current_turn = !self.current_turn;
                 Ok(())
             }
+            Move::ClaimDraw => {
+                if !self.is_legal_move(&player_move) {
+                    return Err(());
+                }
+                self.drawn = true;
+                self.current_turn = !self.current_turn;
+                Ok(())
+            }
             Move::Pass => {
+                self.count_ply(false);
                 self.current_turn = !self.current_turn;
                 Ok(())
             }
-            Move::Purchase { piece, to } => {
+            Move::Reserve(_) => {
+                if !self.is_legal_move(&player_move) {
+                    return Err(());
+                }
+                self.count_ply(false);
+                self.current_turn = !self.current_turn;
+                Ok(())
+            }
+            Move::Purchase { piece, to } | Move::Deploy { piece, to } => {
+                if !self.is_legal_move(&player_move) {
+                    return Err(());
+                }
                 self.spawn(piece, to);
+                self.count_ply(true);
+                if INSERT_SANITY_CHECKS {
+                    // A legal purchase can never leave the buyer in check.
+                    assert!(!self.is_in_check(self.current_turn));
+                }
                 self.current_turn = !self.current_turn;
                 Ok(())
             }
         }
     }
 
+    /// Apply the move to the board, and describe what it did.
+    /// For a `Many` move, the outcome describes the whole turn: every piece
+    /// it captured, and whether any of its moves castled or promoted.
+    pub fn apply_detailed(&mut self, player_move: Move) -> Result<MoveOutcome, MoveError> {
+        self.apply_detailed_with(player_move, Self::apply)
+    }
+
+    /// Apply the move to the board and describe what it did, without looking for checkmate afterwards.
+    pub(crate) fn apply_detailed_without_mate_check(&mut self, player_move: Move) -> Result<MoveOutcome, MoveError> {
+        self.apply_detailed_with(player_move, Self::apply_without_mate_check)
+    }
+
+    /// Apply the move to the board with the given function, and describe what it did.
+    fn apply_detailed_with(&mut self, player_move: Move, apply: fn(&mut Self, Move) -> Result<(), ()>) -> Result<MoveOutcome, MoveError> {
+        let mover = self.current_turn;
+        let player_move = player_move.normalize(self);
+        if let Move::PieceTo { .. } = player_move {
+            return Err(MoveError::NoEligiblePiece);
+        }
+
+        let before = *self;
+        apply(self, player_move.clone()).map_err(|_| MoveError::Illegal)?;
+
+        let moves = match &player_move {
+            Move::Many(moves) => moves.as_slice(),
+            player_move => core::slice::from_ref(player_move),
+        };
+        let was_castle = moves.iter().any(|player_move| matches!(player_move, Move::Castling(_)));
+        // A pawn that left its tile and became another piece was promoted
+        let promotion = moves.iter().find_map(|player_move| match player_move {
+            Move::FromTo { from, to, .. } if before.get_piece(*from).is_some_and(|piece| piece.get_type() == PieceType::Pawn) => {
+                self.get_piece(*to)
+                    .filter(|piece| piece.get_color() == mover && piece.get_type() != PieceType::Pawn)
+                    .map(|piece| piece.get_type())
+            },
+            _ => None,
+        });
+        let captured = captured_pieces(&before, self, mover)
+            .into_iter()
+            .map(|(_, piece)| piece)
+            .collect();
+
+        Ok(MoveOutcome {
+            captured,
+            was_castle,
+            promotion,
+            gave_check: self.is_in_check(!mover),
+        })
+    }
+
+    /// Castle the current player's king on the given side.
+    /// This returns an error if the castling rights are gone, or if the
+    /// castling move is otherwise illegal.
+    pub fn try_castle(&mut self, side: CastlingSide) -> Result<(), MoveError> {
+        let king = Tile::king_start_position(self.current_turn);
+        let rook = Tile::rook_start_position(self.current_turn, side);
+        if !self.can_castle(king, rook) {
+            return Err(MoveError::CannotCastle);
+        }
+        self.apply(Move::Castling(side)).map_err(|_| MoveError::Illegal)
+    }
+
+    /// Get the winner of the game, if a player has resigned or been checkmated.
+    pub fn get_winner(&self) -> Option<Color> {
+        self.winner
+    }
+
     /// Mark a given player as the winner.
-    fn set_winner(&mut self, winner: Color) {
+    pub(crate) fn set_winner(&mut self, winner: Color) {
         info!("Setting winner to {:?}", winner);
         self.winner = Some(winner);
     }
 
     /// Given a move of a piece type and a destination tile, return the eligible
-    /// piece that can move to the destination tile. Return None if no piece can
+    /// piece that can move to the destination tile. Only pieces on the hinted
+    /// file and rank are considered. Return None if no piece can
     /// move to the destination tile, or if there are multiple pieces that can
     /// move to the destination tile.
-    fn get_eligible_piece(&self, piece: PieceType, to: Tile) -> Option<Tile> {
-        info!("Getting eligible piece of type {:?} to move to {:?}", piece, to);
+    pub(crate) fn get_eligible_piece(&self, piece: PieceType, hint: Hint, to: Tile) -> Option<Tile> {
+        info!("Getting eligible piece of type {:?} on {:?} to move to {:?}", piece, hint, to);
         let is_attack = self.has_piece_on(to);
+        let mut result = None;
         for tile in Tile::all() {
+            if !hint.matches(tile) {
+                continue;
+            }
             if let Some(src_piece) = self.get_piece(tile) {
                 if src_piece.get_type() == piece && src_piece.get_color() == self.current_turn {
-                    if src_piece.can_move(tile, to, is_attack, self.en_passant) {
+                    if src_piece.can_move(tile, to, is_attack, self.en_passant) && self.is_legal_piece_move(tile, to) {
+                        if result.is_some() {
+                            warn!("More than one piece can move to {to}, so the move needs a hint");
+                            return None;
+                        }
                         info!("Found eligible piece at {:?}", tile);
-                        return Some(tile);
+                        result = Some(tile);
                     }
                 }
             }
         }
-        warn!("No eligible piece found");
-        None
+        if result.is_none() {
+            warn!("No eligible piece found");
+        }
+        result
     }
 
     /// Perform castling with a given king and rook tile.
@@ -1083,8 +1873,8 @@ impl Board {
             return;
         }
 
-        // Remove the castling rights
-        self.castling_rights.disable_castling(king_tile, rook_tile);
+        // Castling uses up the king's move, so both sides lose their rights
+        self.castling_rights.disable_castling_color(self.current_turn);
         let side = rook_tile.get_castling_side();
 
         // Move the king to the castling tile
@@ -1108,6 +1898,16 @@ impl Board {
             return;
         }
 
+        // Capturing a rook on its starting tile also takes away its castling rights
+        for color in [Color::White, Color::Black] {
+            for side in [CastlingSide::King, CastlingSide::Queen] {
+                if to == Tile::rook_start_position(color, side) {
+                    info!("{color:?} {side:?} rook's tile was captured, disabling its castling rights");
+                    self.castling_rights.disable_castling_color_and_side(color, side);
+                }
+            }
+        }
+
         // Check if the move is a rook move
         if from == Tile::WHITE_KINGSIDE_ROOK_START || from == Tile::WHITE_QUEENSIDE_ROOK_START {
             // Check if the move is a kingside rook move
@@ -1155,12 +1955,20 @@ impl Board {
     /// it will perform the validation and the move, and change
     /// the state accordingly.
     fn perform_move_from_to(&mut self, from: Tile, to: Tile, promotion: Option<PieceType>) -> Result<(), ()> {
+        // Only the player whose turn it is can move their pieces
+        if self.get_piece(from).ok_or(())?.get_color() != self.current_turn {
+            debug!("Tried to move the piece on {from} out of turn");
+            return Err(())
+        }
+
         if !self.is_legal_piece_move(from, to) {
             // debug!("Tried to perform illegal move from {from:?} to {to:?}");
             return Err(())
         }
-        
-        self.current_turn = self.get_piece(from).ok_or(())?.get_color();
+
+        // Captures and pawn moves are progress, but castling onto the rook isn't a capture
+        let is_pawn_move = self.get_piece(from).map(|piece| piece.get_type()) == Some(PieceType::Pawn);
+        self.count_ply(!self.is_castling_move(from, to) && (is_pawn_move || self.has_piece_on(to)));
         
         // Check if the move is a castling
         if self.is_castling_move(from, to) {
@@ -1233,13 +2041,7 @@ impl Board {
         // Check that the piece is a pawn
         if let Some(piece) = self.get_piece(from) {
             if piece.get_type() == PieceType::Pawn {
-                if to.get_rank() == Rank::BACK_RANK_BLACK && piece.get_color() == Color::White {
-                    return true;
-                }
-
-                if to.get_rank() == Rank::BACK_RANK_WHITE && piece.get_color() == Color::Black {
-                    return true;
-                }
+                return to.get_rank() == Rank::promotion_rank(piece.get_color());
             }
         }
 
@@ -1498,40 +2300,160 @@ impl Board {
         self.black_king.count_ones() as u64
     }
 
-    /// Returns the total value of the white pieces on the board
-    /// The value of a piece is as follows:
-    /// - Pawn: 1
-    /// - Knight: 3
-    /// - Bishop: 3
-    /// - Rook: 5
-    /// - Queen: 9
-    /// - King: 1000
+    /// Returns the total market value of the given color's pieces on the board.
+    pub fn material(&self, color: Color, market: &Market) -> Currency {
+        let mut result = Currency::zero();
+        for piece_type in PieceType::ALL {
+            result += market.get_piece_value(piece_type) * self.get_piece_bits(Piece::new(piece_type, color)).count_ones();
+        }
+        result
+    }
+
+    /// The bitboard of all the tiles on the given file.
     #[inline]
-    pub fn total_white_piece_value(&self) -> u64 {
-        (self.white_pawn_count() as f64 * PieceType::Pawn.get_value()
-            + self.white_knight_count() as f64 * PieceType::Knight.get_value()
-            + self.white_bishop_count() as f64 * PieceType::Bishop.get_value()
-            + self.white_rook_count() as f64 * PieceType::Rook.get_value()
-            + self.white_queen_count() as f64 * PieceType::Queen.get_value()
-            + self.white_king_count() as f64 * PieceType::King.get_value()) as u64
-    }
-
-    /// Returns the total value of the black pieces on the board
-    /// The value of a piece is as follows:
-    /// - Pawn: 1
-    /// - Knight: 3
-    /// - Bishop: 3
-    /// - Rook: 5
-    /// - Queen: 9
-    /// - King: 100
+    fn file_bits(file: u8) -> u64 {
+        (0..Self::SIZE).fold(0, |bits, rank| bits | 1 << (rank * Self::SIZE + file))
+    }
+
+    /// The bitboard of the given file and the files beside it.
     #[inline]
-    pub fn total_black_piece_value(&self) -> u64 {
-        (self.black_pawn_count() as f64 * PieceType::Pawn.get_value()
-            + self.black_knight_count() as f64 * PieceType::Knight.get_value()
-            + self.black_bishop_count() as f64 * PieceType::Bishop.get_value()
-            + self.black_rook_count() as f64 * PieceType::Rook.get_value()
-            + self.black_queen_count() as f64 * PieceType::Queen.get_value()
-            + self.black_king_count() as f64 * PieceType::King.get_value()) as u64
+    fn adjacent_file_bits(file: u8) -> u64 {
+        let mut bits = 0;
+        if file > 0 {
+            bits |= Self::file_bits(file - 1);
+        }
+        if file + 1 < Self::SIZE {
+            bits |= Self::file_bits(file + 1);
+        }
+        bits
+    }
+
+    /// Are there no pawns of either color on the given file?
+    pub fn file_is_open(&self, file: File) -> bool {
+        (self.white_pawns | self.black_pawns) & Self::file_bits(file.get_index()) == 0
+    }
+
+    /// Does the given color have no pawns on the given file, while the enemy does?
+    pub fn file_is_half_open(&self, file: File, color: Color) -> bool {
+        let file_bits = Self::file_bits(file.get_index());
+        let pawns = self.get_piece_bits(Piece::new(PieceType::Pawn, color));
+        let enemy_pawns = self.get_piece_bits(Piece::new(PieceType::Pawn, !color));
+        pawns & file_bits == 0 && enemy_pawns & file_bits != 0
+    }
+
+    /// Get the tiles of all the pieces on the given rank.
+    pub fn pieces_on_rank(&self, rank: Rank) -> TileSet {
+        TileSet(self.all_pieces_as_bits() & (0xff << (rank.get_index() * Self::SIZE)))
+    }
+
+    /// Get the pawns of the given color that share their file with another friendly pawn.
+    pub fn doubled_pawns(&self, color: Color) -> TileSet {
+        let pawns = self.get_piece_bits(Piece::new(PieceType::Pawn, color));
+        let mut result = 0;
+        for file in 0..Self::SIZE {
+            let pawns_on_file = pawns & Self::file_bits(file);
+            if pawns_on_file.count_ones() > 1 {
+                result |= pawns_on_file;
+            }
+        }
+        TileSet(result)
+    }
+
+    /// Get the pawns of the given color with no friendly pawns on the files beside them.
+    pub fn isolated_pawns(&self, color: Color) -> TileSet {
+        let pawns = self.get_piece_bits(Piece::new(PieceType::Pawn, color));
+        let mut result = 0;
+        for file in 0..Self::SIZE {
+            if pawns & Self::adjacent_file_bits(file) == 0 {
+                result |= pawns & Self::file_bits(file);
+            }
+        }
+        TileSet(result)
+    }
+
+    /// Get the pawns of the given color with no enemy pawns in front of them,
+    /// on their own file or the files beside them.
+    pub fn passed_pawns(&self, color: Color) -> TileSet {
+        let pawns = self.get_piece_bits(Piece::new(PieceType::Pawn, color));
+        let enemy_pawns = self.get_piece_bits(Piece::new(PieceType::Pawn, !color));
+        let mut result = TileSet::default();
+        for tile in TileSet(pawns).into_iter() {
+            let file = tile.get_file().get_index();
+            let files = Self::file_bits(file) | Self::adjacent_file_bits(file);
+            let rank = tile.get_rank().get_index();
+            let blockers = (0..Self::SIZE)
+                .filter(|other| match color {
+                    Color::White => *other > rank,
+                    Color::Black => *other < rank,
+                })
+                .fold(0, |bits, other| bits | (files & (0xff << (other * Self::SIZE))));
+            if enemy_pawns & blockers == 0 {
+                result.insert(tile);
+            }
+        }
+        result
+    }
+
+    /// Score how well the winning color is mopping up a lone enemy king.
+    /// This rewards driving the losing king toward the edge and corners,
+    /// and bringing the winning king close to it to help deliver mate.
+    pub fn mop_up_score(&self, winning: Color) -> f64 {
+        let (Some(winning_king), Some(losing_king)) = (self.king_tile(winning), self.king_tile(!winning)) else {
+            return 0.0;
+        };
+
+        // The farthest two tiles can be apart in rook steps
+        let max_distance = (Board::SIZE - 1) * 2;
+        4.7 * losing_king.center_distance() as f64
+            + 1.6 * (max_distance - winning_king.manhattan_distance(losing_king)) as f64
+    }
+
+    /// Get the key of the playable position on the board.
+    /// This covers the pieces, whose turn it is, the castling rights, and the
+    /// en passant tile if a pawn can actually capture there, but not the result
+    /// or the history of the game, so repeated positions always share a key.
+    pub fn position_key(&self) -> PositionKey {
+        let mut pieces = [0; 12];
+        for (bits, piece) in pieces.iter_mut().zip(Self::piece_order()) {
+            *bits = self.get_piece_bits(piece);
+        }
+
+        // An en passant tile no pawn can capture on doesn't change the position
+        let en_passant = self.en_passant.filter(|en_passant| {
+            TileSet(self.get_piece_bits(Piece::new(PieceType::Pawn, self.current_turn)))
+                .into_iter()
+                .any(|from| self.is_legal_piece_move(from, *en_passant))
+        });
+
+        PositionKey {
+            pieces,
+            turn: self.current_turn,
+            castling_rights: self.castling_rights,
+            en_passant,
+        }
+    }
+
+    /// Every piece, in the order their bitboards appear in a position key.
+    fn piece_order() -> impl Iterator<Item = Piece> {
+        [Color::White, Color::Black].into_iter()
+            .flat_map(|color| PieceType::ALL.into_iter().map(move |piece_type| Piece::new(piece_type, color)))
+    }
+
+    /// Get the Zobrist hash of the position.
+    /// This is the hash of the position's key, so it doesn't cover the winner either.
+    pub fn zobrist_hash(&self) -> u64 {
+        self.position_key().zobrist_hash()
+    }
+
+    /// Get a lightweight view of the material on the board.
+    pub fn material_view(&self) -> MaterialView {
+        let mut result = MaterialView::default();
+        for color in [Color::White, Color::Black] {
+            for piece_type in PieceType::ALL {
+                result.set_count(Piece::new(piece_type, color), self.get_piece_bits(Piece::new(piece_type, color)).count_ones());
+            }
+        }
+        result
     }
 
     #[inline]
@@ -1560,7 +2482,7 @@ impl Board {
     pub fn next_white_piece(&self, last: Tile) -> Tile {
         // Perform a bit scan on the bit-board to find the next white piece
         let mut white_pieces = self.white_pieces_as_bits();
-        white_pieces &= !(1 << (last.get_rank().get_index() * 8 + last.get_file().get_index()) - 1);
+        white_pieces &= !(1 << ((last.get_rank().get_index() * Self::SIZE + last.get_file().get_index()) - 1));
         Tile::from_bit(white_pieces)
     }
 
@@ -1568,13 +2490,68 @@ impl Board {
     pub fn next_black_piece(&self, last: Tile) -> Tile {
         // Perform a bit scan on the bit-board to find the next black piece
         let mut black_pieces = self.black_pieces_as_bits();
-        black_pieces &= !(1 << (last.get_rank().get_index() * 8 + last.get_file().get_index()) - 1);
+        black_pieces &= !(1 << ((last.get_rank().get_index() * Self::SIZE + last.get_file().get_index()) - 1));
         Tile::from_bit(black_pieces)
     }
 }
 
-impl Display for Board {
-    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+/// The material on a board, without any of the positions of the pieces.
+///
+/// This is all an evaluation needs to count material, and it is much cheaper
+/// to inspect than scanning the board's tiles.
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct MaterialView {
+    /// The number of white pieces of each type.
+    white: [u32; PieceType::ALL.len()],
+    /// The number of black pieces of each type.
+    black: [u32; PieceType::ALL.len()],
+}
+
+impl MaterialView {
+    /// Get the number of the given piece on the board.
+    #[inline]
+    pub fn count(&self, piece: Piece) -> u32 {
+        match piece.get_color() {
+            Color::White => self.white[piece.get_type() as usize],
+            Color::Black => self.black[piece.get_type() as usize],
+        }
+    }
+
+    /// Set the number of the given piece on the board.
+    #[inline]
+    fn set_count(&mut self, piece: Piece, count: u32) {
+        match piece.get_color() {
+            Color::White => self.white[piece.get_type() as usize] = count,
+            Color::Black => self.black[piece.get_type() as usize] = count,
+        }
+    }
+
+    /// Does the given color have a lone king against a queen or rook,
+    /// so that the other side can force checkmate?
+    pub fn is_lone_king_against_major(&self, color: Color) -> bool {
+        let lone_king = PieceType::ALL
+            .into_iter()
+            .filter(|piece_type| *piece_type != PieceType::King)
+            .all(|piece_type| self.count(Piece::new(piece_type, color)) == 0);
+        lone_king
+            && (self.count(Piece::new(PieceType::Queen, !color)) > 0
+                || self.count(Piece::new(PieceType::Rook, !color)) > 0)
+    }
+
+    /// Get the total market value of the given color's pieces.
+    pub fn market_value(&self, color: Color, market: &Market) -> Currency {
+        let mut result = Currency::zero();
+        for piece_type in PieceType::ALL {
+            result += market.get_piece_value(piece_type) * self.count(Piece::new(piece_type, color));
+        }
+        result
+    }
+}
+
+impl Board {
+    /// Write the board with colored tiles. The sectors controlled by the
+    /// player who just moved, priced by the market, are shaded in different colors.
+    pub(crate) fn write_colored(&self, f: &mut Formatter<'_>, market: &Market) -> FmtResult {
         // Magenta
         let primary_color = "\x1b[0;45m";
         // Cyan
@@ -1584,18 +2561,19 @@ impl Display for Board {
         // Blue
         let alt_secondary_color = "\x1b[0;44m";
         write!(f, " ")?;
-        for file in 0..8 {
+        for file in 0..Board::SIZE {
             write!(f, " {}", File::from_index(file))?;
         }
         write!(f, "\n")?;
-        for rank in (0..8).rev() {
+        for rank in (0..Board::SIZE).rev() {
             // Store the pieces in the rank in the result.
             write!(f, "{} ", rank + 1)?;
-            for file in 0..8 {
+            for file in 0..Board::SIZE {
                 // is en-passant square?
                 let tile = Tile::new(Rank::from_index(rank), File::from_index(file));
 
-                let (primary, secondary) = if self.controls_sector(tile.get_sector(), !self.current_turn) {
+                let controlled = market.who_controls_sector(self, tile.get_sector()) == Some(!self.current_turn);
+                let (primary, secondary) = if controlled {
                     (alt_primary_color, alt_secondary_color)
                 } else {
                     (primary_color, secondary_color)
@@ -1634,7 +2612,7 @@ impl Display for Board {
             write!(f, " {}\n", rank + 1)?;
         }
         write!(f, " ")?;
-        for file in 0..8 {
+        for file in 0..Board::SIZE {
             write!(f, " {}", File::from_index(file))?;
         }
         write!(f, "\n")?;
@@ -1642,26 +2620,184 @@ impl Display for Board {
     }
 }
 
-// pub(crate) fn display_bitboard(bitboard: u64) -> String {
-//     let mut result = String::new();
-//     for rank in (0..8).rev() {
-//         // Store the pieces in the rank in the result.
-//         for file in 0..8 {
-//             let location = Tile::new(Rank::from_index(rank), File::from_index(file));
-//             if (bitboard & location.to_bit()) != 0 {
-//                 result.push('1');
-//             } else {
-//                 result.push('0');
-//             }
-//         }
-//         result.push('\n');
-//     }
-//     result
-// }
+impl Display for Board {
+    /// The board alone doesn't know the game's prices, so sector control is
+    /// shaded with the default market's.
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        self.write_colored(f, &Market::default())
+    }
+}
+
+/// Format a bitboard as eight lines of ones and zeros, with the eighth rank first.
+/// This is useful for debugging attack generation.
+pub fn format_bitboard(bitboard: u64) -> String {
+    let mut result = String::new();
+    for rank in (0..Board::SIZE).rev() {
+        // Store the tiles in the rank in the result.
+        for file in 0..Board::SIZE {
+            let location = Tile::new(Rank::from_index(rank), File::from_index(file));
+            if (bitboard & location.to_bit()) != 0 {
+                result.push('1');
+            } else {
+                result.push('0');
+            }
+        }
+        result.push('\n');
+    }
+    result
+}
 
 
-/// A struct that represents the castling rights of a board
+/// A reason a FEN position couldn't be loaded.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum FenError {
+    /// The FEN doesn't have four, six, or seven fields.
+    WrongFieldCount,
+    /// The piece placement field is malformed.
+    InvalidPiecePlacement,
+    /// The active color isn't `w` or `b`.
+    InvalidActiveColor,
+    /// The castling availability field is malformed.
+    InvalidCastling,
+    /// The en passant target isn't a tile.
+    InvalidEnPassant,
+    /// The halfmove clock isn't a number.
+    InvalidHalfmoveClock,
+    /// The result isn't `1-0`, `0-1`, or `1/2-1/2`.
+    InvalidWinner,
+    /// The position breaks the rules of the board, like castling without a rook.
+    InvalidPosition,
+    /// The move at the given index couldn't be played.
+    InvalidMove(usize),
+}
+
+impl Display for FenError {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        match self {
+            Self::WrongFieldCount => write!(f, "the FEN must have four, six, or seven fields"),
+            Self::InvalidPiecePlacement => write!(f, "the piece placement is invalid"),
+            Self::InvalidActiveColor => write!(f, "the active color is invalid"),
+            Self::InvalidCastling => write!(f, "the castling availability is invalid"),
+            Self::InvalidEnPassant => write!(f, "the en passant target is invalid"),
+            Self::InvalidHalfmoveClock => write!(f, "the halfmove clock is invalid"),
+            Self::InvalidWinner => write!(f, "the winner is invalid"),
+            Self::InvalidPosition => write!(f, "the position is invalid"),
+            Self::InvalidMove(i) => write!(f, "move #{i} could not be played"),
+        }
+    }
+}
+
+/// Get the pieces of the mover's opponent that were on the board before a move, but not after it.
+pub(crate) fn captured_pieces(before: &Board, after: &Board, mover: Color) -> Vec<(Tile, Piece)> {
+    before.iter_pieces_of(!mover)
+        .filter(|(tile, piece)| after.get_piece(*tile) != Some(*piece))
+        .collect()
+}
+
+/// What a move did to the board.
+#[derive(Clone, Debug, PartialEq)]
+pub struct MoveOutcome {
+    /// The opponent's pieces that were captured, in the order of their tiles.
+    pub captured: Vec<Piece>,
+    /// Did the move castle?
+    pub was_castle: bool,
+    /// The piece a pawn was promoted to, if any.
+    pub promotion: Option<PieceType>,
+    /// Did the move put the opponent in check?
+    pub gave_check: bool,
+}
+
+/// A reason a move couldn't be applied to the board.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum MoveError {
+    /// No piece of the given type can move to the destination.
+    NoEligiblePiece,
+    /// The player can't castle on the given side.
+    CannotCastle,
+    /// The move breaks the rules of the board.
+    Illegal,
+}
+
+impl Display for MoveError {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        match self {
+            Self::NoEligiblePiece => write!(f, "no piece can make the move"),
+            Self::CannotCastle => write!(f, "the player cannot castle"),
+            Self::Illegal => write!(f, "the move is illegal"),
+        }
+    }
+}
+
+/// What a move made with `Board::make_move` changed,
+/// so `Board::unmake_move` can take it back.
 #[derive(Copy, Clone, Debug, PartialEq)]
+pub struct MoveUndo {
+    /// The tile the piece moved from.
+    from: Tile,
+    /// The tile the piece moved to.
+    to: Tile,
+    /// The piece that was captured, and the tile it was captured on.
+    captured: Option<(Tile, Piece)>,
+    /// The en passant tile before the move.
+    en_passant: Option<Tile>,
+}
+
+impl MoveUndo {
+    /// Get the piece the move captured, if any.
+    pub fn get_captured(&self) -> Option<Piece> {
+        self.captured.map(|(_, piece)| piece)
+    }
+}
+
+/// The playable state of a board, used to tell when a position repeats.
+/// Two boards with the same key have the same legal moves, whoever has won.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct PositionKey {
+    /// The bitboards of every piece, white's then black's.
+    pieces: [u64; 12],
+    /// Whose turn it is.
+    turn: Color,
+    /// Who can still castle, and on which sides.
+    castling_rights: CastlingRights,
+    /// The en passant tile, if a pawn can capture on it.
+    en_passant: Option<Tile>,
+}
+
+impl PositionKey {
+    /// Get the Zobrist hash of the position.
+    pub fn zobrist_hash(&self) -> u64 {
+        let mut result = 0;
+        // Each piece on each tile has its own key
+        for (i, bits) in self.pieces.into_iter().enumerate() {
+            for tile in TileSet(bits).into_iter() {
+                result ^= zobrist_key(i as u64 * Board::TILES as u64 + tile.index() as u64);
+            }
+        }
+
+        // The rest of the keys come after the piece keys
+        let mut index = 12 * Board::TILES as u64;
+        let mut flag = |is_set: bool| {
+            if is_set {
+                result ^= zobrist_key(index);
+            }
+            index += 1;
+        };
+        flag(self.turn == Color::Black);
+        for color in [Color::White, Color::Black] {
+            for side in [CastlingSide::King, CastlingSide::Queen] {
+                flag(self.castling_rights.has(color, side));
+            }
+        }
+        if let Some(en_passant) = self.en_passant {
+            result ^= zobrist_key(index + en_passant.index() as u64);
+        }
+        result
+    }
+}
+
+/// A struct that represents the castling rights of a board
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct CastlingRights {
     white_king_side: bool,
     white_queen_side: bool,
@@ -1708,7 +2844,12 @@ impl CastlingRights {
         }
         let color = king.get_player_side();
         let side = rook.get_castling_side();
-        self.can_castle_color_and_side(color, side)
+        self.has(color, side)
+    }
+
+    /// Returns the king side and queen side castling rights of the given color
+    pub fn for_color(&self, color: Color) -> (bool, bool) {
+        (self.has(color, CastlingSide::King), self.has(color, CastlingSide::Queen))
     }
 
     /// Disable castling for the given color
@@ -1731,7 +2872,7 @@ impl CastlingRights {
         let color = king.get_player_side();
         king == Tile::king_start_position(color)
             && (rook.is_castling_destination_for_king(color)
-            || rook.is_rook_square(color)) && self.can_castle_color_and_side(color, rook.get_castling_side())
+            || rook.is_rook_square(color)) && self.has(color, rook.get_castling_side())
     }
 
     /// Remove the given color's ability to castle on the given side
@@ -1752,8 +2893,8 @@ impl CastlingRights {
         }
     }
 
-    /// Returns true if the given color can castle on the given side
-    fn can_castle_color_and_side(&self, color: Color, side: CastlingSide) -> bool {
+    /// Returns true if the given color still has the right to castle on the given side
+    pub fn has(&self, color: Color, side: CastlingSide) -> bool {
         match color {
             Color::White => {
                 match side {
@@ -1,12 +1,14 @@
-use super::{Currency, Color, Market, Move, Board, Sector};
+use super::{Currency, Color, Market, Move, Board, Sector, PieceType};
 use log::{info, debug, error};
-use core::fmt::{Display, Formatter, Result as FmtResult};
+use core::fmt::{Display, Formatter, Result as FmtResult, Write};
+use alloc::{string::String, vec::Vec};
 
 /// Federal bank for each player.
 /// This adds an economic element to the game. Each player has a bank
 /// which gains income depending on their territory and can be used to
 /// purchase units, and extra moves.
 #[derive(Copy, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Bank {
     /// The color of the bank.
     color: Color,
@@ -16,7 +18,13 @@ pub struct Bank {
     market: Market,
 
     /// The sectors owned by the bank.
-    sectors: [bool; Sector::NUM_SECTORS]
+    sectors: [bool; Sector::NUM_SECTORS],
+
+    /// Has the bank's player captured a piece yet?
+    first_capture_done: bool,
+
+    /// How many of each piece type the bank's player has reserved, but not deployed.
+    reserve: [u8; PieceType::ALL.len()],
 }
 
 impl Bank {
@@ -44,6 +52,8 @@ impl Bank {
             balance: Currency::zero(),
             market,
             sectors,
+            first_capture_done: false,
+            reserve: [0; PieceType::ALL.len()],
         }
     }
 
@@ -54,6 +64,12 @@ impl Bank {
         self.balance >= self.market.get_move_value(player_move)
     }
 
+    /// Can this bank afford to purchase the given piece type?
+    #[inline]
+    pub fn can_afford_piece(&self, piece: PieceType) -> bool {
+        self.balance >= self.market.get_piece_value(piece)
+    }
+
     /// Add money to the bank.
     /// This will add the given amount of money to the bank's balance.
     #[inline]
@@ -81,6 +97,59 @@ impl Bank {
         self.withdraw(self.market.get_move_value(player_move))
     }
 
+    /// Collect the bounty for capturing the given piece.
+    /// The player's first capture is paid the market's first capture bonus.
+    /// This returns the amount deposited.
+    pub fn collect_bounty(&mut self, piece: PieceType) -> Currency {
+        let bounty = self.market.get_capture_bounty(piece, !self.first_capture_done);
+        self.first_capture_done = true;
+        debug!("Bank for {:?} collecting a bounty of {bounty:?} for a {piece}", self.get_color());
+        self.deposit(bounty);
+        bounty
+    }
+
+    /// Has this bank's player captured a piece yet?
+    #[inline]
+    pub fn has_captured(&self) -> bool {
+        self.first_capture_done
+    }
+
+    /// How many of the given piece type are in the reserve?
+    #[inline]
+    pub fn get_reserve_count(&self, piece: PieceType) -> u8 {
+        self.reserve[piece as usize]
+    }
+
+    /// Add a purchased piece to the reserve.
+    /// The piece must already be paid for.
+    /// This returns false if the reserve can't hold any more of the piece.
+    pub fn add_to_reserve(&mut self, piece: PieceType) -> bool {
+        let count = &mut self.reserve[piece as usize];
+        match count.checked_add(1) {
+            Some(added) => {
+                *count = added;
+                true
+            },
+            None => false,
+        }
+    }
+
+    /// Can the reserve hold another of the given piece?
+    pub fn has_reserve_room(&self, piece: PieceType) -> bool {
+        self.get_reserve_count(piece) < u8::MAX
+    }
+
+    /// Take a piece out of the reserve to deploy it.
+    /// This returns false if there are none of the piece in the reserve.
+    pub fn take_from_reserve(&mut self, piece: PieceType) -> bool {
+        let count = &mut self.reserve[piece as usize];
+        if *count == 0 {
+            return false;
+        }
+        *count -= 1;
+        true
+    }
+
     /// Get the color of the bank.
     #[inline]
     pub fn get_color(&self) -> Color {
@@ -101,24 +170,81 @@ impl Bank {
 
     /// Take a census of the board.
     /// This will check which sectors are controlled by the bank,
-    /// and update the bank's income.
+    /// and update the bank's income. Sectors the bank didn't control
+    /// at the last census also pay the sector capture bonus, once.
     pub fn perform_census(&mut self, board: &Board) {
         info!("Taking census for bank controlled by {:?}", self.get_color());
         // Count the board's sectors
-        self.sectors = board.get_controlled_sectors(self.get_color());
+        let previous = self.sectors;
+        self.sectors = board.get_controlled_sectors(self.get_color(), &self.market);
 
         // Update the bank's balance
-        self.balance += self.calculate_income();
+        self.balance += self.calculate_income(previous);
+    }
+
+    /// Write the bank's state as `key=value` lines, with keys starting with
+    /// the bank's color, like `white_balance=40`.
+    /// The market is left out, since it's shared with the rest of the game.
+    pub(crate) fn write_state(&self, result: &mut String) {
+        let prefix = match self.color {
+            Color::White => "white",
+            Color::Black => "black",
+        };
+        let sectors: String = self.sectors.iter().map(|owned| if *owned { '1' } else { '0' }).collect();
+        let reserve: Vec<String> = self.reserve.iter().map(|count| count.to_string()).collect();
+        // Writing to a string can't fail
+        let _ = writeln!(result, "{prefix}_balance={}", self.balance.get_amount());
+        let _ = writeln!(result, "{prefix}_sectors={sectors}");
+        let _ = writeln!(result, "{prefix}_has_captured={}", self.first_capture_done);
+        let _ = writeln!(result, "{prefix}_reserve={}", reserve.join(","));
+    }
+
+    /// Read a line of the bank's state, as written by `write_state`, without the color prefix.
+    /// This returns false if the key isn't part of the bank's state,
+    /// and an error if the value is invalid.
+    pub(crate) fn read_state(&mut self, key: &str, value: &str) -> Result<bool, ()> {
+        match key {
+            "balance" => self.balance = Currency::penny() * value.parse::<i32>().map_err(|_| ())?,
+            "sectors" => {
+                if value.len() != Sector::NUM_SECTORS {
+                    return Err(());
+                }
+                for (owned, c) in self.sectors.iter_mut().zip(value.chars()) {
+                    *owned = match c {
+                        '0' => false,
+                        '1' => true,
+                        _ => return Err(()),
+                    };
+                }
+            },
+            "has_captured" => self.first_capture_done = value.parse().map_err(|_| ())?,
+            "reserve" => {
+                let counts: Vec<&str> = value.split(',').collect();
+                if counts.len() != self.reserve.len() {
+                    return Err(());
+                }
+                for (count, value) in self.reserve.iter_mut().zip(counts) {
+                    *count = value.parse().map_err(|_| ())?;
+                }
+            },
+            _ => return Ok(false),
+        }
+        Ok(true)
     }
 
     /// Calculate income based on the sectors controlled by the bank.
-    fn calculate_income(&self) -> Currency {
+    /// Sectors that weren't controlled at the previous census are newly captured.
+    fn calculate_income(&self, previous: [bool; Sector::NUM_SECTORS]) -> Currency {
         let mut income = Currency::zero();
         for (i, sector) in self.sectors.iter().enumerate() {
             if !sector {
                 continue;
             }
-            let income_for_sector = self.get_market().get_sector_value(Sector::from_index(i));
+            let captured = !previous[i];
+            if captured {
+                debug!("{:?} took control of sector {:?}", self.get_color(), Sector::from_index(i));
+            }
+            let income_for_sector = self.get_market().get_sector_income(Sector::from_index(i), captured);
             debug!("Sector {:?} is controlled by {:?} and is worth {:?}", Sector::from_index(i), self.get_color(), income_for_sector);
             income += income_for_sector;
         }
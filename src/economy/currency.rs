@@ -5,6 +5,8 @@ use core::fmt::{Debug, Display, Formatter, Result as FmtResult};
 /// This is used to purchase units and extra moves.
 /// This is a signed integer, so it can represent debt.
 #[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(transparent))]
 pub struct Currency {
     /// The amount of currency.
     amount: i32,
@@ -51,6 +53,30 @@ impl Currency {
     pub fn get_amount(&self) -> i32 {
         self.amount
     }
+
+    /// Get the given percentage of this amount, rounded to the nearest penny.
+    pub fn percent(&self, pct: u32) -> Self {
+        self.fraction(pct as i32, 100).unwrap_or_default()
+    }
+
+    /// Get the given fraction of this amount, rounded to the nearest penny.
+    /// Halves are rounded away from zero, so half of ¢5 is ¢3.
+    /// This returns None if the denominator is zero.
+    pub fn fraction(&self, numerator: i32, denominator: i32) -> Option<Self> {
+        if denominator == 0 {
+            return None;
+        }
+        let numerator = self.amount as i64 * numerator as i64;
+        let denominator = denominator as i64;
+        // Round half away from zero, using the sign of the result
+        let half = denominator.abs();
+        let rounded = if (numerator < 0) == (denominator < 0) {
+            (numerator * 2 + half * denominator.signum()) / (denominator * 2)
+        } else {
+            (numerator * 2 - half * denominator.signum()) / (denominator * 2)
+        };
+        Some(Self::new(rounded as i32))
+    }
 }
 
 impl Default for Currency {
@@ -61,10 +87,52 @@ impl Default for Currency {
 
 impl Display for Currency {
     fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
-        let required_width = if self.is_debt() {
-            1 + self.amount.abs().max(1).ilog10()
+        match f.width() {
+            Some(width) => write!(f, "{:width$}", self.with_symbol("¢")),
+            None => write!(f, "{}", self.with_symbol("¢")),
+        }
+    }
+}
+
+/// A currency amount written with a given symbol, like `$` or `⛁`.
+/// The symbol goes after the amount, like `5¢`, unless it's written first, like `$5`.
+/// Debt is always written with the sign first, like `-5¢` or `-$5`.
+#[derive(Copy, Clone, PartialEq, Eq)]
+pub struct CurrencyFormat<'a> {
+    /// The amount of currency to write.
+    currency: Currency,
+    /// The symbol to write with the amount.
+    symbol: &'a str,
+    /// Is the symbol written before the amount?
+    symbol_first: bool,
+}
+
+impl<'a> CurrencyFormat<'a> {
+    /// Write the symbol before the amount instead of after it.
+    pub fn symbol_first(mut self) -> Self {
+        self.symbol_first = true;
+        self
+    }
+}
+
+impl Currency {
+    /// Write this amount with the given symbol instead of `¢`.
+    pub fn with_symbol(self, symbol: &str) -> CurrencyFormat<'_> {
+        CurrencyFormat {
+            currency: self,
+            symbol,
+            symbol_first: false,
+        }
+    }
+}
+
+impl Display for CurrencyFormat<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        let amount = self.currency.amount;
+        let required_width = if self.currency.is_debt() {
+            1 + amount.unsigned_abs().max(1).ilog10()
         } else {
-            self.amount.max(1).ilog10()
+            amount.max(1).ilog10()
         } as usize;
         if let Some(mut width) = f.width() {
             while width > required_width {
@@ -73,14 +141,22 @@ impl Display for Currency {
             }
         }
 
-        if self.is_debt() {
-            write!(f, "-{}¢", self.amount)
+        let sign = if self.currency.is_debt() { "-" } else { "" };
+        let magnitude = amount.unsigned_abs();
+        if self.symbol_first {
+            write!(f, "{sign}{}{magnitude}", self.symbol)
         } else {
-            write!(f, "{}¢", self.amount)
+            write!(f, "{sign}{magnitude}{}", self.symbol)
         }
     }
 }
 
+impl Debug for CurrencyFormat<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        write!(f, "{}", self)
+    }
+}
+
 impl Debug for Currency {
     fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
         write!(f, "{}", self)
@@ -123,7 +199,8 @@ impl Mul<f64> for Currency {
     type Output = Self;
 
     fn mul(self, rhs: f64) -> Self::Output {
-        Self::new((self.amount as f64 * rhs) as i32)
+        // Round the same way as `fraction`, to the nearest penny
+        Self::new((self.amount as f64 * rhs).round() as i32)
     }
 }
 
@@ -0,0 +1,188 @@
+use super::{StateCapitalistBoard, Currency, Color, Move, MoveError};
+use alloc::vec::Vec;
+use core::{str::FromStr, ops::Deref, fmt::{Display, Formatter, Result as FmtResult}};
+
+/// A reason a game log couldn't be parsed.
+/// Lines are numbered from one.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum GameLogError {
+    /// The line isn't a numbered move, like `1. White: e2e4 (White 40¢, Black 0¢)`.
+    InvalidLine(usize),
+    /// The line's move couldn't be parsed.
+    InvalidMove(usize),
+}
+
+impl Display for GameLogError {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        match self {
+            Self::InvalidLine(line) => write!(f, "line {line} is not a numbered move"),
+            Self::InvalidMove(line) => write!(f, "line {line} has an invalid move"),
+        }
+    }
+}
+
+/// A move recorded in a game log.
+#[derive(Clone, Debug, PartialEq)]
+pub struct GameLogEntry {
+    /// The player who made the move.
+    pub color: Color,
+    /// The move that was played.
+    pub player_move: Move,
+    /// White's balance after the move.
+    pub white_balance: Currency,
+    /// Black's balance after the move.
+    pub black_balance: Currency,
+}
+
+/// A transcript of the moves played in a game, with the balances after each one.
+/// This is written as a numbered move list, one move per line, like
+/// `1. White: e2e4 (White 40¢, Black 0¢)`, which can be saved, diffed,
+/// and parsed back with `from_str`.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct GameLog {
+    /// The moves played, in order.
+    entries: Vec<GameLogEntry>,
+}
+
+impl GameLog {
+    /// Create an empty log.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Apply the move to the board, and record it in the log.
+    /// Moves are recorded in their normalized form, so a move like `e4`
+    /// is written as `e2e4`, and can be read back on its own.
+    /// If the move is illegal, this returns an error, and records nothing.
+    /// The board is copied throughout a search, so it doesn't keep a log itself;
+    /// use `StateCapitalistBoard::with_logging` or `Game::with_logging` to keep one.
+    pub fn apply(&mut self, board: &mut StateCapitalistBoard, player_move: Move) -> Result<(), MoveError> {
+        let color = board.whose_turn();
+        let player_move = player_move.normalize(board.board());
+        board.apply(player_move.clone()).map_err(|_| MoveError::Illegal)?;
+        self.record(color, player_move, board);
+        Ok(())
+    }
+
+    /// Record a move made by the given player, with the balances on the board after it.
+    pub fn record(&mut self, color: Color, player_move: Move, board: &StateCapitalistBoard) {
+        self.entries.push(GameLogEntry {
+            color,
+            player_move,
+            white_balance: board.get_balance(Color::White),
+            black_balance: board.get_balance(Color::Black),
+        });
+    }
+
+    /// Get the moves played, in order.
+    pub fn get_entries(&self) -> &[GameLogEntry] {
+        &self.entries
+    }
+
+    /// The number of moves played.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Have no moves been played?
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+/// A board that records every move played on it in a game log.
+/// The board is copied throughout a search, so it can't keep the log itself;
+/// this keeps them together instead. It dereferences to the board,
+/// so it can be searched and inspected like one.
+#[derive(Clone, Debug, PartialEq)]
+pub struct LoggedBoard {
+    /// The board the moves are played on.
+    board: StateCapitalistBoard,
+    /// The moves played on the board.
+    log: GameLog,
+}
+
+impl LoggedBoard {
+    /// Start logging the moves played on a board.
+    pub fn new(board: StateCapitalistBoard) -> Self {
+        Self { board, log: GameLog::new() }
+    }
+
+    /// Get the board the moves are played on.
+    pub fn get_board(&self) -> &StateCapitalistBoard {
+        &self.board
+    }
+
+    /// Get the moves played on the board so far.
+    pub fn game_log(&self) -> &GameLog {
+        &self.log
+    }
+
+    /// Apply the move to the board, and record it in the log.
+    /// If the move is illegal, this returns an error, and records nothing.
+    pub fn apply(&mut self, player_move: Move) -> Result<(), MoveError> {
+        self.log.apply(&mut self.board, player_move)
+    }
+}
+
+impl Deref for LoggedBoard {
+    type Target = StateCapitalistBoard;
+
+    fn deref(&self) -> &Self::Target {
+        &self.board
+    }
+}
+
+impl Display for LoggedBoard {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        write!(f, "{}", self.board)
+    }
+}
+
+impl Display for GameLog {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        for (i, entry) in self.entries.iter().enumerate() {
+            writeln!(f, "{}. {:?}: {} (White {}, Black {})", i + 1, entry.color, entry.player_move, entry.white_balance, entry.black_balance)?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for GameLog {
+    type Err = GameLogError;
+
+    /// Parse a log written by `Display`. Blank lines are ignored.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut result = Self::new();
+        for (i, line) in s.lines().enumerate() {
+            let line_number = i + 1;
+            let line = line.trim();
+            if line.is_empty() {
+                continue;
+            }
+
+            let invalid = GameLogError::InvalidLine(line_number);
+            let (_, line) = line.split_once(". ").ok_or(invalid)?;
+            let (color, line) = line.split_once(": ").ok_or(invalid)?;
+            let (player_move, balances) = line.rsplit_once(" (").ok_or(invalid)?;
+            let (white, black) = balances.strip_suffix(')').and_then(|balances| balances.split_once(", ")).ok_or(invalid)?;
+            let balance = |balance: &str, prefix: &str| balance.strip_prefix(prefix)
+                .and_then(|amount| amount.strip_suffix('¢'))
+                .and_then(|amount| amount.parse::<i32>().ok())
+                .map(|amount| Currency::penny() * amount)
+                .ok_or(invalid);
+
+            result.entries.push(GameLogEntry {
+                color: match color {
+                    "White" => Color::White,
+                    "Black" => Color::Black,
+                    _ => return Err(invalid),
+                },
+                player_move: Move::from_str(player_move).map_err(|_| GameLogError::InvalidMove(line_number))?,
+                white_balance: balance(white, "White ")?,
+                black_balance: balance(black, "Black ")?,
+            });
+        }
+        Ok(result)
+    }
+}
@@ -1,7 +1,91 @@
-use super::{PieceType, Sector, Currency, Move};
+use super::{PieceType, Sector, Currency, Move, Board, Color};
+use core::{str::FromStr, fmt::{Display, Formatter, Result as FmtResult, Write}};
+use alloc::{string::String, vec::Vec};
+
+/// A reason a market's configuration is invalid.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum MarketError {
+    /// A piece has a negative price.
+    NegativePieceValue(PieceType),
+    /// Moves must cost something, or players could move for free forever.
+    NonPositiveBaseMoveCost,
+    /// Castling has a negative price.
+    NegativeCastlingValue,
+    /// Passing has a negative price.
+    NegativePassValue,
+    /// A sector pays negative income.
+    NegativeSectorIncome,
+    /// The interest rate on additional moves is negative or not a number.
+    InvalidInterestRate,
+    /// The promotion surcharge rate is negative or not a number.
+    InvalidPromotionSurchargeRate,
+    /// The center control weight is less than one or not a number.
+    InvalidCenterControlWeight,
+    /// A turn must allow at least one action, and the minimum can't exceed the maximum.
+    InvalidActionsPerTurn,
+    /// The capture bounty rate or first capture bonus is negative or not a number.
+    InvalidCaptureBounty,
+    /// The cash discount is not between zero and one.
+    InvalidCashDiscount,
+}
+
+impl Display for MarketError {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        match self {
+            Self::NegativePieceValue(piece) => write!(f, "the value of {piece} is negative"),
+            Self::NonPositiveBaseMoveCost => write!(f, "the base move cost is not positive"),
+            Self::NegativeCastlingValue => write!(f, "the value of castling is negative"),
+            Self::NegativePassValue => write!(f, "the value of passing is negative"),
+            Self::NegativeSectorIncome => write!(f, "a sector's income is negative"),
+            Self::InvalidInterestRate => write!(f, "the move interest rate is negative or not a number"),
+            Self::InvalidPromotionSurchargeRate => write!(f, "the promotion surcharge rate is negative or not a number"),
+            Self::InvalidCenterControlWeight => write!(f, "the center control weight is less than one or not a number"),
+            Self::InvalidActionsPerTurn => write!(f, "the actions allowed per turn are empty or out of order"),
+            Self::InvalidCaptureBounty => write!(f, "the capture bounty is negative or not a number"),
+            Self::InvalidCashDiscount => write!(f, "the cash discount is not between zero and one"),
+        }
+    }
+}
+
+/// A reason a market's config string couldn't be parsed.
+/// Lines are numbered from one.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum MarketConfigError {
+    /// The line isn't a `key=value` pair.
+    InvalidLine(usize),
+    /// The line's key isn't a setting of the market.
+    UnknownKey(usize),
+    /// The line's value couldn't be parsed for its key.
+    InvalidValue(usize),
+    /// The settings were parsed, but the market isn't valid.
+    InvalidMarket(MarketError),
+}
+
+impl Display for MarketConfigError {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        match self {
+            Self::InvalidLine(line) => write!(f, "line {line} is not a key=value pair"),
+            Self::UnknownKey(line) => write!(f, "line {line} has an unknown key"),
+            Self::InvalidValue(line) => write!(f, "line {line} has an invalid value"),
+            Self::InvalidMarket(err) => write!(f, "invalid market: {err}"),
+        }
+    }
+}
+
+/// When a player collects income from the sectors they control.
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum IncomeTiming {
+    /// A player is paid at the start of their turn, after their opponent moves.
+    #[default]
+    TurnStart,
+    /// A player is paid right after their own move, for the territory they now hold.
+    AfterMove,
+}
 
 /// This contains all the configuration data for the banks, and purchase values for pieces
 #[derive(Copy, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Market {
     /// The value of a pawn
     pawn_value: Currency,
@@ -27,9 +111,49 @@ pub struct Market {
     center_sector_income_value: Currency,
     /// Value of the outer sectors
     outer_sector_income_value: Currency,
+    /// The one-time bonus for taking control of a sector
+    sector_capture_bonus: Currency,
+    /// The most a sector can pay at a census, including bonuses
+    max_income_per_sector: Option<Currency>,
 
     /// The compounding interest rate of performing additional moves
     move_interest_rate: f64,
+
+    /// The fraction of the promoted piece's value charged on top of a promoting move
+    promotion_surcharge_rate: f64,
+
+    /// Can pieces be purchased in any sector the player controls,
+    /// instead of only in their home sectors?
+    purchase_in_controlled_sectors: bool,
+
+    /// When players collect income from their sectors
+    income_timing: IncomeTiming,
+
+    /// A center sector is controlled by holding more than this many times the other color's value in it
+    center_control_weight: f64,
+
+    /// The fewest actions a turn may contain
+    min_actions_per_turn: usize,
+    /// The most actions a turn may contain
+    max_actions_per_turn: usize,
+
+    /// The fraction of a captured piece's value paid to the capturer
+    capture_bounty_rate: f64,
+    /// How many times the normal bounty is paid for a player's first capture
+    first_capture_bonus: f64,
+
+    /// Are pieces bought into a reserve and deployed later,
+    /// instead of being placed on the board right away?
+    purchase_into_reserve: bool,
+    /// Which piece types can be purchased, indexed by piece type
+    purchasable_pieces: [bool; PieceType::ALL.len()],
+
+    /// Can each piece act only once in a turn with several actions?
+    piece_may_act_once: bool,
+
+    /// The fraction of its value banked money is discounted by, compared to
+    /// the same value of material on the board, when evaluating a position
+    cash_discount: f64,
 }
 
 impl Default for Market {
@@ -48,8 +172,32 @@ impl Default for Market {
 
             center_sector_income_value: Currency::doubloon() * 2,
             outer_sector_income_value: Currency::doubloon(),
+            sector_capture_bonus: Currency::zero(),
+            max_income_per_sector: None,
 
             move_interest_rate: 2.0,
+
+            promotion_surcharge_rate: 0.0,
+
+            purchase_in_controlled_sectors: false,
+
+            income_timing: IncomeTiming::TurnStart,
+
+            center_control_weight: 1.0,
+
+            min_actions_per_turn: 1,
+            max_actions_per_turn: usize::MAX,
+
+            capture_bounty_rate: 0.0,
+            first_capture_bonus: 2.0,
+
+            purchase_into_reserve: false,
+            // Kings can't be purchased unless a ruleset allows it
+            purchasable_pieces: [true, true, true, true, true, false],
+
+            piece_may_act_once: false,
+
+            cash_discount: 0.75,
         }
     }
 }
@@ -108,6 +256,12 @@ impl Market {
         self
     }
 
+    /// Set the cost of passing a turn
+    pub fn with_pass_value(mut self, pass_value: Currency) -> Self {
+        self.pass_value = pass_value;
+        self
+    }
+
     /// Set the income value of the center sectors
     pub fn with_center_sector_income_value(mut self, center_sector_income_value: Currency) -> Self {
         self.center_sector_income_value = center_sector_income_value;
@@ -120,18 +274,410 @@ impl Market {
         self
     }
 
+    /// Set the one-time bonus for taking control of a sector
+    pub fn with_sector_capture_bonus(mut self, sector_capture_bonus: Currency) -> Self {
+        self.sector_capture_bonus = sector_capture_bonus;
+        self
+    }
+
+    /// Get the one-time bonus for taking control of a sector
+    pub fn get_sector_capture_bonus(&self) -> Currency {
+        self.sector_capture_bonus
+    }
+
+    /// Set the most a sector can pay at a census, including bonuses
+    pub fn with_max_income_per_sector(mut self, max_income_per_sector: Currency) -> Self {
+        self.max_income_per_sector = Some(max_income_per_sector);
+        self
+    }
+
+    /// Get the most a sector can pay at a census, if there's a limit
+    pub fn get_max_income_per_sector(&self) -> Option<Currency> {
+        self.max_income_per_sector
+    }
+
     /// Set the compounding interest rate of performing additional moves
     pub fn with_interest_rate(mut self, move_interest_rate: f64) -> Self {
         self.move_interest_rate = move_interest_rate;
         self
     }
 
+    /// Set the fraction of the promoted piece's value charged on top of a promoting move
+    pub fn with_promotion_surcharge_rate(mut self, promotion_surcharge_rate: f64) -> Self {
+        self.promotion_surcharge_rate = promotion_surcharge_rate;
+        self
+    }
+
+    /// Get the surcharge for promoting a pawn to the given piece
+    pub fn get_promotion_surcharge(&self, piece: PieceType) -> Currency {
+        self.get_piece_value(piece) * self.promotion_surcharge_rate
+    }
+
     /// Set the base cost of a move
     pub fn with_base_move_cost(mut self, base_move_cost: Currency) -> Self {
         self.base_move_cost = base_move_cost;
         self
     }
 
+    /// Allow pieces to be purchased in any sector the player controls
+    pub fn with_purchase_in_controlled_sectors(mut self, purchase_in_controlled_sectors: bool) -> Self {
+        self.purchase_in_controlled_sectors = purchase_in_controlled_sectors;
+        self
+    }
+
+    /// Can pieces be purchased in any sector the player controls?
+    pub fn get_purchase_in_controlled_sectors(&self) -> bool {
+        self.purchase_in_controlled_sectors
+    }
+
+    /// Buy pieces into a reserve and deploy them later,
+    /// instead of placing them on the board right away
+    pub fn with_purchase_into_reserve(mut self, purchase_into_reserve: bool) -> Self {
+        self.purchase_into_reserve = purchase_into_reserve;
+        self
+    }
+
+    /// Are pieces bought into a reserve and deployed later?
+    pub fn get_purchase_into_reserve(&self) -> bool {
+        self.purchase_into_reserve
+    }
+
+    /// Set the piece types that can be purchased
+    pub fn with_purchasable_pieces(mut self, pieces: &[PieceType]) -> Self {
+        self.purchasable_pieces = [false; PieceType::ALL.len()];
+        for piece in pieces {
+            self.purchasable_pieces[*piece as usize] = true;
+        }
+        self
+    }
+
+    /// Get the piece types that can be purchased
+    pub fn get_purchasable_pieces(&self) -> Vec<PieceType> {
+        PieceType::ALL.into_iter().filter(|piece| self.can_purchase_piece(*piece)).collect()
+    }
+
+    /// Can the given piece type be purchased?
+    pub fn can_purchase_piece(&self, piece: PieceType) -> bool {
+        self.purchasable_pieces[piece as usize]
+    }
+
+    /// Set the fraction of its value banked money is discounted by,
+    /// compared to the same value of material on the board
+    pub fn with_cash_discount(mut self, cash_discount: f64) -> Self {
+        self.cash_discount = cash_discount;
+        self
+    }
+
+    /// Get the fraction of its value banked money is discounted by,
+    /// compared to the same value of material on the board
+    pub fn get_cash_discount(&self) -> f64 {
+        self.cash_discount
+    }
+
+    /// Set when players collect income from their sectors
+    pub fn with_income_timing(mut self, income_timing: IncomeTiming) -> Self {
+        self.income_timing = income_timing;
+        self
+    }
+
+    /// When do players collect income from their sectors?
+    pub fn get_income_timing(&self) -> IncomeTiming {
+        self.income_timing
+    }
+
+    /// Set how many times the other color's value must be exceeded to control a center sector
+    pub fn with_center_control_weight(mut self, center_control_weight: f64) -> Self {
+        self.center_control_weight = center_control_weight;
+        self
+    }
+
+    /// How many times the other color's value must be exceeded to control a center sector?
+    pub fn get_center_control_weight(&self) -> f64 {
+        self.center_control_weight
+    }
+
+    /// Set the fewest actions a turn may contain
+    pub fn with_min_actions_per_turn(mut self, min_actions_per_turn: usize) -> Self {
+        self.min_actions_per_turn = min_actions_per_turn;
+        self
+    }
+
+    /// Set the most actions a turn may contain
+    pub fn with_max_actions_per_turn(mut self, max_actions_per_turn: usize) -> Self {
+        self.max_actions_per_turn = max_actions_per_turn;
+        self
+    }
+
+    /// Get the fewest actions a turn may contain
+    pub fn get_min_actions_per_turn(&self) -> usize {
+        self.min_actions_per_turn
+    }
+
+    /// Get the most actions a turn may contain
+    pub fn get_max_actions_per_turn(&self) -> usize {
+        self.max_actions_per_turn
+    }
+
+    /// Allow each piece to act only once in a turn with several actions
+    pub fn with_piece_may_act_once(mut self, piece_may_act_once: bool) -> Self {
+        self.piece_may_act_once = piece_may_act_once;
+        self
+    }
+
+    /// Can each piece act only once in a turn with several actions?
+    pub fn get_piece_may_act_once(&self) -> bool {
+        self.piece_may_act_once
+    }
+
+    /// Can a turn contain the given number of actions?
+    pub fn allows_actions(&self, actions: usize) -> bool {
+        (self.min_actions_per_turn..=self.max_actions_per_turn).contains(&actions)
+    }
+
+    /// Set the fraction of a captured piece's value paid to the capturer
+    pub fn with_capture_bounty_rate(mut self, capture_bounty_rate: f64) -> Self {
+        self.capture_bounty_rate = capture_bounty_rate;
+        self
+    }
+
+    /// Set how many times the normal bounty is paid for a player's first capture
+    pub fn with_first_capture_bonus(mut self, first_capture_bonus: f64) -> Self {
+        self.first_capture_bonus = first_capture_bonus;
+        self
+    }
+
+    /// Get the fraction of a captured piece's value paid to the capturer
+    pub fn get_capture_bounty_rate(&self) -> f64 {
+        self.capture_bounty_rate
+    }
+
+    /// Get how many times the normal bounty is paid for a player's first capture
+    pub fn get_first_capture_bonus(&self) -> f64 {
+        self.first_capture_bonus
+    }
+
+    /// Get the bounty for capturing the given piece.
+    /// A player's first capture of the game is paid the first capture bonus.
+    pub fn get_capture_bounty(&self, piece: PieceType, first_capture: bool) -> Currency {
+        let bounty = self.get_piece_value(piece) * self.capture_bounty_rate;
+        if first_capture {
+            bounty * self.first_capture_bonus
+        } else {
+            bounty
+        }
+    }
+
+    /// Which color controls the given sector under this market's rules?
+    /// Pieces are priced by this market. A color controls a center sector only
+    /// with strictly more than the center control weight times the other color's value.
+    pub fn who_controls_sector(&self, board: &Board, sector: Sector) -> Option<Color> {
+        let weight = if sector.is_center() { self.center_control_weight } else { 1.0 };
+        board.who_controls_sector_in_market(sector, weight, self)
+    }
+
+    /// Can the given color purchase pieces in the given sector?
+    /// Home sectors are always allowed, and controlled sectors are
+    /// allowed if the market permits it.
+    pub fn can_purchase_in(&self, board: &Board, sector: Sector, color: Color) -> bool {
+        sector.is_home_for(color)
+            || (self.purchase_in_controlled_sectors && self.who_controls_sector(board, sector) == Some(color))
+    }
+
+    /// Confirm the market is configured sensibly.
+    /// Prices and incomes must not be negative, and moves must cost something.
+    pub fn validate(&self) -> Result<(), MarketError> {
+        for piece in PieceType::ALL {
+            if self.get_piece_value(piece).is_debt() {
+                return Err(MarketError::NegativePieceValue(piece));
+            }
+        }
+        if !self.base_move_cost.is_surplus() {
+            return Err(MarketError::NonPositiveBaseMoveCost);
+        }
+        if self.castling_value.is_debt() {
+            return Err(MarketError::NegativeCastlingValue);
+        }
+        if self.pass_value.is_debt() {
+            return Err(MarketError::NegativePassValue);
+        }
+        if self.center_sector_income_value.is_debt() || self.outer_sector_income_value.is_debt() || self.sector_capture_bonus.is_debt()
+            || self.max_income_per_sector.is_some_and(|max| max.is_debt()) {
+            return Err(MarketError::NegativeSectorIncome);
+        }
+        if self.move_interest_rate.is_nan() || self.move_interest_rate < 0.0 {
+            return Err(MarketError::InvalidInterestRate);
+        }
+        if self.promotion_surcharge_rate.is_nan() || self.promotion_surcharge_rate < 0.0 {
+            return Err(MarketError::InvalidPromotionSurchargeRate);
+        }
+        if self.center_control_weight.is_nan() || self.center_control_weight < 1.0 {
+            return Err(MarketError::InvalidCenterControlWeight);
+        }
+        if self.min_actions_per_turn == 0 || self.min_actions_per_turn > self.max_actions_per_turn {
+            return Err(MarketError::InvalidActionsPerTurn);
+        }
+        if self.capture_bounty_rate.is_nan() || self.capture_bounty_rate < 0.0
+            || self.first_capture_bonus.is_nan() || self.first_capture_bonus < 0.0 {
+            return Err(MarketError::InvalidCaptureBounty);
+        }
+        if !(0.0..=1.0).contains(&self.cash_discount) {
+            return Err(MarketError::InvalidCashDiscount);
+        }
+        Ok(())
+    }
+
+    /// Get a hash of every setting of the market, so results computed under
+    /// one market, like an engine's cached evaluations, aren't mistaken for
+    /// results under another.
+    pub fn fingerprint(&self) -> u64 {
+        let cents = |currency: Currency| currency.get_amount() as u64;
+        let purchasable = self.purchasable_pieces.iter()
+            .fold(0, |bits, purchasable| (bits << 1) | *purchasable as u64);
+        let mut result = 0;
+        for value in [
+            cents(self.pawn_value),
+            cents(self.knight_value),
+            cents(self.bishop_value),
+            cents(self.rook_value),
+            cents(self.queen_value),
+            cents(self.king_value),
+            cents(self.base_move_cost),
+            cents(self.castling_value),
+            cents(self.pass_value),
+            cents(self.center_sector_income_value),
+            cents(self.outer_sector_income_value),
+            cents(self.sector_capture_bonus),
+            self.max_income_per_sector.map_or(u64::MAX, cents),
+            self.move_interest_rate.to_bits(),
+            self.promotion_surcharge_rate.to_bits(),
+            self.purchase_in_controlled_sectors as u64,
+            self.income_timing as u64,
+            self.center_control_weight.to_bits(),
+            self.min_actions_per_turn as u64,
+            self.max_actions_per_turn as u64,
+            self.capture_bounty_rate.to_bits(),
+            self.first_capture_bonus.to_bits(),
+            self.purchase_into_reserve as u64,
+            purchasable,
+            self.piece_may_act_once as u64,
+            self.cash_discount.to_bits(),
+        ] {
+            result = (result ^ value).rotate_left(23).wrapping_mul(0x9E37_79B9_7F4A_7C15);
+        }
+        result
+    }
+
+    /// Write the market as `key=value` lines, which can be edited by hand
+    /// and read back with `from_config_string`.
+    /// Prices are written in pennies.
+    pub fn to_config_string(&self) -> String {
+        let mut result = String::new();
+        let cents = |currency: Currency| currency.get_amount();
+        let income_timing = match self.income_timing {
+            IncomeTiming::TurnStart => "turn_start",
+            IncomeTiming::AfterMove => "after_move",
+        };
+        // Writing to a string can't fail
+        let mut line = |key: &str, value: &dyn Display| {
+            let _ = writeln!(result, "{key}={value}");
+        };
+        line("pawn_value", &cents(self.pawn_value));
+        line("knight_value", &cents(self.knight_value));
+        line("bishop_value", &cents(self.bishop_value));
+        line("rook_value", &cents(self.rook_value));
+        line("queen_value", &cents(self.queen_value));
+        line("king_value", &cents(self.king_value));
+        line("base_move_cost", &cents(self.base_move_cost));
+        line("castling_value", &cents(self.castling_value));
+        line("pass_value", &cents(self.pass_value));
+        line("center_sector_income_value", &cents(self.center_sector_income_value));
+        line("outer_sector_income_value", &cents(self.outer_sector_income_value));
+        line("sector_capture_bonus", &cents(self.sector_capture_bonus));
+        match self.max_income_per_sector {
+            Some(max) => line("max_income_per_sector", &cents(max)),
+            None => line("max_income_per_sector", &"none"),
+        }
+        line("move_interest_rate", &self.move_interest_rate);
+        line("promotion_surcharge_rate", &self.promotion_surcharge_rate);
+        line("purchase_in_controlled_sectors", &self.purchase_in_controlled_sectors);
+        line("income_timing", &income_timing);
+        line("center_control_weight", &self.center_control_weight);
+        line("min_actions_per_turn", &self.min_actions_per_turn);
+        line("max_actions_per_turn", &self.max_actions_per_turn);
+        line("capture_bounty_rate", &self.capture_bounty_rate);
+        line("first_capture_bonus", &self.first_capture_bonus);
+        line("purchase_into_reserve", &self.purchase_into_reserve);
+        line("purchasable_pieces", &self.get_purchasable_pieces().into_iter().map(char::from).collect::<String>());
+        line("piece_may_act_once", &self.piece_may_act_once);
+        line("cash_discount", &self.cash_discount);
+        result
+    }
+
+    /// Read a market from `key=value` lines, as written by `to_config_string`.
+    /// Blank lines and lines starting with `#` are ignored, and any settings
+    /// that are left out keep their default values.
+    pub fn from_config_string(config: &str) -> Result<Self, MarketConfigError> {
+        let mut result = Self::default();
+        for (i, line) in config.lines().enumerate() {
+            let line_number = i + 1;
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let (key, value) = line.split_once('=').ok_or(MarketConfigError::InvalidLine(line_number))?;
+            let (key, value) = (key.trim(), value.trim());
+            let invalid = MarketConfigError::InvalidValue(line_number);
+            let cents = |value: &str| value.parse::<i32>().map(|amount| Currency::penny() * amount).map_err(|_| invalid);
+            let rate = |value: &str| value.parse::<f64>().map_err(|_| invalid);
+            let count = |value: &str| value.parse::<usize>().map_err(|_| invalid);
+            match key {
+                "pawn_value" => result.pawn_value = cents(value)?,
+                "knight_value" => result.knight_value = cents(value)?,
+                "bishop_value" => result.bishop_value = cents(value)?,
+                "rook_value" => result.rook_value = cents(value)?,
+                "queen_value" => result.queen_value = cents(value)?,
+                "king_value" => result.king_value = cents(value)?,
+                "base_move_cost" => result.base_move_cost = cents(value)?,
+                "castling_value" => result.castling_value = cents(value)?,
+                "pass_value" => result.pass_value = cents(value)?,
+                "center_sector_income_value" => result.center_sector_income_value = cents(value)?,
+                "outer_sector_income_value" => result.outer_sector_income_value = cents(value)?,
+                "sector_capture_bonus" => result.sector_capture_bonus = cents(value)?,
+                "max_income_per_sector" => result.max_income_per_sector = match value {
+                    "none" => None,
+                    value => Some(cents(value)?),
+                },
+                "move_interest_rate" => result.move_interest_rate = rate(value)?,
+                "promotion_surcharge_rate" => result.promotion_surcharge_rate = rate(value)?,
+                "purchase_in_controlled_sectors" => result.purchase_in_controlled_sectors = value.parse().map_err(|_| invalid)?,
+                "income_timing" => result.income_timing = match value {
+                    "turn_start" => IncomeTiming::TurnStart,
+                    "after_move" => IncomeTiming::AfterMove,
+                    _ => return Err(invalid),
+                },
+                "center_control_weight" => result.center_control_weight = rate(value)?,
+                "min_actions_per_turn" => result.min_actions_per_turn = count(value)?,
+                "max_actions_per_turn" => result.max_actions_per_turn = count(value)?,
+                "capture_bounty_rate" => result.capture_bounty_rate = rate(value)?,
+                "first_capture_bonus" => result.first_capture_bonus = rate(value)?,
+                "purchase_into_reserve" => result.purchase_into_reserve = value.parse().map_err(|_| invalid)?,
+                "purchasable_pieces" => {
+                    let pieces = value.chars()
+                        .map(|piece| PieceType::from_str(piece.encode_utf8(&mut [0; 4])))
+                        .collect::<Result<Vec<_>, _>>()
+                        .map_err(|_| invalid)?;
+                    result = result.with_purchasable_pieces(&pieces);
+                },
+                "piece_may_act_once" => result.piece_may_act_once = value.parse().map_err(|_| invalid)?,
+                "cash_discount" => result.cash_discount = rate(value)?,
+                _ => return Err(MarketConfigError::UnknownKey(line_number)),
+            }
+        }
+        result.validate().map_err(MarketConfigError::InvalidMarket)?;
+        Ok(result)
+    }
+
     /// Get the base cost of a move
     pub fn get_base_move_cost(&self) -> Currency {
         self.base_move_cost
@@ -152,18 +698,43 @@ impl Market {
     /// Get the value of a move in the market.
     pub fn get_move_value(&self, player_move: &Move) -> Currency {
         match player_move {
+            Move::FromTo { promotion: Some(piece), .. } | Move::PieceTo { promotion: Some(piece), .. } => {
+                self.base_move_cost + self.get_promotion_surcharge(*piece)
+            },
             Move::FromTo { .. } | Move::PieceTo { .. } => self.base_move_cost,
-            Move::Purchase { piece, to: _ } => self.get_piece_value(*piece),
+            Move::Purchase { piece, to: _ } | Move::Reserve(piece) => self.get_piece_value(*piece),
+            // The piece was paid for when it was reserved
+            Move::Deploy { .. } => Currency::zero(),
             Move::Castling { .. } => self.castling_value,
             Move::Many(moves) => {
                 let mut total = Currency::zero();
                 for (i, player_move) in moves.iter().enumerate() {
-                    total += self.get_move_value(player_move) * self.move_interest_rate.powi(i as i32);
+                    total += self.get_action_value(player_move, i);
                 }
                 total
             },
             Move::Pass => self.pass_value,
-            Move::Resign => Currency::zero(),
+            Move::Resign | Move::ClaimDraw => Currency::zero(),
+        }
+    }
+
+    /// Get the value of an action taken as the given action of a turn, counting from zero.
+    /// The first action costs its usual value, and each extra action is
+    /// charged the move interest rate on top of the action before it.
+    pub fn get_action_value(&self, player_move: &Move, index: usize) -> Currency {
+        self.get_move_value(player_move) * self.move_interest_rate.powi(index as i32)
+    }
+
+    /// Get the income a sector pays at a census, including the capture bonus
+    /// if it was just captured, limited to the most a sector can pay.
+    pub fn get_sector_income(&self, sector: Sector, captured: bool) -> Currency {
+        let mut income = self.get_sector_value(sector);
+        if captured {
+            income += self.sector_capture_bonus;
+        }
+        match self.max_income_per_sector {
+            Some(max) => income.min(max),
+            None => income,
         }
     }
 
@@ -1,24 +1,137 @@
-use log::{info, error};
+use log::{debug, info, error};
 
 mod currency;
-pub use currency::Currency;
+pub use currency::{Currency, CurrencyFormat};
 
 mod bank;
 pub use bank::Bank;
 
 mod market;
-pub use market::Market;
+pub use market::{Market, MarketError, MarketConfigError, IncomeTiming};
 
-use core::fmt::{Display, Formatter, Result as FmtResult};
-use alloc::vec::Vec;
+mod game_log;
+pub use game_log::{GameLog, GameLogEntry, GameLogError, LoggedBoard};
+
+use core::fmt::{Display, Formatter, Result as FmtResult, Write};
+use alloc::{string::String, vec::Vec};
+use itertools::Itertools;
 
 use super::*;
 
+/// A reason a saved game couldn't be loaded by `StateCapitalistBoard::from_string_repr`.
+/// Lines are numbered from one.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum StateReprError {
+    /// There's no `fen` line for the board.
+    MissingFen,
+    /// The board's FEN couldn't be loaded.
+    InvalidFen(FenError),
+    /// The line has a bank key, but its value couldn't be parsed.
+    InvalidBank(usize),
+    /// The line has the positions kept to find repetitions, but they couldn't be parsed.
+    InvalidHistory(usize),
+    /// The line has the plies played or the recent changes of sector control, but they couldn't be parsed.
+    InvalidSectorChanges(usize),
+    /// The market's lines couldn't be parsed.
+    InvalidMarket(MarketConfigError),
+}
+
+impl Display for StateReprError {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        match self {
+            Self::MissingFen => write!(f, "the board's FEN is missing"),
+            Self::InvalidFen(err) => write!(f, "invalid board: {err}"),
+            Self::InvalidBank(line) => write!(f, "line {line} has an invalid bank value"),
+            Self::InvalidHistory(line) => write!(f, "line {line} has an invalid position history"),
+            Self::InvalidSectorChanges(line) => write!(f, "line {line} has an invalid history of sector control"),
+            Self::InvalidMarket(err) => write!(f, "{err}"),
+        }
+    }
+}
+
+/// A reason a purchase is not allowed.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum PurchaseError {
+    /// The tile already has a piece on it.
+    Occupied,
+    /// The tile is not in a sector the player can purchase in.
+    NotPurchasableSector,
+    /// The player can't afford the piece.
+    CannotAfford,
+    /// The player is in check, so they must move out of it instead.
+    InCheck,
+    /// The piece would leave the player's king in check.
+    LeavesKingInCheck,
+    /// The market buys pieces into the reserve, so they can't be placed on the board directly.
+    ReserveOnly,
+    /// The market places pieces on the board directly, so they can't be reserved.
+    NoReserve,
+    /// The player has none of the piece in their reserve.
+    NotInReserve,
+    /// The player's reserve can't hold any more of the piece.
+    ReserveFull,
+    /// The market doesn't sell the piece.
+    NotPurchasablePiece,
+}
+
+impl Display for PurchaseError {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        match self {
+            Self::Occupied => write!(f, "the tile is occupied"),
+            Self::NotPurchasableSector => write!(f, "the tile is not in a sector the player can purchase in"),
+            Self::CannotAfford => write!(f, "the player cannot afford the piece"),
+            Self::InCheck => write!(f, "the player is in check"),
+            Self::LeavesKingInCheck => write!(f, "the purchase would leave the king in check"),
+            Self::ReserveOnly => write!(f, "pieces must be bought into the reserve"),
+            Self::NoReserve => write!(f, "pieces can't be bought into the reserve"),
+            Self::NotInReserve => write!(f, "the piece is not in the player's reserve"),
+            Self::ReserveFull => write!(f, "the player's reserve can't hold any more of the piece"),
+            Self::NotPurchasablePiece => write!(f, "the piece can't be purchased"),
+        }
+    }
+}
+
+/// Something that happened while applying a move.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum GameEvent {
+    /// A piece was captured on the given tile.
+    Captured { piece: Piece, tile: Tile },
+    /// A player was paid income for the sectors they control.
+    IncomeGranted { color: Color, amount: Currency },
+    /// A piece was purchased onto the given tile.
+    PurchaseMade { piece: Piece, tile: Tile, cost: Currency },
+    /// The given player was put in check.
+    CheckDelivered { checked: Color },
+    /// Control of a sector changed hands, or was lost by both players.
+    SectorControlChanged { sector: Sector, owner: Option<Color> },
+    /// The game is over.
+    GameOver(GameResult),
+}
+
+/// The key used to sort moves into a stable order.
+fn move_order(player_move: &Move) -> (u8, u8, u8, u8) {
+    let promotion = |promotion: &Option<PieceType>| promotion.map_or(0, |piece| piece as u8 + 1);
+    match player_move {
+        Move::Purchase { piece, to } => (0, *piece as u8, to.index(), 0),
+        Move::Reserve(piece) => (1, *piece as u8, 0, 0),
+        Move::Deploy { piece, to } => (2, *piece as u8, to.index(), 0),
+        Move::FromTo { from, to, promotion: piece } => (3, from.index(), to.index(), promotion(piece)),
+        Move::PieceTo { piece, to, promotion: promoted, .. } => (4, *piece as u8, to.index(), promotion(promoted)),
+        Move::Castling(CastlingSide::King) => (5, 0, 0, 0),
+        Move::Castling(CastlingSide::Queen) => (5, 1, 0, 0),
+        Move::Many(_) => (6, 0, 0, 0),
+        Move::Pass => (7, 0, 0, 0),
+        Move::Resign => (8, 0, 0, 0),
+        Move::ClaimDraw => (9, 0, 0, 0),
+    }
+}
+
 /// A board for a game of State Capitalist Chess.
 /// 
 /// This board is used to keep track of the game state.
 /// It is also used to validate moves.
 #[derive(Copy, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct StateCapitalistBoard {
     /// The market for the game.
     market: Market,
@@ -28,8 +141,37 @@ pub struct StateCapitalistBoard {
     black_bank: Bank,
     /// The current board state.
     board: Board,
+    /// The hashes of the most recent positions since the last capture,
+    /// pawn move, or purchase, used to find repeated positions.
+    /// This is a ring, so only the last `REPETITION_HISTORY` positions are kept.
+    history: [u64; REPETITION_HISTORY],
+    /// The number of positions recorded in the history, including any that were overwritten.
+    history_count: usize,
+    /// The number of turns played on this board.
+    plies: usize,
+    /// The most recent changes of sector control: the ply of the turn that
+    /// caused each one, counting from one, the sector, and its new owner.
+    /// This is a ring, so only the last `SECTOR_CHANGE_HISTORY` changes are kept.
+    sector_changes: [(usize, Sector, Option<Color>); SECTOR_CHANGE_HISTORY],
+    /// The number of changes of sector control recorded, including any that were overwritten.
+    sector_change_count: usize,
 }
 
+/// The number of recent positions kept to find repetitions.
+/// Repetitions that take longer than this to come around aren't found,
+/// but the fifty-move rule still ends those games.
+const REPETITION_HISTORY: usize = 32;
+
+/// The number of recent changes of sector control kept on the board.
+/// Older changes are dropped, so the board doesn't keep a game's full history
+/// of sector control; watch for `GameEvent::SectorControlChanged` to record it all.
+pub const SECTOR_CHANGE_HISTORY: usize = 32;
+
+/// The most turns of several actions generated for a position.
+/// The number of turns grows exponentially with the actions they must take,
+/// so the rest are left out once this many are found.
+const MAX_TURN_SEQUENCES: usize = 1024;
+
 impl Default for StateCapitalistBoard {
     fn default() -> Self {
         Self::new(Market::default())
@@ -38,15 +180,61 @@ impl Default for StateCapitalistBoard {
 
 impl StateCapitalistBoard {
     /// Create a new board.
+    /// This panics if the market is invalid, use `try_new` to handle the error.
     pub fn new(market: Market) -> Self {
+        match Self::try_new(market) {
+            Ok(result) => result,
+            Err(err) => panic!("Invalid market: {err}"),
+        }
+    }
+
+    /// Create a new board where neither player has been paid for their
+    /// starting territory, so both balances start at zero.
+    /// This is useful for setting up scenarios with known balances.
+    /// This panics if the market is invalid.
+    pub fn new_without_census(market: Market) -> Self {
+        match Self::try_new_without_census(market) {
+            Ok(result) => result,
+            Err(err) => panic!("Invalid market: {err}"),
+        }
+    }
+
+    /// Create a new board without the first census, if the market is valid.
+    pub fn try_new_without_census(market: Market) -> Result<Self, MarketError> {
+        market.validate()?;
         let mut result = Self {
             market,
             white_bank: Bank::new(Color::White, market),
             black_bank: Bank::new(Color::Black, market),
             board: Board::default(),
+            history: [0; REPETITION_HISTORY],
+            history_count: 0,
+            plies: 0,
+            sector_changes: [(0, Sector::BOTTOM_LEFT, None); SECTOR_CHANGE_HISTORY],
+            sector_change_count: 0,
         };
-        result.perform_census_for_color(Color::White);
-        result
+        result.record_position();
+        Ok(result)
+    }
+
+    /// Create a new board, if the market is valid.
+    pub fn try_new(market: Market) -> Result<Self, MarketError> {
+        let mut result = Self::try_new_without_census(market)?;
+        match market.get_income_timing() {
+            IncomeTiming::TurnStart => result.perform_census_for_color(Color::White),
+            IncomeTiming::AfterMove => {
+                // Neither player has moved yet, so both are paid for their starting territory
+                result.perform_census_for_color(Color::White);
+                result.perform_census_for_color(Color::Black);
+            },
+        }
+        Ok(result)
+    }
+
+    /// Record the moves played on this board from here on.
+    /// The log is kept alongside the board, so the board stays cheap to copy.
+    pub fn with_logging(self) -> LoggedBoard {
+        LoggedBoard::new(self)
     }
 
     pub fn get_market(&self) -> &Market {
@@ -57,6 +245,62 @@ impl StateCapitalistBoard {
         self.get_bank(color).get_balance()
     }
 
+    /// Get the total money held by both players.
+    pub fn total_money(&self) -> Currency {
+        self.get_balance(Color::White) + self.get_balance(Color::Black)
+    }
+
+    /// Confirm a move didn't create or destroy any money, other than destroying
+    /// the move's cost and creating the income paid out after it.
+    fn check_money_conserved(&self, total_before: Currency, destroyed: Currency, created: Currency) {
+        let expected = total_before - destroyed + created;
+        if self.total_money() != expected {
+            error!("{self}");
+            panic!("Money was created or destroyed: expected {expected} in total, found {}", self.total_money());
+        }
+    }
+
+    /// Get the piece types the given player can currently afford to purchase.
+    pub fn affordable_pieces(&self, color: Color) -> Vec<PieceType> {
+        let bank = self.get_bank(color);
+        self.market.get_purchasable_pieces()
+            .into_iter()
+            .filter(|piece| bank.can_afford_piece(*piece))
+            .collect()
+    }
+
+    /// Get the purchases the given player can make, paired with their costs,
+    /// cheapest first. If the market buys pieces into the reserve, these are
+    /// the reservations and deployments instead.
+    /// If it isn't the player's turn, these are the purchases they could make
+    /// if it were.
+    pub fn purchase_options(&self, color: Color) -> Vec<(Move, Currency)> {
+        let mut board = self.board;
+        board.set_turn(color);
+        let mut result: Vec<(Move, Currency)> = Move::legal_purchases(&board, self.get_bank(color))
+            .into_iter()
+            .map(|player_move| {
+                let cost = self.market.get_move_value(&player_move);
+                (player_move, cost)
+            })
+            .collect();
+        result.sort_by_key(|(player_move, cost)| (*cost, move_order(player_move)));
+        result
+    }
+
+    /// Borrow the underlying chess board.
+    #[inline]
+    pub fn board(&self) -> &Board {
+        &self.board
+    }
+
+    /// Mutably borrow the underlying chess board, to set up custom scenarios.
+    /// Banks aren't paid for the changes until the next census.
+    #[inline]
+    pub fn board_mut(&mut self) -> &mut Board {
+        &mut self.board
+    }
+
     /// Get a piece at the given tile.
     #[inline]
     pub fn get_piece(&self, tile: Tile) -> Option<Piece> {
@@ -69,6 +313,49 @@ impl StateCapitalistBoard {
         self.board.whose_turn()
     }
     
+    /// Get a lightweight view of the material on the board.
+    #[inline]
+    pub fn material_view(&self) -> MaterialView {
+        self.board.material_view()
+    }
+
+    /// Is the given player in checkmate?
+    /// Besides being checkmated on the board, the player to move is
+    /// checkmated if they can't afford any of the moves out of check.
+    pub fn is_in_checkmate(&self, color: Color) -> bool {
+        if !self.board.is_in_check(color) {
+            return false;
+        }
+        if self.board.is_in_checkmate(color) {
+            return true;
+        }
+        // The player can move out of check on the board, but may not be able to afford to.
+        // A longer turn costs more than the shortest turns it starts with, so those are enough to tell
+        color == self.whose_turn() && self.legal_moves_with(false, 1).is_empty()
+    }
+
+    /// Is the board in a state of stalemate?
+    /// Besides having no legal piece moves, the player to move must also be
+    /// unable to afford a purchase or a pass.
+    pub fn is_stalemate(&self) -> bool {
+        self.board.is_stalemate() && self.legal_moves_with(false, 1).is_empty()
+    }
+
+    /// Would the given move stalemate the opponent?
+    /// Besides having no legal piece moves, the opponent must also be unable
+    /// to afford a purchase or a pass.
+    pub fn results_in_stalemate(&self, player_move: &Move) -> bool {
+        let mut board = *self;
+        board.apply(player_move.clone()).is_ok() && board.is_stalemate()
+    }
+
+    /// Get the winner of the game, if a player has resigned or been checkmated.
+    /// A player in check who can't afford any way out of it is checkmated, too.
+    #[inline]
+    pub fn get_winner(&self) -> Option<Color> {
+        self.board.get_winner()
+    }
+
     /// Get the bank for the given color.
     #[inline]
     pub fn get_bank(&self, color: Color) -> &Bank {
@@ -89,29 +376,56 @@ impl StateCapitalistBoard {
 
     /// Is the given move legal?
     pub fn is_legal_move(&self, player_move: &Move) -> bool {
+        // No moves can be played once the game is over
+        if self.get_winner().is_some() || self.board.is_drawn() {
+            return false;
+        }
+        // Passing, resigning, and claiming a draw don't act, so they're allowed regardless of the turn's length
+        if !matches!(player_move, Move::Pass | Move::Resign | Move::ClaimDraw) && !self.market.allows_actions(player_move.action_count()) {
+            error!("A turn can't take {} actions", player_move.action_count());
+            return false;
+        }
+        self.is_legal_action(player_move)
+    }
+
+    /// Is the given move legal, regardless of how many actions the turn allows?
+    fn is_legal_action(&self, player_move: &Move) -> bool {
         let whose_turn = self.whose_turn();
 
         match player_move {
-            Move::Purchase { to, .. } => {
-                // First, confirm the "to" tile is empty
-                if self.board.has_piece_on(*to) {
-                    error!("Tile is not empty!");
-                    return false;
+            Move::Purchase { piece, to } => {
+                match self.validate_purchase(*piece, *to) {
+                    Ok(()) => true,
+                    Err(err) => {
+                        error!("Illegal purchase: {err}");
+                        false
+                    }
                 }
-
-                if !to.get_sector().is_home_for(whose_turn) {
-                    error!("Tile is not in the home sector!");
-                    return false;
+            },
+            Move::Reserve(piece) => {
+                match self.validate_reserve(*piece) {
+                    Ok(()) => true,
+                    Err(err) => {
+                        error!("Illegal reservation: {err}");
+                        false
+                    }
                 }
-
-                // Next, confirm the player can afford the piece
-                let result = self.get_bank(whose_turn).can_afford(player_move) && self.board.is_legal_move(player_move);
-                if !result {
-                    error!("Player cannot afford to purchase!");
+            },
+            Move::Deploy { piece, to } => {
+                match self.validate_deploy(*piece, *to) {
+                    Ok(()) => true,
+                    Err(err) => {
+                        error!("Illegal deployment: {err}");
+                        false
+                    }
                 }
-                result
             },
             Move::Pass => {
+                // A player in check must get out of it, so they can't pass
+                if self.board.is_in_check(whose_turn) {
+                    error!("Player cannot pass while in check!");
+                    return false;
+                }
                 // Confirm the player can afford to pass
                 let result = self.get_bank(whose_turn).can_afford(player_move);
                 if !result {
@@ -119,16 +433,68 @@ impl StateCapitalistBoard {
                 }
                 result
             }
+            Move::FromTo { .. } | Move::PieceTo { .. } => {
+                let player_move = self.with_default_promotion(player_move.clone());
+                if !self.board.is_legal_move(&player_move) {
+                    return false;
+                }
+
+                // Promotions may carry a surcharge, so confirm the player can afford it
+                if let Move::FromTo { promotion: Some(_), .. } | Move::PieceTo { promotion: Some(_), .. } = player_move {
+                    let result = self.get_bank(whose_turn).can_afford(&player_move);
+                    if !result {
+                        error!("Player cannot afford to promote!");
+                    }
+                    return result;
+                }
+                true
+            },
             Move::Many(moves) => {
-                let mut copy = self.clone();
+                // Extra actions are board moves and purchases, not whole turns
+                if moves.is_empty() || moves.iter().any(|player_move| matches!(player_move, Move::Pass | Move::Resign | Move::ClaimDraw | Move::Many(_))) {
+                    error!("A turn's extra actions must be board moves or purchases!");
+                    return false;
+                }
+                // Each extra action costs interest, so confirm the player can afford the whole turn
+                if !self.get_bank(whose_turn).can_afford(player_move) {
+                    error!("Player cannot afford all the actions in the turn!");
+                    return false;
+                }
+
+                let mut copy = *self;
+                // The tiles of the pieces that have already acted this turn, if each piece may only act once
+                let mut acted = TileSet::default();
                 for (i, player_move) in moves.iter().enumerate() {
                     copy.board.set_turn(self.whose_turn());
-                    if !copy.is_legal_move(player_move) {
+                    if !copy.is_legal_action(player_move) {
                         error!("Illegal move #{i} {player_move:?} move!");
                         return false;
                     }
                     copy.board.set_turn(self.whose_turn());
+                    let before = copy.board;
                     copy.apply_without_census(player_move.clone()).unwrap();
+                    if !self.market.get_piece_may_act_once() {
+                        continue;
+                    }
+
+                    // Follow the pieces that acted to their new tiles, so they can't act again
+                    for tile in Tile::all() {
+                        let was = before.get_piece(tile).filter(|piece| piece.get_color() == whose_turn);
+                        let is = copy.board.get_piece(tile).filter(|piece| piece.get_color() == whose_turn);
+                        if was == is {
+                            continue;
+                        }
+                        if was.is_some() {
+                            if acted.contains(tile) {
+                                error!("The piece on {tile} already acted this turn!");
+                                return false;
+                            }
+                            acted.remove(tile);
+                        }
+                        if is.is_some() {
+                            acted.insert(tile);
+                        }
+                    }
                 }
                 true
             },
@@ -136,6 +502,116 @@ impl StateCapitalistBoard {
         }
     }
 
+    /// Can the current player purchase the given piece onto the given tile?
+    /// This returns the reason the purchase isn't allowed, if it isn't.
+    pub fn validate_purchase(&self, piece: PieceType, to: Tile) -> Result<(), PurchaseError> {
+        let whose_turn = self.whose_turn();
+
+        if self.market.get_purchase_into_reserve() {
+            return Err(PurchaseError::ReserveOnly);
+        }
+
+        if !self.market.can_purchase_piece(piece) {
+            return Err(PurchaseError::NotPurchasablePiece);
+        }
+
+        // First, confirm the "to" tile is empty
+        if self.board.has_piece_on(to) {
+            return Err(PurchaseError::Occupied);
+        }
+
+        if !self.market.can_purchase_in(&self.board, to.get_sector(), whose_turn) {
+            return Err(PurchaseError::NotPurchasableSector);
+        }
+
+        // Next, confirm the player can afford the piece
+        if !self.get_bank(whose_turn).can_afford(&Move::Purchase { piece, to }) {
+            return Err(PurchaseError::CannotAfford);
+        }
+
+        self.validate_placement(piece, to)
+    }
+
+    /// Can the current player buy the given piece into their reserve?
+    pub fn validate_reserve(&self, piece: PieceType) -> Result<(), PurchaseError> {
+        let whose_turn = self.whose_turn();
+
+        if !self.market.get_purchase_into_reserve() {
+            return Err(PurchaseError::NoReserve);
+        }
+
+        if !self.market.can_purchase_piece(piece) {
+            return Err(PurchaseError::NotPurchasablePiece);
+        }
+
+        if !self.get_bank(whose_turn).can_afford(&Move::Reserve(piece)) {
+            return Err(PurchaseError::CannotAfford);
+        }
+
+        if !self.get_bank(whose_turn).has_reserve_room(piece) {
+            return Err(PurchaseError::ReserveFull);
+        }
+
+        // Reserving a piece doesn't change the board, so it can't get the player out of check
+        if self.board.is_in_check(whose_turn) {
+            return Err(PurchaseError::InCheck);
+        }
+
+        Ok(())
+    }
+
+    /// Can the current player deploy the given piece from their reserve onto the given tile?
+    /// Pieces are deployed under the same rules as they're purchased.
+    pub fn validate_deploy(&self, piece: PieceType, to: Tile) -> Result<(), PurchaseError> {
+        let whose_turn = self.whose_turn();
+
+        if self.get_bank(whose_turn).get_reserve_count(piece) == 0 {
+            return Err(PurchaseError::NotInReserve);
+        }
+
+        if self.board.has_piece_on(to) {
+            return Err(PurchaseError::Occupied);
+        }
+
+        if !self.market.can_purchase_in(&self.board, to.get_sector(), whose_turn) {
+            return Err(PurchaseError::NotPurchasableSector);
+        }
+
+        self.validate_placement(piece, to)
+    }
+
+    /// Confirm placing the given piece on the given tile doesn't break the rules of check.
+    fn validate_placement(&self, piece: PieceType, to: Tile) -> Result<(), PurchaseError> {
+        let whose_turn = self.whose_turn();
+
+        if self.board.is_in_check(whose_turn) {
+            return Err(PurchaseError::InCheck);
+        }
+
+        if self.board.is_in_check_after_purchase(whose_turn, piece, to) {
+            return Err(PurchaseError::LeavesKingInCheck);
+        }
+
+        Ok(())
+    }
+
+    /// Fill in the queen promotion the board would default to, so the
+    /// promotion is priced the same whether or not it was given explicitly.
+    fn with_default_promotion(&self, player_move: Move) -> Move {
+        let from = match player_move {
+            Move::FromTo { from, promotion: None, .. } => Some(from),
+            Move::PieceTo { piece, hint, to, promotion: None } => self.board.get_eligible_piece(piece, hint, to),
+            _ => None,
+        };
+
+        match (player_move, from) {
+            (Move::FromTo { to, .. } | Move::PieceTo { to, .. }, Some(from)) if self.board.is_valid_promotion(from, to) => {
+                Move::new(from, to, Some(PieceType::Queen))
+            },
+            (player_move, _) => player_move,
+        }
+    }
+
     /// Perform a census for the given color.
     fn perform_census_for_color(&mut self, color: Color) {
         info!("Performing census for {color:?}");
@@ -144,56 +620,520 @@ impl StateCapitalistBoard {
         bank.perform_census(&board);
     }
 
+    /// Take a census for the given color outside of a move,
+    /// paying them for the sectors they control right now.
+    pub fn run_census(&mut self, color: Color) {
+        self.perform_census_for_color(color);
+    }
+
+    /// Take a census for both colors outside of a move.
+    pub fn run_census_all(&mut self) {
+        self.perform_census_for_color(Color::White);
+        self.perform_census_for_color(Color::Black);
+    }
+
+    /// Get the result of the game, if it's over.
+    /// This covers resignation, checkmate, stalemate, a claimed draw, and the
+    /// draw after `Board::PROGRESS_FORCED_DRAW_PLIES` plies without progress,
+    /// so a game loop only needs to check this once per turn.
+    pub fn is_game_over(&self) -> Option<GameResult> {
+        if let Some(winner) = self.get_winner() {
+            return Some(GameResult::Win(winner));
+        }
+
+        let turn = self.whose_turn();
+        if self.is_in_checkmate(turn) {
+            return Some(GameResult::Win(!turn));
+        }
+
+        if self.board.is_drawn() || self.is_stalemate() {
+            return Some(GameResult::Draw);
+        }
+
+        if self.board.is_seventy_five_move_draw() {
+            return Some(GameResult::Draw);
+        }
+
+        if self.is_threefold_repetition() {
+            return Some(GameResult::Draw);
+        }
+
+        None
+    }
+
+    /// Has the current position occurred three times?
+    /// Positions are compared by the board alone, so the players' balances
+    /// don't count. They change with nearly every move, so otherwise no
+    /// position would ever repeat. Purchases change the board, so they're
+    /// never mistaken for a repetition.
+    pub fn is_threefold_repetition(&self) -> bool {
+        let hash = self.board.zobrist_hash();
+        let recorded = self.history_count.min(REPETITION_HISTORY);
+        self.history[..recorded].iter().filter(|past| **past == hash).count() >= 3
+    }
+
+    /// Record the current position in the history.
+    /// Positions before a capture, pawn move, or purchase can't come back,
+    /// so they're forgotten.
+    fn record_position(&mut self) {
+        if self.board.plies_since_progress() == 0 {
+            self.history_count = 0;
+        }
+        self.history[self.history_count % REPETITION_HISTORY] = self.board.zobrist_hash();
+        self.history_count += 1;
+    }
+
+    /// The number of turns played on this board.
+    pub fn get_plies(&self) -> usize {
+        self.plies
+    }
+
+    /// Get the most recent changes of sector control, oldest first.
+    /// Each change is the ply of the turn that caused it, counting from one,
+    /// the sector, and the color that controls it now.
+    ///
+    /// Only the last `SECTOR_CHANGE_HISTORY` changes are kept, so in a long game
+    /// this isn't every change; record `GameEvent::SectorControlChanged` with
+    /// `apply_with_observer` to keep them all.
+    pub fn sector_control_changes(&self) -> Vec<(usize, Sector, Option<Color>)> {
+        let recorded = self.sector_change_count.min(SECTOR_CHANGE_HISTORY);
+        (self.sector_change_count - recorded..self.sector_change_count)
+            .map(|i| self.sector_changes[i % SECTOR_CHANGE_HISTORY])
+            .collect()
+    }
+
+    /// Record the sectors whose control changed since the given board, in this turn's ply.
+    fn record_sector_changes(&mut self, before: &Board) {
+        for sector in Sector::all() {
+            // Only the pieces in a sector decide who controls it
+            if Tile::in_sector(sector).all(|tile| before.get_piece(tile) == self.board.get_piece(tile)) {
+                continue;
+            }
+            let owner = self.market.who_controls_sector(&self.board, sector);
+            if self.market.who_controls_sector(before, sector) != owner {
+                self.sector_changes[self.sector_change_count % SECTOR_CHANGE_HISTORY] = (self.plies, sector, owner);
+                self.sector_change_count += 1;
+            }
+        }
+    }
+
     /// Apply the move to the board.
     pub fn apply(&mut self, player_move: Move) -> Result<(), ()> {
+        self.apply_with_observer(player_move, None).map_err(|_| ())
+    }
+
+    /// Apply the move to the board, reporting what happened to the observer.
+    pub fn apply_with_observer(&mut self, player_move: Move, on_event: Option<&mut dyn FnMut(GameEvent)>) -> Result<(), MoveError> {
+        let player_move = self.with_default_promotion(player_move);
         if !self.is_legal_move(&player_move) {
-            eprintln!("Illegal move!!!!");
-            return Err(())
+            debug!("Illegal move: {player_move:?}");
+            return Err(MoveError::Illegal)
         }
         let whose_turn = self.whose_turn();
+        let total_before = self.total_money();
+        let cost = self.market.get_move_value(&player_move);
         // Purchase the move
-        self.get_bank_mut(whose_turn).purchase(&player_move)?;
+        self.get_bank_mut(whose_turn).purchase(&player_move).map_err(|_| MoveError::Illegal)?;
+
+        let before = self.board;
+        // Apply each action on its own, so every capture is paid a bounty
+        let actions = match &player_move {
+            Move::Many(moves) => moves.as_slice(),
+            player_move => core::slice::from_ref(player_move),
+        };
+        let mut bounty = Currency::zero();
+        let plies = self.board.plies_since_progress();
+        let mut is_progress = false;
+        for action in actions {
+            self.board.set_turn(whose_turn);
+            let outcome = self.board.apply_detailed_without_mate_check(action.clone())?;
+            self.update_reserve(whose_turn, action);
+            for piece in outcome.captured {
+                bounty += self.get_bank_mut(whose_turn).collect_bounty(piece.get_type());
+            }
+            is_progress |= self.board.plies_since_progress() == 0;
+        }
+        // However many actions it took, a turn is a single ply
+        if let Move::Many(_) = player_move {
+            self.board.count_turn(plies, is_progress);
+        }
+        self.board.set_turn(!whose_turn);
+
+        let census_color = match self.market.get_income_timing() {
+            // The player about to move is paid for the territory they hold
+            IncomeTiming::TurnStart => !whose_turn,
+            // The player who just moved is paid for the territory they now hold
+            IncomeTiming::AfterMove => whose_turn,
+        };
+        let balance = self.get_balance(census_color);
+        self.perform_census_for_color(census_color);
+        let income = self.get_balance(census_color) - balance;
+
+        // The board only knows about checkmate on the board, so the economy
+        // marks a player who can't afford to get out of check as the loser
+        if self.get_winner().is_none() && self.is_in_checkmate(!whose_turn) {
+            self.board.set_winner(whose_turn);
+        }
+        self.record_position();
+        self.plies += 1;
+        self.record_sector_changes(&before);
 
-        self.board.apply(player_move)?;
-        self.perform_census_for_color(!whose_turn);
+        if INSERT_SANITY_CHECKS {
+            self.check_money_conserved(total_before, cost, income + bounty);
+        }
+
+        if let Some(on_event) = on_event {
+            self.report_events(&before, &player_move, whose_turn, on_event);
+            on_event(GameEvent::IncomeGranted { color: census_color, amount: income });
+            if let Some(result) = self.is_game_over() {
+                on_event(GameEvent::GameOver(result));
+            }
+        }
         Ok(())
     }
 
+    /// Report the purchases, captures, checks, and changes of sector control from a move made from the given board.
+    fn report_events(&self, before: &Board, player_move: &Move, mover: Color, on_event: &mut dyn FnMut(GameEvent)) {
+        let moves = match player_move {
+            Move::Many(moves) => moves.as_slice(),
+            player_move => core::slice::from_ref(player_move),
+        };
+        for player_move in moves {
+            if let Move::Purchase { piece, to } = player_move {
+                on_event(GameEvent::PurchaseMade {
+                    piece: Piece::new(*piece, mover),
+                    tile: *to,
+                    cost: self.market.get_piece_value(*piece),
+                });
+            }
+        }
+
+        for (tile, piece) in captured_pieces(before, &self.board, mover) {
+            on_event(GameEvent::Captured { piece, tile });
+        }
+
+        if self.board.is_in_check(!mover) {
+            on_event(GameEvent::CheckDelivered { checked: !mover });
+        }
+
+        for (_, sector, owner) in self.sector_control_changes().into_iter().filter(|(ply, ..)| *ply == self.plies) {
+            on_event(GameEvent::SectorControlChanged { sector, owner });
+        }
+    }
+
     /// This applies a move without performing a census.
     /// This is used to perform partial moves, without updating the bank.
     fn apply_without_census(&mut self, player_move: Move) -> Result<(), ()> {
-        if !self.is_legal_move(&player_move) {
-            eprintln!("Illegal move!!!!");
+        let player_move = self.with_default_promotion(player_move);
+        if !self.is_legal_action(&player_move) {
+            debug!("Illegal action: {player_move:?}");
             return Err(())
         }
         let whose_turn = self.whose_turn();
         // Purchase the move
         self.get_bank_mut(whose_turn).purchase(&player_move)?;
 
-        self.board.apply(player_move)?;
+        self.board.apply_without_mate_check(player_move.clone())?;
+        self.update_reserve(whose_turn, &player_move);
         Ok(())
     }
 
-    /// Get the legal moves for the current player.
+    /// Put a reserved piece into the player's reserve, or take a deployed piece out of it.
+    fn update_reserve(&mut self, color: Color, action: &Move) {
+        match action {
+            Move::Reserve(piece) => {
+                let reserved = self.get_bank_mut(color).add_to_reserve(*piece);
+                debug_assert!(reserved, "Reserved a piece the reserve had no room for");
+            },
+            Move::Deploy { piece, .. } => {
+                let deployed = self.get_bank_mut(color).take_from_reserve(*piece);
+                debug_assert!(deployed, "Deployed a piece that wasn't in the reserve");
+            },
+            _ => {},
+        }
+    }
+
+    /// Get the legal moves for the current player, in a stable order.
+    /// Purchases come first, by piece and then tile, then reserving and
+    /// deploying pieces in the same order, followed by board moves
+    /// by their from tile and then to tile, then castling, and passing last.
+    /// If a turn must take more than one action, these are the legal
+    /// combinations of the fewest actions allowed.
+    /// Claiming a draw ends the game instead of playing it, so it's left out.
     pub fn legal_moves(&self) -> Vec<Move> {
+        self.legal_moves_in_order(true)
+    }
+
+    /// Get the legal moves for the current player.
+    /// If `sorted` is false, the moves are left in the order they're generated,
+    /// which skips the cost of sorting them.
+    pub fn legal_moves_in_order(&self, sorted: bool) -> Vec<Move> {
+        self.legal_moves_with(sorted, self.market.get_max_actions_per_turn())
+    }
+
+    /// Get the legal moves for the current player, like `legal_moves`, but
+    /// leave out turns of more than the given number of actions.
+    /// If the market requires more actions than that, the turns of the
+    /// fewest actions it allows are kept.
+    pub fn legal_moves_up_to(&self, max_actions: usize) -> Vec<Move> {
+        self.legal_moves_with(true, max_actions)
+    }
+
+    /// Get the legal moves for the current player, with turns of no more than
+    /// `max_actions` actions, unless the market requires more.
+    fn legal_moves_with(&self, sorted: bool, max_actions: usize) -> Vec<Move> {
+        let min_actions = self.market.get_min_actions_per_turn();
+        let max_actions = self.market.get_max_actions_per_turn().min(max_actions.max(min_actions));
+        let mut result = if min_actions <= 1 {
+            self.legal_actions(sorted)
+        } else {
+            vec![]
+        };
+
+        // Turns of several actions are generated shortest first, until
+        // the player can't afford to take any more actions in a turn
+        let mut turns = vec![];
+        for length in min_actions.max(2)..=max_actions {
+            if !self.extend_turns(self, &mut vec![], length, sorted, &mut turns) || turns.len() >= MAX_TURN_SEQUENCES {
+                break;
+            }
+        }
+        result.extend(turns);
+
+        // Passing lets the player save their money and collect income instead
+        if self.is_legal_move(&Move::Pass) {
+            result.push(Move::Pass);
+        }
+
+        result
+    }
+
+    /// Get the single actions the current player can take: purchases and board moves.
+    fn legal_actions(&self, sorted: bool) -> Vec<Move> {
         let mut result = vec![];
 
         let whose_turn = self.whose_turn();
 
         // Add purchase moves
         for player_move in Move::legal_purchases(&self.board, self.get_bank(whose_turn)) {
-            assert!(self.is_legal_move(&player_move));
+            assert!(self.is_legal_action(&player_move));
             result.push(player_move);
         }
 
-        // Add board moves
+        // Add board moves, leaving out promotions the player can't afford
         for player_move in Move::legal_moves(&self.board) {
-            assert!(self.is_legal_move(&player_move));
-            result.push(player_move);
+            if self.is_legal_action(&player_move) {
+                result.push(player_move);
+            }
+        }
+
+        if sorted {
+            result.sort_by_key(move_order);
+        }
+        result
+    }
+
+    /// Add the legal turns of `length` actions that start with `actions` to `turns`.
+    /// `board` is this board after `actions` were played, so each action is
+    /// generated on the board the actions before it left behind. That lets a
+    /// piece act twice, and the same actions be played in any order.
+    /// No more than `MAX_TURN_SEQUENCES` turns are generated.
+    /// This returns whether the player could afford any sequence of `length` actions,
+    /// even if none of them were a legal turn.
+    fn extend_turns(&self, board: &Self, actions: &mut Vec<Move>, length: usize, sorted: bool, turns: &mut Vec<Move>) -> bool {
+        if actions.len() == length {
+            let turn = Move::Many(actions.clone());
+            if self.is_legal_move(&turn) {
+                turns.push(turn);
+            }
+            return true;
+        }
+
+        let whose_turn = self.whose_turn();
+        let bank = self.get_bank(whose_turn);
+        let mut reached = false;
+        for action in board.legal_actions(sorted) {
+            if turns.len() >= MAX_TURN_SEQUENCES {
+                break;
+            }
+            // Each extra action costs interest on top of the one before it, so
+            // the turn so far is priced as a whole instead of action by action
+            actions.push(action.clone());
+            let affordable = bank.can_afford(&Move::Many(actions.clone()));
+            actions.pop();
+            if !affordable {
+                continue;
+            }
+            let mut next = *board;
+            if next.apply_without_census(action.clone()).is_err() {
+                continue;
+            }
+            next.board.set_turn(whose_turn);
+            actions.push(action);
+            reached |= self.extend_turns(&next, actions, length, sorted, turns);
+            actions.pop();
+        }
+        reached
+    }
+
+    /// Write the board for a player at the REPL.
+    /// Besides the banks and the board, this shows who controls each sector,
+    /// and whose turn it is.
+    pub fn pretty_print(&self) -> String {
+        let mut result = String::new();
+        // Writing to a string can't fail
+        let _ = self.write_pretty(&mut result);
+        result
+    }
+
+    /// Write the board the way `pretty_print` shows it.
+    fn write_pretty(&self, f: &mut String) -> FmtResult {
+        write!(f, "{self}")?;
+
+        // The sectors are laid out like the board, with the eighth rank first
+        writeln!(f, "Sectors  a-b c-d e-f g-h")?;
+        for row in (0..4).rev() {
+            write!(f, "  {}-{}  ", row * 2 + 1, row * 2 + 2)?;
+            for column in 0..4 {
+                let sector = Sector::from_index(row * 4 + column);
+                let owner = match self.market.who_controls_sector(&self.board, sector) {
+                    Some(Color::White) => 'W',
+                    Some(Color::Black) => 'B',
+                    None => '.',
+                };
+                write!(f, "   {owner}")?;
+            }
+            writeln!(f)?;
         }
+        writeln!(f, "W = white controls, B = black controls, . = nobody")?;
+        writeln!(f, "{:?} to move", self.whose_turn())
+    }
+}
 
+impl StateCapitalistBoard {
+    /// Write the whole game as `key=value` lines, so it can be saved and
+    /// resumed with `from_string_repr`. The first line is the board's FEN,
+    /// followed by each player's bank, the positions kept to find
+    /// repetitions, and then the market's config, as written by
+    /// `Market::to_config_string`.
+    pub fn to_string_repr(&self) -> String {
+        let mut result = format!("fen={}\n", self.board.to_fen());
+        self.white_bank.write_state(&mut result);
+        self.black_bank.write_state(&mut result);
+        let recorded = self.history[..self.history_count.min(REPETITION_HISTORY)]
+            .iter()
+            .map(|hash| format!("{hash:016x}"))
+            .join(",");
+        result.push_str(&format!("history_count={}\nhistory={recorded}\n", self.history_count));
+        let owner = |owner: Option<Color>| match owner {
+            Some(Color::White) => 'w',
+            Some(Color::Black) => 'b',
+            None => '-',
+        };
+        let changes = self.sector_control_changes()
+            .into_iter()
+            .map(|(ply, sector, new_owner)| format!("{ply}:{}:{}", sector.get_index(), owner(new_owner)))
+            .join(",");
+        result.push_str(&format!("plies={}\nsector_change_count={}\nsector_changes={changes}\n", self.plies, self.sector_change_count));
+        result.push_str(&self.market.to_config_string());
         result
     }
+
+    /// Load a game written by `to_string_repr`.
+    /// The banks keep the sectors they owned when the game was saved,
+    /// so no census is taken, and nobody is paid twice for the same turn.
+    pub fn from_string_repr(repr: &str) -> Result<Self, StateReprError> {
+        let mut fen = None;
+        let (mut history_count, mut history) = (None, None);
+        let (mut plies, mut sector_change_count, mut sector_changes) = (None, None, None);
+        let mut bank_lines = Vec::new();
+        // Keep the market's lines where they are, so its errors have the right line numbers
+        let mut market_config = String::new();
+        for (i, line) in repr.lines().enumerate() {
+            match line.trim().split_once('=') {
+                Some(("fen", value)) => fen = Some(value),
+                Some(("history_count", value)) => history_count = Some((i + 1, value)),
+                Some(("history", value)) => history = Some((i + 1, value)),
+                Some(("plies", value)) => plies = Some((i + 1, value)),
+                Some(("sector_change_count", value)) => sector_change_count = Some((i + 1, value)),
+                Some(("sector_changes", value)) => sector_changes = Some((i + 1, value)),
+                // Both colors are five letters long
+                Some((key, value)) if key.starts_with("white_") => bank_lines.push((i + 1, Color::White, &key[6..], value)),
+                Some((key, value)) if key.starts_with("black_") => bank_lines.push((i + 1, Color::Black, &key[6..], value)),
+                _ => {
+                    market_config.push_str(line);
+                    market_config.push('\n');
+                    continue;
+                },
+            }
+            market_config.push('\n');
+        }
+
+        let market = Market::from_config_string(&market_config).map_err(StateReprError::InvalidMarket)?;
+        let board = Board::from_fen(fen.ok_or(StateReprError::MissingFen)?).map_err(StateReprError::InvalidFen)?;
+        let mut result = Self {
+            market,
+            white_bank: Bank::new(Color::White, market),
+            black_bank: Bank::new(Color::Black, market),
+            board,
+            history: [0; REPETITION_HISTORY],
+            history_count: 0,
+            plies: 0,
+            sector_changes: [(0, Sector::BOTTOM_LEFT, None); SECTOR_CHANGE_HISTORY],
+            sector_change_count: 0,
+        };
+        for (line_number, color, key, value) in bank_lines {
+            if !result.get_bank_mut(color).read_state(key, value).map_err(|_| StateReprError::InvalidBank(line_number))? {
+                return Err(StateReprError::InvalidMarket(MarketConfigError::UnknownKey(line_number)));
+            }
+        }
+
+        if let Some((line_number, value)) = plies {
+            result.plies = value.parse().map_err(|_| StateReprError::InvalidSectorChanges(line_number))?;
+        }
+        if let Some((line_number, value)) = sector_change_count {
+            result.sector_change_count = value.parse().map_err(|_| StateReprError::InvalidSectorChanges(line_number))?;
+        }
+        let changes: Vec<&str> = sector_changes.map_or(vec![], |(_, value)| value.split(',').filter(|change| !change.is_empty()).collect());
+        let line_number = sector_changes.map_or(0, |(line_number, _)| line_number);
+        if changes.len() != result.sector_change_count.min(SECTOR_CHANGE_HISTORY) {
+            return Err(StateReprError::InvalidSectorChanges(line_number));
+        }
+        let first = result.sector_change_count - changes.len();
+        for (i, change) in changes.into_iter().enumerate() {
+            let mut fields = change.split(':');
+            let (Some(ply), Some(sector), Some(owner), None) = (fields.next(), fields.next(), fields.next(), fields.next()) else {
+                return Err(StateReprError::InvalidSectorChanges(line_number));
+            };
+            let ply = ply.parse().map_err(|_| StateReprError::InvalidSectorChanges(line_number))?;
+            let sector = match sector.parse() {
+                Ok(index) if index < Sector::NUM_SECTORS => Sector::from_index(index),
+                _ => return Err(StateReprError::InvalidSectorChanges(line_number)),
+            };
+            let owner = match owner {
+                "w" => Some(Color::White),
+                "b" => Some(Color::Black),
+                "-" => None,
+                _ => return Err(StateReprError::InvalidSectorChanges(line_number)),
+            };
+            result.sector_changes[(first + i) % SECTOR_CHANGE_HISTORY] = (ply, sector, owner);
+        }
+
+        // Without the saved positions, repetitions are counted from this position
+        let (Some((count_line, count)), Some((history_line, hashes))) = (history_count, history) else {
+            result.record_position();
+            return Ok(result);
+        };
+        result.history_count = count.parse().map_err(|_| StateReprError::InvalidHistory(count_line))?;
+        let hashes: Vec<&str> = hashes.split(',').filter(|hash| !hash.is_empty()).collect();
+        if hashes.len() != result.history_count.min(REPETITION_HISTORY) {
+            return Err(StateReprError::InvalidHistory(history_line));
+        }
+        for (past, hash) in result.history.iter_mut().zip(hashes) {
+            *past = u64::from_str_radix(hash, 16).map_err(|_| StateReprError::InvalidHistory(history_line))?;
+        }
+        Ok(result)
+    }
 }
 
 impl From<StateCapitalistBoard> for Board {
@@ -206,8 +1146,8 @@ impl Display for StateCapitalistBoard {
     fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
         // Print black bank
         write!(f, "{}", self.black_bank)?;
-        // Print board
-        write!(f, "{}", self.board)?;
+        // Print board, shading the sectors by the market's prices
+        self.board.write_colored(f, &self.market)?;
         // Print white bank
         write!(f, "{}", self.white_bank)?;
         Ok(())
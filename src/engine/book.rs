@@ -0,0 +1,115 @@
+use super::{StateCapitalistBoard, Move};
+use alloc::vec::Vec;
+use core::fmt::{Display, Formatter, Result as FmtResult};
+use std::collections::HashMap;
+use rand::seq::SliceRandom;
+use log::debug;
+
+/// A reason an opening book couldn't be loaded.
+/// Lines are numbered from one.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum BookError {
+    /// The line doesn't have a position hash and a move.
+    InvalidLine(usize),
+    /// The line's position hash isn't a hexadecimal number.
+    InvalidHash(usize),
+    /// The line's move isn't a UCI move.
+    InvalidMove(usize),
+    /// The line's weight isn't a positive number.
+    InvalidWeight(usize),
+}
+
+impl Display for BookError {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        match self {
+            Self::InvalidLine(line) => write!(f, "line {line} doesn't have a position and a move"),
+            Self::InvalidHash(line) => write!(f, "line {line} has an invalid position hash"),
+            Self::InvalidMove(line) => write!(f, "line {line} has an invalid move"),
+            Self::InvalidWeight(line) => write!(f, "line {line} has an invalid weight"),
+        }
+    }
+}
+
+/// A book of opening moves, so an engine can play varied openings without searching.
+/// Positions are looked up by `Board::zobrist_hash`, and each has a list of
+/// moves, weighted by how often they should be played.
+///
+/// Books only know the board, not the players' money. Positions that come up
+/// after a purchase won't be in a standard chess book, so they simply miss.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct OpeningBook {
+    /// The weighted moves for each position, by the position's hash.
+    entries: HashMap<u64, Vec<(Move, u32)>>,
+}
+
+impl OpeningBook {
+    /// Create an empty book.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a move to play in the position with the given hash.
+    /// Moves with a higher weight are played more often.
+    pub fn add(&mut self, hash: u64, player_move: Move, weight: u32) {
+        self.entries.entry(hash).or_default().push((player_move, weight));
+    }
+
+    /// Get the weighted moves for the position with the given hash.
+    pub fn get_moves(&self, hash: u64) -> &[(Move, u32)] {
+        self.entries.get(&hash).map(Vec::as_slice).unwrap_or_default()
+    }
+
+    /// The number of positions in the book.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Is the book empty?
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Read a book with one move per line, like `463b96181691fc9c e2e4 3`.
+    /// Each line has the position's hash in hexadecimal, the move in UCI
+    /// notation, and an optional weight, which is 1 if it's left out.
+    /// Blank lines and lines starting with `#` are ignored.
+    pub fn from_text(text: &str) -> Result<Self, BookError> {
+        let mut result = Self::new();
+        for (i, line) in text.lines().enumerate() {
+            let line_number = i + 1;
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            let (hash, player_move, weight) = match fields[..] {
+                [hash, player_move] => (hash, player_move, None),
+                [hash, player_move, weight] => (hash, player_move, Some(weight)),
+                _ => return Err(BookError::InvalidLine(line_number)),
+            };
+            let hash = u64::from_str_radix(hash, 16).map_err(|_| BookError::InvalidHash(line_number))?;
+            let player_move = Move::from_uci(player_move).ok_or(BookError::InvalidMove(line_number))?;
+            let weight = match weight {
+                Some(weight) => weight.parse().ok().filter(|weight| *weight > 0).ok_or(BookError::InvalidWeight(line_number))?,
+                None => 1,
+            };
+            result.add(hash, player_move, weight);
+        }
+        Ok(result)
+    }
+
+    /// Pick a move from the book for the given board, at random by weight.
+    /// Book moves that aren't legal on the board are never picked,
+    /// so this returns None if the position is missing or none of its moves are legal.
+    pub fn choose(&self, board: &StateCapitalistBoard) -> Option<Move> {
+        let moves: Vec<&(Move, u32)> = self.get_moves(board.board().zobrist_hash())
+            .iter()
+            .filter(|(player_move, _)| board.is_legal_move(player_move))
+            .collect();
+        // The weights are summed as u64, so a position with many heavy moves can't overflow
+        let (player_move, _) = moves.choose_weighted(&mut rand::thread_rng(), |(_, weight)| u64::from(*weight)).ok()?;
+        debug!("Playing {player_move:?} from the opening book");
+        Some(player_move.clone())
+    }
+}
@@ -1,27 +1,219 @@
-use super::{StateCapitalistBoard, Color, Bank, Move, Tile};
-use alloc::vec::Vec;
-use itertools::Itertools;
-use log::{debug, info};
-use rayon::prelude::*;
+use super::{StateCapitalistBoard, Color, Move, PieceType};
+use alloc::{boxed::Box, vec::Vec};
+use core::time::Duration;
+use std::{sync::{Mutex, atomic::{AtomicUsize, Ordering}}, time::Instant};
+use rand::{Rng, SeedableRng, rngs::StdRng, seq::SliceRandom};
+use log::{debug, info, warn};
 
-/// Return all the combinations of moves where the total cost of the moves
-/// is affordable to the given bank.
-#[allow(dead_code)]
-fn affordable_combinations(moves: Vec<Move>, bank: &Bank, board: &StateCapitalistBoard) -> Vec<Move> {
-    let max_moves = bank.get_balance() / bank.get_market().get_base_move_cost();
-    let mut result = Vec::new();
+mod book;
+pub use book::{OpeningBook, BookError};
 
-    for moves in moves.into_iter().combinations((max_moves.round() as usize).max(1).min(3)) {
-        let m = Move::Many(moves);
-        if board.is_legal_move(&m) {
-            debug!("Can afford: {:?}", m);
-            result.push(m);
+/// Is this a purchase or deployment of a piece the opponent can capture at a profit?
+/// A purchase that loses more than a pawn's price to the opponent's captures is thrown away.
+fn is_hanging_purchase(board: &StateCapitalistBoard, player_move: &Move) -> bool {
+    if let Move::Purchase { piece, to } | Move::Deploy { piece, to } = player_move {
+        let market = board.get_market();
+        let mut after = *board.board();
+        after.spawn(*piece, *to);
+        after.see(*to, !board.whose_turn(), market) > market.get_piece_value(PieceType::Pawn)
+    } else {
+        false
+    }
+}
+
+/// The key used to rank a purchase or deployment by where the piece lands.
+/// Pieces that take control of a sector come first, then pieces closer to the center.
+/// Every other move ranks after them.
+fn purchase_order(board: &StateCapitalistBoard, player_move: &Move) -> (u8, u8) {
+    match player_move {
+        Move::Purchase { piece, to } | Move::Deploy { piece, to } => {
+            let market = board.get_market();
+            let color = board.whose_turn();
+            let mut after = *board.board();
+            after.spawn(*piece, *to);
+            let takes_control = market.who_controls_sector(board.board(), to.get_sector()) != Some(color)
+                && market.who_controls_sector(&after, to.get_sector()) == Some(color);
+            (!takes_control as u8, to.center_distance())
+        },
+        _ => (u8::MAX, u8::MAX),
+    }
+}
+
+/// Sort the purchases to the front of the moves, best placed first,
+/// so the engine prefers them to other purchases that score the same.
+/// The other moves keep their order.
+fn sort_purchases(board: &StateCapitalistBoard, moves: &mut [Move]) {
+    moves.sort_by_cached_key(|player_move| purchase_order(board, player_move));
+}
+
+/// The most actions in the turns an engine searches, unless the market requires more.
+/// Every extra action multiplies the turns there are to search, so engines
+/// only search single actions by default, even when the market allows more.
+const MAX_SEARCHED_ACTIONS: usize = 1;
+
+/// The most captures `Engine::quiescence` will play out past the end of the search.
+const MAX_QUIESCENCE_DEPTH: u32 = 8;
+
+/// Search only the captures on the given board, the way `Engine::quiescence` does.
+/// The player to move can stop capturing whenever the board's score is good enough,
+/// so the score is never worse for them than `leaf_eval`.
+/// Purchases and deployments are left out, even in answer to a capture:
+/// the tile just captured on is taken, so a bought piece can only threaten
+/// the capturing piece, and the threat is a quiet move for the full search to find.
+/// This returns None if the deadline passes before the captures are played out.
+fn quiesce<E: Engine + ?Sized>(engine: &E, board: &StateCapitalistBoard, color: Color, mut alpha: f64, beta: f64, depth: u32, deadline: Option<Instant>) -> Option<f64> {
+    if is_out_of_time(deadline) {
+        return None;
+    }
+    let stand_pat = engine.leaf_eval(board, color);
+    if depth == 0 || stand_pat >= beta {
+        return Some(stand_pat);
+    }
+    alpha = alpha.max(stand_pat);
+
+    let mut best_score = stand_pat;
+    for capture in engine.legal_moves(board).into_iter().filter(|player_move| player_move.is_capture(board.board())) {
+        let mut board_copy = *board;
+        if board_copy.apply(capture).is_err() {
+            continue;
+        }
+
+        let score = -quiesce(engine, &board_copy, !color, -beta, -alpha, depth - 1, deadline)?;
+        best_score = best_score.max(score);
+        alpha = alpha.max(score);
+        if alpha >= beta {
+            break;
+        }
+    }
+    Some(best_score)
+}
+
+/// Has the deadline of a timed search passed? A search without a deadline never runs out of time.
+fn is_out_of_time(deadline: Option<Instant>) -> bool {
+    deadline.is_some_and(|deadline| Instant::now() >= deadline)
+}
+
+/// Search the given board with alpha-beta pruning, the way `Engine::alpha_beta` does by default.
+/// Each move is searched with the engine's own `alpha_beta`, so engines that
+/// override it are used all the way down the tree.
+fn search<E: Engine + ?Sized>(engine: &E, board: &StateCapitalistBoard, depth: u32, color: Color, original_move: Option<Move>, mut alpha: f64, beta: f64) -> (f64, Move) {
+    // A repeated position is a draw, so shuffling back and forth gains nothing
+    if let Some(original_move) = &original_move {
+        if board.is_threefold_repetition() {
+            return (0.0, original_move.clone());
+        }
+    }
+
+    if depth == 0 {
+        return (engine.quiescence(board, color, alpha, beta), original_move.unwrap());
+    }
+
+    info!("Checking minimax at depth {}", depth);
+    let legal_moves = engine.legal_moves(board);
+    let Some(first_move) = legal_moves.first() else {
+        return (f64::NEG_INFINITY, Move::Pass);
+    };
+
+    // Ties go to the move that was ordered first
+    let (mut best_score, mut best_move) = (f64::NEG_INFINITY, first_move.clone());
+    for legal_move in legal_moves {
+        let mut board_copy = *board;
+        if board_copy.apply(legal_move.clone()).is_err() {
+            // The engine's own legal moves should always apply, so this is a bug in the engine
+            warn!("{} generated an illegal move: {legal_move:?}", engine.name());
+            continue;
         }
+
+        let next_move = Some(original_move.clone().unwrap_or(legal_move.clone()));
+        let score = -engine.alpha_beta(&board_copy, depth - 1, color, next_move, -beta, -alpha).0;
+        if score > best_score {
+            best_score = score;
+            best_move = legal_move;
+        }
+
+        alpha = alpha.max(score);
+        if alpha >= beta {
+            debug!("Pruned the moves after {best_move:?} at depth {depth}");
+            break;
+        }
+    }
+
+    (best_score, best_move)
+}
+
+/// Search the given board like `search`, and return the score with the line of
+/// moves the search expects both players to play, starting with the best move.
+/// The line stops at the end of the search, or once the game ends.
+fn search_line<E: Engine + ?Sized>(engine: &E, board: &StateCapitalistBoard, depth: u32, color: Color, mut alpha: f64, beta: f64, is_root: bool) -> (f64, Vec<Move>) {
+    if !is_root && board.is_threefold_repetition() {
+        return (0.0, Vec::new());
+    }
+    if depth == 0 {
+        return (engine.quiescence(board, color, alpha, beta), Vec::new());
+    }
+
+    // Ties go to the move that was ordered first
+    let (mut best_score, mut best_line) = (f64::NEG_INFINITY, Vec::new());
+    for legal_move in engine.legal_moves(board) {
+        let mut board_copy = *board;
+        if board_copy.apply(legal_move.clone()).is_err() {
+            continue;
+        }
+
+        let (score, line) = search_line(engine, &board_copy, depth - 1, color, -beta, -alpha, false);
+        let score = -score;
+        if score > best_score || best_line.is_empty() {
+            best_score = score;
+            best_line = core::iter::once(legal_move).chain(line).collect();
+        }
+
+        alpha = alpha.max(score);
+        if alpha >= beta {
+            break;
+        }
+    }
+
+    (best_score, best_line)
+}
+
+/// The deepest search `Engine::best_move_timed` will try, however much time it has.
+const MAX_TIMED_DEPTH: u32 = 32;
+
+/// Search the given board like `search`, giving up once the deadline passes.
+/// The captures at the leaves are played out like `Engine::quiescence` does,
+/// checking the time before each one.
+/// This returns the score of the board, or None if it ran out of time.
+fn timed_search<E: Engine + ?Sized>(engine: &E, board: &StateCapitalistBoard, depth: u32, color: Color, mut alpha: f64, beta: f64, deadline: Option<Instant>) -> Option<f64> {
+    if is_out_of_time(deadline) {
+        return None;
     }
+    if board.is_threefold_repetition() {
+        return Some(0.0);
+    }
+    if depth == 0 {
+        return quiesce(engine, board, color, alpha, beta, MAX_QUIESCENCE_DEPTH, deadline);
+    }
+
+    let mut best_score = f64::NEG_INFINITY;
+    for legal_move in engine.legal_moves(board) {
+        let mut board_copy = *board;
+        if board_copy.apply(legal_move).is_err() {
+            continue;
+        }
 
-    result
+        let score = -timed_search(engine, &board_copy, depth - 1, color, -beta, -alpha, deadline)?;
+        best_score = best_score.max(score);
+        alpha = alpha.max(score);
+        if alpha >= beta {
+            break;
+        }
+    }
+    Some(best_score)
 }
 
+/// The score of a position where the player has been checkmated.
+/// This is larger than any material or economic evaluation.
+pub const MATE_SCORE: f64 = 1_000_000.0;
 
 /// An engine evaluates a chess board and returns a score.
 pub trait Engine: Send + Sync {
@@ -31,78 +223,128 @@ pub trait Engine: Send + Sync {
     /// Evaluate the given board.
     fn evaluate(&self, board: &StateCapitalistBoard, color: Color) -> f64;
 
-    /// Get the legal moves for the given board.
+    /// Evaluate a leaf of the search tree.
+    /// Terminal positions are scored as a win or loss for the given color
+    /// before falling back to `evaluate`, so every engine scores checkmate
+    /// correctly. This should not be overridden.
+    fn leaf_eval(&self, board: &StateCapitalistBoard, color: Color) -> f64 {
+        let turn = board.whose_turn();
+        if board.is_in_checkmate(turn) {
+            debug!("{turn:?} is checkmated at a leaf");
+            return if turn == color { -MATE_SCORE } else { MATE_SCORE };
+        }
+        self.evaluate(board, color)
+    }
+
+    /// Score a leaf of the search once its captures have been played out,
+    /// so the search doesn't stop in the middle of an exchange, like after
+    /// taking a defended piece but before it's taken back.
+    /// Each player may stop capturing whenever they like, so only captures
+    /// that improve on `leaf_eval` change the score. Pieces bought or deployed
+    /// to win back material aren't played out, so an exchange that ends in a
+    /// purchase is scored before it. As with `alpha_beta`,
+    /// only scores between `alpha` and `beta` are exact.
+    fn quiescence(&self, board: &StateCapitalistBoard, color: Color, alpha: f64, beta: f64) -> f64 {
+        // Without a deadline, the captures are always played out
+        quiesce(self, board, color, alpha, beta, MAX_QUIESCENCE_DEPTH, None).unwrap_or(f64::NEG_INFINITY)
+    }
+
+    /// Get the legal moves for the given board, with the best placed purchases first.
+    /// Only turns of a single action are searched, unless the market requires more.
     fn legal_moves(&self, board: &StateCapitalistBoard) -> Vec<Move> {
-        // Move::legal_moves(&Board::from(*board))
-        let result = board.legal_moves();
-        // result.extend(affordable_combinations(result.clone(), board.get_bank(board.whose_turn()), board));
+        let mut result = board.legal_moves_up_to(MAX_SEARCHED_ACTIONS);
+        sort_purchases(board, &mut result);
         info!("Legal moves: {:?}", result);
-        // info!("Legal moves: {:?}", affordable_combinations(result.clone(), board.get_bank(board.whose_turn()), &board));
-
-        // // result
-        // let mut new_result = affordable_combinations(result.clone(), board.get_bank(board.whose_turn()), &board);
-        // result.append(&mut new_result);
         result
     }
 
     /// Return the best move for the given board.
     fn best_move(&self, board: &StateCapitalistBoard) -> Option<Move> {
         let (score, best_move) = self.minimax(board, 4, board.whose_turn(), None);
-        eprintln!("Score: {}", score);
+        debug!("Score: {score}");
         Some(best_move)
     }
 
-    /// Perform a minimax search on the given board.
-    /// This function returns a tuple of the score and the best move.
-    fn minimax(&self, board: &StateCapitalistBoard, depth: u32, color: Color, original_move: Option<Move>) -> (f64, Move) {
-        if depth == 0 {
-            return (self.evaluate(board, color), original_move.unwrap());
-        }
+    /// Get the line of moves the search expects both players to play from the
+    /// given board, starting with the best move. The line is collected by a
+    /// single search of the given depth, which keeps the best line below each
+    /// move it searches, so the first move is the one `minimax` would play.
+    /// The line ends early if the game ends.
+    fn principal_variation(&self, board: &StateCapitalistBoard, depth: u32) -> Vec<Move> {
+        // Like `best_move_timed`, odd depths score the leaves for the opponent
+        let turn = board.whose_turn();
+        let color = if depth.is_multiple_of(2) { turn } else { !turn };
+        search_line(self, board, depth, color, f64::NEG_INFINITY, f64::INFINITY, true).1
+    }
 
-        info!("Checking minimax at depth {}", depth);
-        // let mut best_score = f64::NEG_INFINITY;
-        // let mut best_move = None;
+    /// Return a move from the opening book for the given board, if it has one.
+    /// Otherwise, search for the best move, like `best_move`.
+    fn best_move_with_book(&self, board: &StateCapitalistBoard, book: &OpeningBook) -> Option<Move> {
+        book.choose(board).or_else(|| self.best_move(board))
+    }
 
-        
-        let all_scores_and_moves = self.legal_moves(board).par_iter().map(|legal_move| {
-            let mut board_copy = board.clone();
-            if board_copy.apply(legal_move.clone()).is_err() {
-                eprintln!("Illegal move: {:?}", legal_move);
-                return (f64::NEG_INFINITY, legal_move.clone());
+    /// Return the best move for the given board, searching for at most the given time.
+    /// This searches one ply deeper at a time, starting each search with the
+    /// best moves of the last one, and plays the best move of the deepest
+    /// search that finished. If not even the first search finishes, this
+    /// plays the first legal move. Every position searched checks the time,
+    /// including the captures played out at the leaves, so this never runs
+    /// over by more than one evaluation, besides finding the first legal moves.
+    /// A budget too long to fit in an `Instant` means there's no deadline.
+    fn best_move_timed(&self, board: &StateCapitalistBoard, budget: Duration) -> Option<Move> {
+        let deadline = Instant::now().checked_add(budget);
+        let turn = board.whose_turn();
+        let mut moves = self.legal_moves(board);
+        let mut best_move = moves.first()?.clone();
+        for depth in 1..=MAX_TIMED_DEPTH {
+            if is_out_of_time(deadline) {
+                debug!("Out of time before depth {depth}, playing {best_move:?}");
+                break;
+            }
+            // Leaves are scored for `color` and negated at every ply,
+            // so odd depths have to score them for the opponent
+            let color = if depth % 2 == 0 { turn } else { !turn };
+            let mut alpha = f64::NEG_INFINITY;
+            let mut scored = Vec::with_capacity(moves.len());
+            for player_move in moves {
+                let mut board_copy = *board;
+                let score = if board_copy.apply(player_move.clone()).is_ok() {
+                    match timed_search(self, &board_copy, depth - 1, color, f64::NEG_INFINITY, -alpha, deadline) {
+                        Some(score) => -score,
+                        None => {
+                            debug!("Out of time at depth {depth}, playing {best_move:?}");
+                            return Some(best_move);
+                        },
+                    }
+                } else {
+                    f64::NEG_INFINITY
+                };
+                alpha = alpha.max(score);
+                scored.push((player_move, score));
             }
-            
-            let score = -self.minimax(&board_copy, depth - 1, color, Some(original_move.clone().unwrap_or(legal_move.clone()))).0;
-            // eprintln!("Score: {}", score);
-            // if score > best_score {
-            //     best_score = score;
-            //     best_move = Some(legal_move);
-            // }
-
-            return (score, legal_move.clone());
-        }).collect::<Vec<_>>();
 
-        if all_scores_and_moves.is_empty() {
-            return (f64::NEG_INFINITY, Move::Pass);
+            // The sort is stable, so ties go to the move the last search preferred
+            scored.sort_by(|(_, a), (_, b)| b.total_cmp(a));
+            moves = scored.into_iter().map(|(player_move, _)| player_move).collect();
+            best_move = moves[0].clone();
+            info!("Best move at depth {depth} is {best_move:?}");
         }
+        Some(best_move)
+    }
 
-        let (best_score, best_move) = all_scores_and_moves.into_iter().max_by(|(score1, _), (score2, _)| score1.partial_cmp(score2).unwrap()).unwrap();
-
-        // for legal_move in self.legal_moves(board) {
-        //     let mut board_copy = board.clone();
-        //     if board_copy.apply(legal_move.clone()).is_err() {
-        //         eprintln!("Illegal move: {:?}", legal_move);
-        //         continue;
-        //     }
-            
-        //     let score = -self.minimax(&board_copy, depth - 1, color, Some(original_move.clone().unwrap_or(legal_move.clone()))).0;
-        //     // eprintln!("Score: {}", score);
-        //     if score > best_score {
-        //         best_score = score;
-        //         best_move = Some(legal_move);
-        //     }
-        // }
+    /// Perform a minimax search on the given board.
+    /// This function returns a tuple of the score and the best move.
+    fn minimax(&self, board: &StateCapitalistBoard, depth: u32, color: Color, original_move: Option<Move>) -> (f64, Move) {
+        self.alpha_beta(board, depth, color, original_move, f64::NEG_INFINITY, f64::INFINITY)
+    }
 
-        (best_score, best_move)
+    /// Perform a minimax search on the given board, only looking for scores
+    /// between `alpha` and `beta`. Once a move scores at least `beta`, the
+    /// opponent would never allow this position, so the rest of the moves
+    /// are skipped. With infinite bounds, this returns the same score and
+    /// move as searching every move.
+    fn alpha_beta(&self, board: &StateCapitalistBoard, depth: u32, color: Color, original_move: Option<Move>, alpha: f64, beta: f64) -> (f64, Move) {
+        search(self, board, depth, color, original_move, alpha, beta)
     }
 }
 
@@ -115,10 +357,19 @@ impl Engine for RandomEngine {
     }
 
     fn evaluate(&self, _board: &StateCapitalistBoard, _color: Color) -> f64 {
-        use rand::Rng;
         let mut rng = rand::thread_rng();
         rng.gen()
     }
+
+    /// A random engine doesn't need to search, it just picks any legal move.
+    fn best_move(&self, board: &StateCapitalistBoard) -> Option<Move> {
+        let mut rng = rand::thread_rng();
+        self.legal_moves(board).choose(&mut rng).cloned()
+    }
+
+    fn best_move_timed(&self, board: &StateCapitalistBoard, _budget: Duration) -> Option<Move> {
+        self.best_move(board)
+    }
 }
 
 /// A simple engine that evaluates the board based on the number of pieces.
@@ -130,19 +381,427 @@ impl Engine for SimpleEngine {
     }
 
     fn evaluate(&self, board: &StateCapitalistBoard, color: Color) -> f64 {
-        let mut score = 0.0;
         let market = board.get_market();
+        let material = board.material_view();
+        let score = (material.market_value(color, market).get_amount() * 2) as f64
+            - (material.market_value(!color, market).get_amount() * 2) as f64;
 
-        for tile in Tile::all() {
-            if let Some(piece) = board.get_piece(tile) {
-                if piece.get_color() == color {
-                    score += (market.get_piece_value(piece.get_type()).get_amount() * 2) as f64;
-                } else {
-                    score -= (market.get_piece_value(piece.get_type()).get_amount() * 2) as f64;
-                }
+        // Money in the bank still has to be spent, which costs tempo,
+        // so it's worth less than the same value of material on the board
+        let cash = |color| board.get_balance(color).get_amount() as f64 * (1.0 - market.get_cash_discount()) * 2.0;
+        let mut score = score + cash(color) - cash(!color);
+
+        // With an overwhelming advantage, drive the lone king to the edge to mate it
+        if material.is_lone_king_against_major(!color) {
+            score += board.board().mop_up_score(color);
+        } else if material.is_lone_king_against_major(color) {
+            score -= board.board().mop_up_score(!color);
+        }
+        score
+    }
+
+    /// Leave out purchases the opponent can simply capture, so the engine
+    /// doesn't search moves that throw money away. The rest of the purchases
+    /// are searched best placed first.
+    fn legal_moves(&self, board: &StateCapitalistBoard) -> Vec<Move> {
+        let mut result: Vec<Move> = board.legal_moves_up_to(MAX_SEARCHED_ACTIONS)
+            .into_iter()
+            .filter(|player_move| !is_hanging_purchase(board, player_move))
+            .collect();
+        sort_purchases(board, &mut result);
+        result
+    }
+}
+
+/// The chance a handicapped engine with no skill at all plays a bad move.
+const MAX_BLUNDER_CHANCE: f64 = 0.5;
+
+/// An engine that sometimes plays a worse move than its inner engine would,
+/// so new players have a chance to win.
+pub struct HandicapEngine {
+    /// How well the engine plays, from 0 (blunders often) to 255 (never blunders).
+    pub skill: u8,
+    /// The engine that picks the moves when the engine doesn't blunder.
+    pub inner: Box<dyn Engine>,
+    /// The source of the engine's blunders.
+    rng: Mutex<StdRng>,
+}
+
+impl HandicapEngine {
+    /// Handicap the given engine with the given skill.
+    pub fn new(skill: u8, inner: Box<dyn Engine>) -> Self {
+        Self {
+            skill,
+            inner,
+            rng: Mutex::new(StdRng::from_entropy()),
+        }
+    }
+
+    /// Seed the engine's blunders, so its games can be reproduced.
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.rng = Mutex::new(StdRng::seed_from_u64(seed));
+        self
+    }
+
+    /// The chance this engine blunders on any given move.
+    pub fn blunder_chance(&self) -> f64 {
+        (u8::MAX - self.skill) as f64 / u8::MAX as f64 * MAX_BLUNDER_CHANCE
+    }
+
+    /// Play the inner engine's best move, unless the engine blunders
+    /// and picks any other legal move instead.
+    fn handicap(&self, board: &StateCapitalistBoard, best_move: Move) -> Move {
+        let mut rng = self.rng.lock().unwrap();
+        if !rng.gen_bool(self.blunder_chance()) {
+            return best_move;
+        }
+
+        let other_moves: Vec<Move> = self.legal_moves(board)
+            .into_iter()
+            .filter(|player_move| *player_move != best_move)
+            .collect();
+        debug!("{} blunders instead of playing {best_move:?}", self.name());
+        other_moves.choose(&mut *rng).cloned().unwrap_or(best_move)
+    }
+}
+
+impl Engine for HandicapEngine {
+    fn name(&self) -> &str {
+        "Handicap Engine"
+    }
+
+    fn evaluate(&self, board: &StateCapitalistBoard, color: Color) -> f64 {
+        self.inner.evaluate(board, color)
+    }
+
+    fn legal_moves(&self, board: &StateCapitalistBoard) -> Vec<Move> {
+        self.inner.legal_moves(board)
+    }
+
+    /// Play the inner engine's move, unless the engine blunders
+    /// and picks any other legal move instead.
+    fn best_move(&self, board: &StateCapitalistBoard) -> Option<Move> {
+        let best_move = self.inner.best_move(board)?;
+        Some(self.handicap(board, best_move))
+    }
+
+    fn best_move_timed(&self, board: &StateCapitalistBoard, budget: Duration) -> Option<Move> {
+        let best_move = self.inner.best_move_timed(board, budget)?;
+        Some(self.handicap(board, best_move))
+    }
+}
+
+/// The key of a position in an engine's cache.
+/// Evaluations depend on the market and the players' money and reserves as well as the position.
+fn search_key(board: &StateCapitalistBoard, color: Color) -> u64 {
+    let reserve = |color| PieceType::ALL.iter()
+        .fold(0, |bits, piece| (bits << 8) | board.get_bank(color).get_reserve_count(*piece) as u64);
+    let mut key = board.board().zobrist_hash();
+    for (i, value) in [
+        board.get_balance(Color::White).get_amount() as u64,
+        board.get_balance(Color::Black).get_amount() as u64,
+        reserve(Color::White),
+        reserve(Color::Black),
+        (color == Color::Black) as u64,
+        board.get_market().fingerprint(),
+    ].into_iter().enumerate() {
+        key = (key ^ value).rotate_left(17 + i as u32).wrapping_mul(0x9E37_79B9_7F4A_7C15);
+    }
+    key
+}
+
+/// The number of evaluations a `CachedEngine` remembers.
+const EVALUATION_CACHE_SIZE: usize = 1 << 16;
+
+/// An engine that remembers the evaluations of its inner engine,
+/// so positions reached more than once in a search are only evaluated once.
+/// The cache has a fixed size, and each position has one slot in it,
+/// so a new evaluation replaces any other position in its slot.
+pub struct CachedEngine<E: Engine> {
+    /// The engine whose evaluations are cached.
+    inner: E,
+    /// The cached evaluations with the keys of their positions and colors,
+    /// in the slot picked by the key.
+    cache: Mutex<Vec<Option<(u64, f64)>>>,
+    /// The number of evaluations found in the cache.
+    hits: AtomicUsize,
+    /// The number of evaluations that had to be computed.
+    misses: AtomicUsize,
+}
+
+impl<E: Engine> CachedEngine<E> {
+    /// Cache the evaluations of the given engine.
+    pub fn new(inner: E) -> Self {
+        Self {
+            inner,
+            cache: Mutex::new(vec![None; EVALUATION_CACHE_SIZE]),
+            hits: AtomicUsize::new(0),
+            misses: AtomicUsize::new(0),
+        }
+    }
+
+    /// Get the engine whose evaluations are cached.
+    pub fn get_inner(&self) -> &E {
+        &self.inner
+    }
+
+    /// The number of evaluations found in the cache.
+    pub fn hits(&self) -> usize {
+        self.hits.load(Ordering::Relaxed)
+    }
+
+    /// The number of evaluations that had to be computed.
+    pub fn misses(&self) -> usize {
+        self.misses.load(Ordering::Relaxed)
+    }
+
+    /// Forget all the cached evaluations.
+    pub fn clear(&self) {
+        self.cache.lock().unwrap().fill(None);
+    }
+}
+
+impl<E: Engine> Engine for CachedEngine<E> {
+    fn name(&self) -> &str {
+        self.inner.name()
+    }
+
+    fn evaluate(&self, board: &StateCapitalistBoard, color: Color) -> f64 {
+        let key = search_key(board, color);
+        let slot = key as usize % EVALUATION_CACHE_SIZE;
+        if let Some((_, score)) = self.cache.lock().unwrap()[slot].filter(|(cached, _)| *cached == key) {
+            self.hits.fetch_add(1, Ordering::Relaxed);
+            return score;
+        }
+
+        self.misses.fetch_add(1, Ordering::Relaxed);
+        let score = self.inner.evaluate(board, color);
+        self.cache.lock().unwrap()[slot] = Some((key, score));
+        score
+    }
+
+    fn legal_moves(&self, board: &StateCapitalistBoard) -> Vec<Move> {
+        self.inner.legal_moves(board)
+    }
+}
+
+/// Is this a promotion to a rook or bishop that a queen promotion does better?
+/// An underpromotion is only worth searching if it gives check, or if promoting
+/// to a queen instead would stalemate the opponent.
+fn is_needless_underpromotion(board: &StateCapitalistBoard, player_move: &Move) -> bool {
+    let queen_move = match player_move {
+        Move::FromTo { from, to, promotion: Some(PieceType::Rook | PieceType::Bishop) } => {
+            Move::new(*from, *to, Some(PieceType::Queen))
+        },
+        Move::PieceTo { piece, hint, to, promotion: Some(PieceType::Rook | PieceType::Bishop) } => {
+            Move::PieceTo { piece: *piece, hint: *hint, to: *to, promotion: Some(PieceType::Queen) }
+        },
+        _ => return false,
+    };
+
+    let mut after = *board;
+    let gives_check = after.apply(player_move.clone()).is_ok()
+        && after.board().is_in_check(!board.whose_turn());
+    !gives_check && !board.results_in_stalemate(&queen_move)
+}
+
+/// An engine that only searches promotions to a queen or a knight,
+/// which are the only promotions that matter in almost every position.
+/// Rook and bishop promotions are still searched when they give check,
+/// or when promoting to a queen would stalemate the opponent.
+pub struct PromotionPruningEngine<E: Engine> {
+    /// The engine whose moves are pruned.
+    inner: E,
+}
+
+impl<E: Engine> PromotionPruningEngine<E> {
+    /// Prune the underpromotions searched by the given engine.
+    pub fn new(inner: E) -> Self {
+        Self { inner }
+    }
+
+    /// Get the engine whose moves are pruned.
+    pub fn get_inner(&self) -> &E {
+        &self.inner
+    }
+}
+
+impl<E: Engine> Engine for PromotionPruningEngine<E> {
+    fn name(&self) -> &str {
+        self.inner.name()
+    }
+
+    fn evaluate(&self, board: &StateCapitalistBoard, color: Color) -> f64 {
+        self.inner.evaluate(board, color)
+    }
+
+    fn legal_moves(&self, board: &StateCapitalistBoard) -> Vec<Move> {
+        self.inner.legal_moves(board)
+            .into_iter()
+            .filter(|player_move| !is_needless_underpromotion(board, player_move))
+            .collect()
+    }
+}
+
+/// How a score in the transposition table relates to the position's real score.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Bound {
+    /// The score is the position's real score.
+    Exact,
+    /// A move scored at least `beta`, so the real score is at least this.
+    Lower,
+    /// No move scored more than `alpha`, so the real score is at most this.
+    Upper,
+}
+
+/// The number of positions an `AlphaBetaEngine` remembers.
+const TRANSPOSITION_TABLE_SIZE: usize = 1 << 16;
+
+/// A position searched by an `AlphaBetaEngine`.
+#[derive(Clone, Copy, Debug)]
+struct TableEntry {
+    /// The key of the position and color that was searched.
+    key: u64,
+    /// The depth the position was searched to.
+    depth: u32,
+    /// The score the search found.
+    score: f64,
+    /// How the score relates to the position's real score.
+    bound: Bound,
+}
+
+/// An engine that remembers the positions its inner engine has searched,
+/// in a transposition table shared across the whole search and between searches.
+/// A position reached by different move orders is only searched once, and
+/// searching the same position again is mostly table lookups.
+///
+/// Positions are keyed like `CachedEngine`, so the table doesn't know how a
+/// position was reached, and a position that would repeat is scored by its
+/// first search. The table has a fixed size, and when two positions share a
+/// slot, the one searched deeper is kept, since it saved the most work.
+pub struct AlphaBetaEngine<E: Engine> {
+    /// The engine whose searches are remembered.
+    inner: E,
+    /// The searched positions, in slots picked by the key of the position and color.
+    table: Mutex<Vec<Option<TableEntry>>>,
+    /// The number of searches answered by the table.
+    hits: AtomicUsize,
+}
+
+impl<E: Engine> AlphaBetaEngine<E> {
+    /// Remember the searches of the given engine.
+    pub fn new(inner: E) -> Self {
+        Self {
+            inner,
+            table: Mutex::new(vec![None; TRANSPOSITION_TABLE_SIZE]),
+            hits: AtomicUsize::new(0),
+        }
+    }
+
+    /// Get the engine whose searches are remembered.
+    pub fn get_inner(&self) -> &E {
+        &self.inner
+    }
+
+    /// The number of searches answered by the table.
+    pub fn hits(&self) -> usize {
+        self.hits.load(Ordering::Relaxed)
+    }
+
+    /// Forget all the searched positions.
+    pub fn clear(&self) {
+        self.table.lock().unwrap().fill(None);
+    }
+}
+
+impl<E: Engine> Engine for AlphaBetaEngine<E> {
+    fn name(&self) -> &str {
+        self.inner.name()
+    }
+
+    fn evaluate(&self, board: &StateCapitalistBoard, color: Color) -> f64 {
+        self.inner.evaluate(board, color)
+    }
+
+    fn legal_moves(&self, board: &StateCapitalistBoard) -> Vec<Move> {
+        self.inner.legal_moves(board)
+    }
+
+    fn alpha_beta(&self, board: &StateCapitalistBoard, depth: u32, color: Color, original_move: Option<Move>, mut alpha: f64, mut beta: f64) -> (f64, Move) {
+        // The root has to find a move, not just a score, and leaves are cheap
+        let original_move = match original_move {
+            Some(original_move) if depth > 0 => original_move,
+            _ => return search(self, board, depth, color, original_move, alpha, beta),
+        };
+
+        let key = search_key(board, color);
+        let slot = key as usize % TRANSPOSITION_TABLE_SIZE;
+        let entry = self.table.lock().unwrap()[slot].filter(|entry| entry.key == key);
+        // Leaves are always scored for `color`, so a deeper search
+        // can only be reused if it flips the sign the same number of times
+        if let Some(entry) = entry.filter(|entry| entry.depth >= depth && (entry.depth - depth).is_multiple_of(2)) {
+            match entry.bound {
+                Bound::Exact => alpha = beta,
+                Bound::Lower => alpha = alpha.max(entry.score),
+                Bound::Upper => beta = beta.min(entry.score),
+            }
+            if alpha >= beta {
+                self.hits.fetch_add(1, Ordering::Relaxed);
+                return (entry.score, original_move);
             }
         }
 
-        score + board.get_balance(color).get_amount() as f64 / 2.0 - board.get_balance(!color).get_amount() as f64 / 2.0
+        let (original_alpha, original_beta) = (alpha, beta);
+        let (score, best_move) = search(self, board, depth, color, Some(original_move), alpha, beta);
+        let bound = if score <= original_alpha {
+            Bound::Upper
+        } else if score >= original_beta {
+            Bound::Lower
+        } else {
+            Bound::Exact
+        };
+        let mut table = self.table.lock().unwrap();
+        // A deeper search of another position in the slot is worth more than this one
+        if table[slot].is_none_or(|entry| entry.key == key || entry.depth <= depth) {
+            table[slot] = Some(TableEntry { key, depth, score, bound });
+        }
+        (score, best_move)
     }
-}
\ No newline at end of file
+}
+
+/// An engine that searches captures of the most valuable pieces by the
+/// least valuable pieces first, then purchases by the value of the piece.
+/// Searching the best moves first lets alpha-beta pruning skip more of the tree.
+/// Moves with the same priority are kept in the inner engine's order.
+pub struct MoveOrderingEngine<E: Engine> {
+    /// The engine whose moves are ordered.
+    inner: E,
+}
+
+impl<E: Engine> MoveOrderingEngine<E> {
+    /// Order the moves searched by the given engine.
+    pub fn new(inner: E) -> Self {
+        Self { inner }
+    }
+
+    /// Get the engine whose moves are ordered.
+    pub fn get_inner(&self) -> &E {
+        &self.inner
+    }
+}
+
+impl<E: Engine> Engine for MoveOrderingEngine<E> {
+    fn name(&self) -> &str {
+        self.inner.name()
+    }
+
+    fn evaluate(&self, board: &StateCapitalistBoard, color: Color) -> f64 {
+        self.inner.evaluate(board, color)
+    }
+
+    fn legal_moves(&self, board: &StateCapitalistBoard) -> Vec<Move> {
+        let mut result = self.inner.legal_moves(board);
+        result.sort_by_cached_key(|player_move| core::cmp::Reverse(player_move.capture_priority(board.board(), board.get_market())));
+        result
+    }
+}
@@ -0,0 +1,303 @@
+use super::{StateCapitalistBoard, Market, Engine, Color, Move, GameLog};
+use core::time::Duration;
+use std::time::Instant;
+use log::{info, error};
+
+/// The outcome of a finished game.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum GameResult {
+    /// The given player won the game.
+    Win(Color),
+    /// Neither player won the game.
+    Draw,
+    /// The given player ran out of time, and lost the game.
+    Timeout(Color),
+}
+
+/// The number of moves a player budgets the rest of their time for.
+const MOVES_TO_BUDGET: u32 = 20;
+
+/// The time each player has left to make their moves.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Clock {
+    /// The time white has left.
+    pub white: Duration,
+    /// The time black has left.
+    pub black: Duration,
+    /// The time added to a player's clock after each of their moves.
+    pub increment: Duration,
+}
+
+impl Clock {
+    /// Create a clock giving both players the same time.
+    pub fn new(time: Duration, increment: Duration) -> Self {
+        Self {
+            white: time,
+            black: time,
+            increment,
+        }
+    }
+
+    /// Get the time the given player has left.
+    pub fn remaining(&self, color: Color) -> Duration {
+        match color {
+            Color::White => self.white,
+            Color::Black => self.black,
+        }
+    }
+
+    /// Get the time the given player should spend on their next move.
+    /// This is a share of the time they have left, plus the increment
+    /// they'll get back for the move, but never more than they have left.
+    pub fn budget(&self, color: Color) -> Duration {
+        let remaining = self.remaining(color);
+        (remaining / MOVES_TO_BUDGET + self.increment).min(remaining)
+    }
+
+    /// Charge the given player for the time they spent on a move.
+    /// If they had enough time, they get the increment, and this returns true.
+    /// Otherwise, their flag falls and this returns false.
+    pub fn spend(&mut self, color: Color, elapsed: Duration) -> bool {
+        let increment = self.increment;
+        let remaining = match color {
+            Color::White => &mut self.white,
+            Color::Black => &mut self.black,
+        };
+        match remaining.checked_sub(elapsed) {
+            Some(left) => {
+                *remaining = left + increment;
+                true
+            },
+            None => {
+                *remaining = Duration::ZERO;
+                false
+            },
+        }
+    }
+}
+
+/// A headless game between two engines.
+pub struct Game<'a> {
+    /// The engine playing white.
+    white: &'a dyn Engine,
+    /// The engine playing black.
+    black: &'a dyn Engine,
+    /// The current game state.
+    board: StateCapitalistBoard,
+    /// The number of plies to play before giving up on the game.
+    max_plies: Option<u32>,
+    /// The players' clocks, if the game is timed.
+    clock: Option<Clock>,
+    /// The player who ran out of time, if either did.
+    flagged: Option<Color>,
+    /// The moves played so far, if the game is logged.
+    log: Option<GameLog>,
+}
+
+impl<'a> Game<'a> {
+    /// Create a new game between two engines.
+    pub fn new(market: Market, white: &'a dyn Engine, black: &'a dyn Engine) -> Self {
+        Self {
+            white,
+            black,
+            board: StateCapitalistBoard::new(market),
+            max_plies: None,
+            clock: None,
+            flagged: None,
+            log: None,
+        }
+    }
+
+    /// Time the game with the given clock.
+    /// A player who takes longer than the time they have left loses the game.
+    pub fn with_clock(mut self, clock: Clock) -> Self {
+        self.clock = Some(clock);
+        self
+    }
+
+    /// Get the players' clocks, if the game is timed.
+    pub fn get_clock(&self) -> Option<&Clock> {
+        self.clock.as_ref()
+    }
+
+    /// Stop the game after the given number of plies.
+    pub fn with_max_plies(mut self, max_plies: u32) -> Self {
+        self.max_plies = Some(max_plies);
+        self
+    }
+
+    /// Record every move played in a `GameLog`.
+    pub fn with_logging(mut self) -> Self {
+        self.log = Some(GameLog::new());
+        self
+    }
+
+    /// Get the moves played so far, if the game is logged.
+    pub fn game_log(&self) -> Option<&GameLog> {
+        self.log.as_ref()
+    }
+
+    /// Get the current game state.
+    pub fn get_board(&self) -> &StateCapitalistBoard {
+        &self.board
+    }
+
+    /// Get the number of plies played so far.
+    pub fn get_plies(&self) -> usize {
+        self.board.get_plies()
+    }
+
+    /// Get the engine playing the given color.
+    pub fn get_engine(&self, color: Color) -> &'a dyn Engine {
+        match color {
+            Color::White => self.white,
+            Color::Black => self.black,
+        }
+    }
+
+    /// Get the result of the game, if it's over.
+    pub fn result(&self) -> Option<GameResult> {
+        if let Some(color) = self.flagged {
+            return Some(GameResult::Timeout(color));
+        }
+        self.board.is_game_over()
+    }
+
+    /// Let the engine whose turn it is play a move.
+    /// This returns the move played, or None if the engine couldn't move,
+    /// or ran out of time.
+    pub fn step(&mut self) -> Option<Move> {
+        let color = self.board.whose_turn();
+        let engine = self.get_engine(color);
+        let player_move = match &mut self.clock {
+            Some(clock) => {
+                let start = Instant::now();
+                let player_move = engine.best_move_timed(&self.board, clock.budget(color));
+                if !clock.spend(color, start.elapsed()) {
+                    info!("{} ran out of time", engine.name());
+                    self.flagged = Some(color);
+                    return None;
+                }
+                player_move?
+            },
+            None => engine.best_move(&self.board)?,
+        };
+        info!("{} plays {:?}", engine.name(), player_move);
+        match &mut self.log {
+            Some(log) => log.apply(&mut self.board, player_move.clone()).ok()?,
+            None => self.board.apply(player_move.clone()).ok()?,
+        }
+        Some(player_move)
+    }
+
+    /// Play the game until it's over.
+    /// This returns None if the game hit the ply limit, or an engine couldn't move.
+    pub fn play(&mut self) -> Option<GameResult> {
+        loop {
+            if let Some(result) = self.result() {
+                info!("Game over after {} plies: {:?}", self.get_plies(), result);
+                return Some(result);
+            }
+
+            if self.max_plies.is_some_and(|max_plies| self.get_plies() >= max_plies as usize) {
+                info!("Game stopped at the ply limit of {}", self.get_plies());
+                return None;
+            }
+
+            if self.step().is_none() {
+                if let Some(result) = self.result() {
+                    info!("Game over after {} plies: {:?}", self.get_plies(), result);
+                    return Some(result);
+                }
+                error!("{:?} could not make a move", self.board.whose_turn());
+                return None;
+            }
+        }
+    }
+}
+
+/// The tally of a match, from the first engine's point of view.
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct MatchResult {
+    /// The games the first engine won.
+    pub wins: u32,
+    /// The games that were drawn.
+    pub draws: u32,
+    /// The games the first engine lost.
+    pub losses: u32,
+    /// The games that didn't finish.
+    pub unfinished: u32,
+}
+
+impl MatchResult {
+    /// The number of games that finished.
+    pub fn completed(&self) -> u32 {
+        self.wins + self.draws + self.losses
+    }
+
+    /// The first engine's score: a point for a win, and half a point for a draw.
+    pub fn score(&self) -> f64 {
+        self.wins as f64 + self.draws as f64 / 2.0
+    }
+}
+
+/// A series of games between two engines, alternating colors.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Match {
+    /// The number of games to play.
+    games: u32,
+    /// The market to play each game with.
+    market: Market,
+    /// The ply limit for each game.
+    max_plies: Option<u32>,
+}
+
+impl Match {
+    /// Create a new match of the given number of games.
+    pub fn new(games: u32) -> Self {
+        Self {
+            games,
+            market: Market::default(),
+            max_plies: None,
+        }
+    }
+
+    /// Play each game with the given market.
+    pub fn with_market(mut self, market: Market) -> Self {
+        self.market = market;
+        self
+    }
+
+    /// Stop each game after the given number of plies.
+    pub fn with_max_plies(mut self, max_plies: u32) -> Self {
+        self.max_plies = Some(max_plies);
+        self
+    }
+
+    /// Play the match between two engines.
+    /// The first engine plays white in the even games, and black in the odd games.
+    pub fn play(&self, first: &dyn Engine, second: &dyn Engine) -> MatchResult {
+        let mut result = MatchResult::default();
+        for i in 0..self.games {
+            let first_color = if i % 2 == 0 { Color::White } else { Color::Black };
+            let mut game = match first_color {
+                Color::White => Game::new(self.market, first, second),
+                Color::Black => Game::new(self.market, second, first),
+            };
+            if let Some(max_plies) = self.max_plies {
+                game = game.with_max_plies(max_plies);
+            }
+
+            match game.play() {
+                Some(GameResult::Win(winner)) if winner == first_color => result.wins += 1,
+                Some(GameResult::Win(_)) => result.losses += 1,
+                Some(GameResult::Timeout(loser)) if loser == first_color => result.losses += 1,
+                Some(GameResult::Timeout(_)) => result.wins += 1,
+                Some(GameResult::Draw) => result.draws += 1,
+                None => result.unfinished += 1,
+            }
+            info!("Game {} of {}: {:?}", i + 1, self.games, result);
+        }
+        result
+    }
+}
@@ -13,7 +13,10 @@ pub use turn::*;
 mod engine;
 pub use engine::*;
 
-use core::{str::FromStr, fmt::{Display, Debug, Formatter, Result as FmtResult}, ops::{Add, Sub, Not}};
+mod game;
+pub use game::*;
+
+use core::{str::FromStr, convert::TryFrom, fmt::{Display, Debug, Formatter, Result as FmtResult}, ops::{Add, Sub, Not, BitAnd, BitOr, BitXor}};
 use alloc::{boxed::Box, vec};
 
 /// Indicates whether we should insert sanity checks into
@@ -22,6 +25,7 @@ pub const INSERT_SANITY_CHECKS: bool = cfg!(debug_assertions);
 
 /// The type of a piece.
 #[derive(Copy, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum PieceType {
     Pawn,
     Knight,
@@ -108,7 +112,8 @@ impl FromStr for PieceType {
 }
 
 /// A color is either white or black.
-#[derive(Copy, Clone, Default, Debug, PartialEq)]
+#[derive(Copy, Clone, Default, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Color {
     #[default]
     White,
@@ -136,6 +141,7 @@ impl Not for Color {
 
 /// A castling side is either the king side or the queen side.
 #[derive(Copy, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum CastlingSide {
     King,
     Queen,
@@ -172,6 +178,7 @@ impl Debug for CastlingSide {
 }
 
 #[derive(Copy, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Piece(PieceType, Color);
 
 impl Piece {
@@ -225,6 +232,22 @@ impl Piece {
         self.0.get_value()
     }
 
+    /// Get the piece as an ASCII letter, uppercase for white and lowercase for black.
+    /// This renders in terminals and logs that can't show the chess glyphs.
+    pub fn to_ascii(&self) -> char {
+        let letter = char::from(self.get_type());
+        match self.get_color() {
+            Color::White => letter,
+            Color::Black => letter.to_ascii_lowercase(),
+        }
+    }
+
+    /// Get the piece's letter in Forsyth-Edwards Notation.
+    /// FEN uses the same letters as `to_ascii`.
+    pub fn to_fen_char(&self) -> char {
+        self.to_ascii()
+    }
+
     /// Can this piece type move from one tile to another?
     pub fn can_move(&self, from: Tile, to: Tile, is_attack: bool, en_passant_tile: Option<Tile>) -> bool {
         match self.get_type() {
@@ -265,7 +288,8 @@ impl Display for Piece {
 
 /// A chessboard is a 8x8 grid of squares.
 /// The rank is the horizontal row of squares, numbered 0 to 7 from the bottom up.
-#[derive(Copy, Clone, Debug, PartialEq, PartialOrd, Eq, Ord)]
+#[derive(Copy, Clone, Debug, PartialEq, PartialOrd, Eq, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize), serde(transparent))]
 pub struct Rank(u8);
 
 impl Rank {
@@ -297,6 +321,31 @@ impl Rank {
     /// The black pawn starter rank is the rank that black pawns start on.
     pub const PAWN_STARTER_BLACK: Self = Self(6);
 
+    /// The back rank that the given color's pieces start on.
+    #[inline]
+    pub fn back_rank(color: Color) -> Self {
+        match color {
+            Color::White => Self::BACK_RANK_WHITE,
+            Color::Black => Self::BACK_RANK_BLACK,
+        }
+    }
+
+    /// The rank that the given color's pawns start on.
+    #[inline]
+    pub fn pawn_start(color: Color) -> Self {
+        match color {
+            Color::White => Self::PAWN_STARTER_WHITE,
+            Color::Black => Self::PAWN_STARTER_BLACK,
+        }
+    }
+
+    /// The rank that the given color's pawns promote on.
+    /// This is the enemy's back rank.
+    #[inline]
+    pub fn promotion_rank(color: Color) -> Self {
+        Self::back_rank(!color)
+    }
+
     /// Direction of white pawns.
     pub const WHITE_DIRECTION: i8 = 1;
     /// Direction of black pawns.
@@ -310,7 +359,7 @@ impl Rank {
         } else {
             self.0 as i8 + Self::BLACK_DIRECTION * count
         };
-        if result < 0 || result > 7 {
+        if result < 0 || result >= Board::SIZE as i8 {
             panic!("Cannot advance rank {} by {}", self, count);
         }
         Self::from_index(result as u8)
@@ -352,7 +401,7 @@ impl Rank {
     /// The number must be from 0 to 7.
     #[inline]
     const fn from_index(n: u8) -> Self {
-        assert!(n < 8);
+        assert!(n < Board::SIZE);
         Self(n)
     }
 }
@@ -409,7 +458,8 @@ impl Display for Rank {
 
 /// A chessboard is a 8x8 grid of squares.
 /// The file is the vertical column of squares, numbered 0 to 7 from the left.
-#[derive(Copy, Clone, Debug, PartialEq, PartialOrd, Eq, Ord)]
+#[derive(Copy, Clone, Debug, PartialEq, PartialOrd, Eq, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize), serde(transparent))]
 pub struct File(u8);
 
 impl File {
@@ -478,7 +528,7 @@ impl File {
     /// The number must be from 0 to 7.
     #[inline]
     const fn from_index(n: u8) -> Self {
-        assert!(n < 8);
+        assert!(n < Board::SIZE);
         Self(n)
     }
 }
@@ -536,6 +586,7 @@ impl Display for File {
 /// A sector is a 2x2 square of squares.
 /// The board is divided into 16 sectors, numbered 0 to 15 from the bottom left to the top right.
 #[derive(Copy, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Sector(u8);
 
 impl Sector {
@@ -622,6 +673,7 @@ impl From<Tile> for Sector {
 /// A tile set is a set of tiles.
 /// 
 /// This is used to do bitwise operations on tiles.
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
 pub struct TileSet(u64);
 
 impl TileSet {
@@ -645,14 +697,88 @@ impl TileSet {
         self.0.count_ones() as usize
     }
 
-    pub fn into_iter(self) -> impl Iterator<Item = Tile> {
-        (0..64).filter_map(move |i| {
-            if self.0 & (1 << i) != 0 {
-                Some(Tile::from_nth(i as u8))
-            } else {
-                None
-            }
-        })
+    /// Create a set from a bitboard, where bit `n` is the tile `Tile::from_nth(n)`.
+    pub fn from_bits(bits: u64) -> Self {
+        Self(bits)
+    }
+
+    /// Get the bitboard of the tiles in the set.
+    pub fn to_bits(&self) -> u64 {
+        self.0
+    }
+}
+
+/// An iterator over the tiles in a `TileSet`, from a1 to h8.
+#[derive(Copy, Clone, Debug)]
+pub struct TileSetIter(u64);
+
+impl Iterator for TileSetIter {
+    type Item = Tile;
+
+    fn next(&mut self) -> Option<Tile> {
+        if self.0 == 0 {
+            return None;
+        }
+        let tile = Tile::from_nth(self.0.trailing_zeros() as u8);
+        self.0 &= self.0 - 1;
+        Some(tile)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.0.count_ones() as usize;
+        (len, Some(len))
+    }
+}
+
+impl IntoIterator for TileSet {
+    type Item = Tile;
+    type IntoIter = TileSetIter;
+
+    fn into_iter(self) -> TileSetIter {
+        TileSetIter(self.0)
+    }
+}
+
+impl FromIterator<Tile> for TileSet {
+    fn from_iter<I: IntoIterator<Item = Tile>>(tiles: I) -> Self {
+        let mut result = Self::default();
+        for tile in tiles {
+            result.insert(tile);
+        }
+        result
+    }
+}
+
+impl BitOr for TileSet {
+    type Output = Self;
+
+    fn bitor(self, other: Self) -> Self {
+        Self(self.0 | other.0)
+    }
+}
+
+impl BitAnd for TileSet {
+    type Output = Self;
+
+    fn bitand(self, other: Self) -> Self {
+        Self(self.0 & other.0)
+    }
+}
+
+impl BitXor for TileSet {
+    type Output = Self;
+
+    fn bitxor(self, other: Self) -> Self {
+        Self(self.0 ^ other.0)
+    }
+}
+
+impl Not for TileSet {
+    type Output = Self;
+
+    /// Get every tile on the board that isn't in the set.
+    fn not(self) -> Self {
+        Self(!self.0)
     }
 }
 
@@ -661,7 +787,7 @@ impl TileSet {
 /// The location is represented by a rank and a file.
 /// The rank is the horizontal row of squares, numbered 0 to 7 from the bottom up.
 /// The file is the vertical column of squares, numbered 0 to 7 from the left.
-#[derive(Copy, Clone, PartialEq)]
+#[derive(Copy, Clone, PartialEq, Eq, Hash)]
 pub struct Tile(Rank, File);
 
 impl Tile {
@@ -696,6 +822,21 @@ impl Tile {
         }
     }
 
+    /// The tile the king lands on after castling on the given side.
+    #[inline]
+    pub fn castle_destination(color: Color, side: CastlingSide) -> Self {
+        match color {
+            Color::White => match side {
+                CastlingSide::King => Self::WHITE_KINGSIDE_CASTLE_DESTINATION,
+                CastlingSide::Queen => Self::WHITE_QUEENSIDE_CASTLE_DESTINATION,
+            },
+            Color::Black => match side {
+                CastlingSide::King => Self::BLACK_KINGSIDE_CASTLE_DESTINATION,
+                CastlingSide::Queen => Self::BLACK_QUEENSIDE_CASTLE_DESTINATION,
+            },
+        }
+    }
+
     #[inline]
     pub fn rook_start_position(color: Color, side: CastlingSide) -> Self {
         match color {
@@ -738,7 +879,31 @@ impl Tile {
     /// Get an iterator over all the tiles.
     #[inline]
     pub fn all() -> impl Iterator<Item = Self> {
-        (0..64).map(Self::from_nth)
+        (0..Board::TILES as u8).map(Self::from_nth)
+    }
+
+    /// Get all the tiles in the given sector.
+    pub fn in_sector(sector: Sector) -> impl Iterator<Item = Self> {
+        Self::all().filter(move |tile| tile.get_sector() == sector)
+    }
+
+    /// Get all the tiles in the given color's home sectors.
+    pub fn home_tiles(color: Color) -> impl Iterator<Item = Self> {
+        Sector::all()
+            .filter(move |sector| sector.is_home_for(color))
+            .flat_map(Self::in_sector)
+    }
+
+    /// Is this a light square? The corner a1 is dark, and h1 is light.
+    #[inline]
+    pub fn is_light_square(&self) -> bool {
+        (self.get_rank().get_index() + self.get_file().get_index()) % 2 == 1
+    }
+
+    /// Is this a dark square?
+    #[inline]
+    pub fn is_dark_square(&self) -> bool {
+        !self.is_light_square()
     }
 
     /// Advance the tile a pawn's move for a given color and count of tiles.
@@ -754,7 +919,8 @@ impl Tile {
         let file = file.into();
         let new_rank = self.get_rank().get_index() as i8 + rank;
         let new_file = self.get_file().get_index() as i8 + file;
-        if new_rank < 8 && new_file < 8 && new_rank >= 0 && new_file >= 0 {
+        let size = Board::SIZE as i8;
+        if new_rank < size && new_file < size && new_rank >= 0 && new_file >= 0 {
             Some(Self::new(Rank::from_index(new_rank as u8), File::from_index(new_file as u8)))
         } else {
             None
@@ -793,7 +959,7 @@ impl Tile {
 
     #[inline]
     pub fn from_nth(n: u8) -> Self {
-        Self::new(Rank::from_index(n / 8), File::from_index(n % 8))
+        Self::new(Rank::from_index(n / Board::SIZE), File::from_index(n % Board::SIZE))
     }
 
     /// Create a new location from a rank and a file.
@@ -847,6 +1013,30 @@ impl Tile {
     pub fn get_castling_side(&self) -> CastlingSide {
         self.get_file().get_castling_side()
     }
+
+    /// The number of king moves between this tile and another.
+    #[inline]
+    pub fn distance(&self, other: Tile) -> u8 {
+        let rank_distance = self.get_rank().get_index().abs_diff(other.get_rank().get_index());
+        let file_distance = self.get_file().get_index().abs_diff(other.get_file().get_index());
+        rank_distance.max(file_distance)
+    }
+
+    /// The number of rook steps between this tile and another.
+    #[inline]
+    pub fn manhattan_distance(&self, other: Tile) -> u8 {
+        let rank_distance = self.get_rank().get_index().abs_diff(other.get_rank().get_index());
+        let file_distance = self.get_file().get_index().abs_diff(other.get_file().get_index());
+        rank_distance + file_distance
+    }
+
+    /// The number of rook steps between this tile and the closest of the four center tiles.
+    #[inline]
+    pub fn center_distance(&self) -> u8 {
+        let half = Board::SIZE / 2;
+        let distance_from_center = |index: u8| if index < half { half - 1 - index } else { index - half };
+        distance_from_center(self.get_rank().get_index()) + distance_from_center(self.get_file().get_index())
+    }
     
     /// Get the sector of the location.
     /// The sector is the 2x2 square of squares that the location is in.
@@ -868,10 +1058,17 @@ impl Tile {
         Sector(self.get_sector_number())
     }
 
+    /// Get the index of the location, from 0 to 63.
+    /// The index counts from a1 along each rank, so a1 is 0, h1 is 7, and h8 is 63.
+    #[inline]
+    pub fn index(&self) -> u8 {
+        self.get_rank().get_index() * Board::SIZE + self.get_file().get_index()
+    }
+
     /// Convert this location to a bit representation on a bitboard.
     #[inline]
     pub(crate) fn to_bit(&self) -> u64 {
-        1 << (self.get_rank().get_index() * 8 + self.get_file().get_index())
+        1 << self.index()
     }
 
     /// Is this tile a knight move away from the other tile?
@@ -947,12 +1144,7 @@ impl Tile {
         }
 
         // Check if on start rank and is two ranks away.
-        if color == Color::White && my_rank == Rank::PAWN_STARTER_WHITE && my_rank.advance(color, 2) == other_rank && my_file == other_file {
-            return true;
-        }
-
-        // Check if on start rank and is two ranks away.
-        if color == Color::Black && my_rank == Rank::PAWN_STARTER_BLACK && my_rank.advance(color, 2) == other_rank && my_file == other_file {
+        if my_rank == Rank::pawn_start(color) && my_rank.advance(color, 2) == other_rank && my_file == other_file {
             return true;
         }
 
@@ -964,8 +1156,7 @@ impl Tile {
     /// A castling rook is the rook that the king castles with.
     #[inline]
     pub fn is_rook_square(&self, color: Color) -> bool {
-        ((color == Color::White && self.get_rank() == Rank::BACK_RANK_WHITE)
-            || (color == Color::Black && self.get_rank() == Rank::BACK_RANK_BLACK))
+        self.get_rank() == Rank::back_rank(color)
             && (self.get_file() == File::KINGSIDE_ROOK || self.get_file() == File::QUEENSIDE_ROOK)
     }
 
@@ -995,15 +1186,19 @@ impl Tile {
     }
 
     /// The attacking bits for a pawn on this tile.
+    /// A pawn on its promotion rank, which can only get there by being purchased, attacks nothing.
     pub fn pawn_attacking_bits(&self, color: Color) -> u64 {
         let mut bits = 0;
+        if self.get_rank() == Rank::promotion_rank(color) {
+            return bits;
+        }
         let rank = self.get_rank().advance(color, 1).get_index();
         let file = self.get_file().get_index();
         if self.get_file() > File::LEFTMOST {
-            bits |= 1 << (rank * 8 + file - 1);
+            bits |= 1 << (rank * Board::SIZE + file - 1);
         }
         if self.get_file() < File::RIGHTMOST {
-            bits |= 1 << (rank * 8 + file + 1);
+            bits |= 1 << (rank * Board::SIZE + file + 1);
         }
         bits
     }
@@ -1075,6 +1270,30 @@ impl Tile {
         }
     }
 
+    /// The possible tiles a piece might try to move to from this tile, as a bitboard.
+    /// This is a superset of the legal moves, ignoring blockers and castling.
+    fn candidate_move_bits(&self, piece: Piece) -> u64 {
+        match piece.get_type() {
+            PieceType::Pawn => {
+                let direction = match piece.get_color() {
+                    Color::White => Rank::WHITE_DIRECTION,
+                    Color::Black => Rank::BLACK_DIRECTION,
+                };
+                [
+                    self.move_by(direction, 1),
+                    self.move_by(direction, -1),
+                    self.move_by(direction, 0),
+                    self.move_by(direction * 2, 0),
+                ].into_iter().flatten().fold(0, |bits, tile| bits | tile.to_bit())
+            },
+            PieceType::Knight => self.knight_attacking_bits(),
+            PieceType::Bishop => self.bishop_attacking_bits(),
+            PieceType::Rook => self.rook_attacking_bits(),
+            PieceType::Queen => self.queen_attacking_bits(),
+            PieceType::King => self.king_attacking_bits(),
+        }
+    }
+
     /// The possible tiles a pawn might try to move to from this tile.
     fn pawn_moves(&self, color: Color) -> impl Iterator<Item = Tile> {
         // Return the two tiles diagonally in front of the pawn,
@@ -1189,10 +1408,10 @@ impl Tile {
                 if rank == my_rank && file == my_file {
                     continue;
                 }
-                if rank < 0 || rank > 7 || file < 0 || file > 7 {
+                if !(0..Board::SIZE as i8).contains(&rank) || !(0..Board::SIZE as i8).contains(&file) {
                     continue;
                 }
-                bits |= 1 << (rank * 8 + file);
+                bits |= 1 << (rank * Board::SIZE as i8 + file);
             }
         }
         bits
@@ -1233,11 +1452,43 @@ impl FromStr for Tile {
         let mut chars = s.chars();
         let file = chars.next().ok_or(())?;
         let rank = chars.next().ok_or(())?;
+        if !('1'..='8').contains(&rank) || !('a'..='h').contains(&file.to_ascii_lowercase()) {
+            return Err(());
+        }
 
         Ok(Self::new(Rank::from_char(rank), File::from_char(file)))
     }
 }
 
+impl TryFrom<u8> for Tile {
+    type Error = ();
+
+    fn try_from(index: u8) -> Result<Self, Self::Error> {
+        if (index as usize) < Board::TILES {
+            Ok(Self::from_nth(index))
+        } else {
+            Err(())
+        }
+    }
+}
+
+/// Tiles are serialized by name, like `"e4"`.
+#[cfg(feature = "serde")]
+impl serde::Serialize for Tile {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for Tile {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        use serde::de::Error;
+        let name = alloc::string::String::deserialize(deserializer)?;
+        Tile::from_str(&name).map_err(|_| D::Error::custom(alloc::format!("invalid tile {name:?}")))
+    }
+}
+
 impl Display for Tile {
     fn fmt(&self, f: &mut Formatter) -> FmtResult {
         write!(f, "{}{}", self.get_file(), self.get_rank())
@@ -4,24 +4,31 @@ use std::{str::FromStr, io::{stdin, stdout, Write}};
 fn main() {
     env_logger::init();
 
-    // Set the thread stack size with rayon
-    rayon::ThreadPoolBuilder::new().stack_size(1024 * 1024 * 1024).build_global().unwrap();
-
-    let mut board = StateCapitalistBoard::default();
+    let mut board = StateCapitalistBoard::default().with_logging();
 
     // Loop and read moves from stdin
     loop {
+        if let Some(result) = board.is_game_over() {
+            println!("{board}");
+            println!("Game over: {result:?}");
+            print!("{}", board.game_log());
+            break;
+        }
+
         let legal_moves = SimpleEngine.legal_moves(&board);
         for (i, legal_move) in legal_moves.iter().enumerate() {
             let cost = board.get_bank(board.whose_turn()).get_market().get_move_value(legal_move);
             println!("{i}. {legal_move:?} ({cost})", i=i+1);
         }
-        println!("{board}");
+        print!("{}", board.pretty_print());
 
         if board.whose_turn() == Color::Black {
             eprintln!("Engine is thinking...");
-            let result = SimpleEngine.best_move(&board).unwrap();
+            // The first move of the line the engine expects is its best move
+            let line = SimpleEngine.principal_variation(&board, 4);
+            let result = line.first().cloned().unwrap();
             println!("Engine move: {result:?}");
+            println!("Engine expects: {}", line.iter().map(|player_move| player_move.to_string()).collect::<Vec<_>>().join(" "));
             board.apply(result).unwrap();
             continue;
         }
@@ -38,6 +45,12 @@ fn main() {
             break;
         }
 
+        // Print the moves played so far
+        if input == "log" {
+            print!("{}", board.game_log());
+            continue;
+        }
+
 
         if let Ok(player_move) = Move::from_str(input) {
             println!("{player_move:?}");
@@ -1,12 +1,68 @@
 use core::{str::FromStr, fmt::{Debug, Display, Formatter, Result as FmtResult}};
-use alloc::{vec::Vec, vec};
+use alloc::{vec::Vec, vec, string::String, format};
 
-use super::{Tile, Board, Bank, CastlingSide, PieceType};
+use super::{Tile, Board, Bank, Market, CastlingSide, PieceType, Sector, File, Rank};
 // pub struct Turn {
 //     white_move: Move,
 //     black_move: Move,
 // }
 
+/// The file or rank a piece moves from, when more than one piece
+/// of the same type could make a `PieceTo` move, like the `b` in `Nbd2`
+/// or the `1` in `R1a3`. The default hint doesn't narrow down the piece.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Hint {
+    pub file: Option<File>,
+    pub rank: Option<Rank>,
+}
+
+impl Hint {
+    /// Is the given tile on the hinted file and rank?
+    pub fn matches(&self, tile: Tile) -> bool {
+        self.file.is_none_or(|file| tile.get_file() == file)
+            && self.rank.is_none_or(|rank| tile.get_rank() == rank)
+    }
+}
+
+impl Display for Hint {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        if let Some(file) = self.file {
+            write!(f, "{file}")?;
+        }
+        if let Some(rank) = self.rank {
+            write!(f, "{rank}")?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for Hint {
+    type Err = ();
+
+    /// Parse a file, a rank, or both, like `b`, `1`, or `b1`. An empty hint is allowed.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut result = Self::default();
+        let mut chars = s.bytes().peekable();
+        if let Some(c @ b'a'..=b'h') = chars.peek().copied() {
+            result.file = Some(File::from_index(c - b'a'));
+            chars.next();
+        }
+        if let Some(c @ b'1'..=b'8') = chars.peek().copied() {
+            result.rank = Some(Rank::from_index(c - b'1'));
+            chars.next();
+        }
+        if chars.next().is_some() {
+            return Err(());
+        }
+        Ok(result)
+    }
+}
+
+/// A player's turn.
+/// A turn is a single action, like a board move or a purchase, a pass, a
+/// resignation, or `Many` actions in a row. Every action after the first
+/// is an extra move the player buys at the market's interest rate.
 #[derive(Clone, PartialEq)]
 pub enum Move {
     FromTo {
@@ -16,6 +72,8 @@ pub enum Move {
     },
     PieceTo {
         piece: PieceType,
+        /// Which piece moves, when more than one could, like the `b` in `Nbd2`.
+        hint: Hint,
         to: Tile,
         promotion: Option<PieceType>,
     },
@@ -23,13 +81,26 @@ pub enum Move {
         piece: PieceType,
         to: Tile,
     },
+    /// Buy a piece into the player's reserve, to be deployed later.
+    Reserve(PieceType),
+    /// Place a piece from the player's reserve onto the board.
+    Deploy {
+        piece: PieceType,
+        to: Tile,
+    },
     Castling(CastlingSide),
     Resign,
+    /// Claim a draw, once the game has gone `Board::PROGRESS_DRAW_PLIES` plies without progress.
+    ClaimDraw,
     Pass,
     Many(Vec<Move>),
 }
 
 impl Move {
+    /// The least priority of a capture in `capture_priority`,
+    /// above the priority of any move that doesn't capture.
+    const CAPTURE_PRIORITY: i32 = 1_000_000;
+
     /// Create a new move from a tile to a tile
     pub fn new(from: Tile, to: Tile, promotion: Option<PieceType>) -> Self {
         Self::FromTo {
@@ -39,25 +110,338 @@ impl Move {
         }
     }
 
+    /// Rewrite this move into its canonical form on the given board.
+    /// A king moving onto its castling square or rook becomes a `Castling` move,
+    /// and a `PieceTo` move becomes a `FromTo` move from the eligible piece.
+    pub fn normalize(&self, board: &Board) -> Self {
+        match self {
+            Self::FromTo { from, to, .. } if board.is_castling_move(*from, *to) => {
+                Self::Castling(to.get_castling_side())
+            },
+            Self::PieceTo { piece, hint, to, promotion } => {
+                match board.get_eligible_piece(*piece, *hint, *to) {
+                    Some(from) => Self::new(from, *to, *promotion).normalize(board),
+                    None => self.clone(),
+                }
+            },
+            Self::Many(moves) => {
+                let mut copy = *board;
+                let turn = board.whose_turn();
+                Self::Many(moves.iter().map(|player_move| {
+                    copy.set_turn(turn);
+                    let normalized = player_move.normalize(&copy);
+                    let _ = copy.apply(normalized.clone());
+                    normalized
+                }).collect())
+            },
+            _ => self.clone(),
+        }
+    }
+
+    /// Parse a move in UCI long algebraic notation, like `e2e4` or `e7e8q`.
+    /// Unlike `from_str`, this only accepts coordinates, with an optional
+    /// lowercase promotion. Castling is the king's move, like `e1g1`, and
+    /// `0000` is a pass. This returns None if the string isn't a UCI move.
+    pub fn from_uci(s: &str) -> Option<Self> {
+        if s == "0000" {
+            return Some(Self::Pass);
+        }
+        if !s.is_ascii() || !(4..=5).contains(&s.len()) {
+            return None;
+        }
+
+        let from = Tile::from_str(&s[0..2]).ok()?;
+        let to = Tile::from_str(&s[2..4]).ok()?;
+        let promotion = match &s[4..] {
+            "" => None,
+            "n" => Some(PieceType::Knight),
+            "b" => Some(PieceType::Bishop),
+            "r" => Some(PieceType::Rook),
+            "q" => Some(PieceType::Queen),
+            _ => return None,
+        };
+        Some(Self::new(from, to, promotion))
+    }
+
+    /// Parse a move in Standard Algebraic Notation, like `Nf3`, `exd5`, `O-O`, or `e8=Q`.
+    /// The board is needed to find which piece moves, so this returns an error if the move
+    /// isn't legal there, or if more than one piece could make it. Captures must be
+    /// marked with `x`, and moves that don't capture must not be, so `Ne5` doesn't
+    /// match a knight taking on e5. Check and annotation marks like `+`, `#`, and `!` are ignored.
+    ///
+    /// This also reads the moves `to_san` writes without SAN: purchases like `$Ne4`,
+    /// reserving and deploying like `$N` and `N@e4`, passing, resigning, and claiming a draw,
+    /// and a combination of moves separated by spaces, each read on the board the last one left.
+    pub fn from_san(s: &str, board: &Board) -> Result<Self, SanError> {
+        let s = s.trim();
+        if s.contains(char::is_whitespace) {
+            let turn = board.whose_turn();
+            let mut copy = *board;
+            let mut moves = Vec::new();
+            for word in s.split_whitespace() {
+                copy.set_turn(turn);
+                let player_move = Self::from_san(word, &copy)?;
+                copy.apply(player_move.clone()).map_err(|_| SanError::Illegal)?;
+                moves.push(player_move);
+            }
+            return Ok(Self::Many(moves));
+        }
+
+        let s = s.trim_end_matches(['+', '#', '!', '?']);
+        if s.starts_with('$') || s.contains('@') || ["pass", "resign", "draw"].contains(&s) {
+            let player_move = Self::from_str(s).map_err(|_| SanError::Malformed)?;
+            // Whether a player can pass depends on what the market charges, which the board doesn't know
+            if player_move != Self::Pass && !board.is_legal_move(&player_move) {
+                return Err(SanError::Illegal);
+            }
+            return Ok(player_move);
+        }
+        if let Ok(side) = CastlingSide::from_str(&s.replace('0', "O")) {
+            let player_move = Self::Castling(side);
+            if !board.is_legal_move(&player_move) {
+                return Err(SanError::Illegal);
+            }
+            return Ok(player_move);
+        }
+        if !s.is_ascii() || s.len() < 2 {
+            return Err(SanError::Malformed);
+        }
+
+        // Split off the piece, the promotion, and the destination
+        let (piece, rest) = match s.as_bytes()[0] {
+            b'N' | b'B' | b'R' | b'Q' | b'K' => (PieceType::from_str(&s[0..1]).map_err(|_| SanError::Malformed)?, &s[1..]),
+            _ => (PieceType::Pawn, s),
+        };
+        let (rest, promotion) = match rest.len().checked_sub(1).map(|i| rest.split_at(i)) {
+            Some((rest, letter)) if piece == PieceType::Pawn && letter.as_bytes()[0].is_ascii_uppercase() => {
+                let promotion = PieceType::from_str(letter).map_err(|_| SanError::Malformed)?;
+                (rest.strip_suffix('=').unwrap_or(rest), Some(promotion))
+            },
+            _ => (rest, None),
+        };
+        let (rest, to) = rest.split_at(rest.len().checked_sub(2).ok_or(SanError::Malformed)?);
+        let to = Tile::from_str(to).map_err(|_| SanError::Malformed)?;
+        let (hint, capture) = match rest.strip_suffix('x') {
+            Some(hint) => (hint, true),
+            None => (rest, false),
+        };
+        if hint.len() > 2 || !hint.bytes().all(|c| matches!(c, b'a'..=b'h' | b'1'..=b'8')) {
+            return Err(SanError::Malformed);
+        }
+
+        let mut candidates = board.legal_moves().into_iter().filter(|player_move| match player_move {
+            Self::FromTo { from, to: dest, promotion: p } => {
+                *dest == to
+                    && *p == promotion
+                    && board.get_piece(*from).map(|piece| piece.get_type()) == Some(piece)
+                    && hint.chars().all(|c| from.to_string().contains(c))
+                    && player_move.is_capture(board) == capture
+            },
+            _ => false,
+        });
+        let result = candidates.next().ok_or(SanError::Illegal)?;
+        if candidates.next().is_some() {
+            // Ambiguous, like `Nd2` when both knights can reach it
+            return Err(SanError::Ambiguous);
+        }
+        Ok(result)
+    }
+
+    /// Write this move in Standard Algebraic Notation for the given board, like `Nbd2`,
+    /// `exd5`, `e8=Q+`, or `O-O#`. This is the notation read by `from_san`.
+    ///
+    /// Purchases have no SAN equivalent, so they're written with a dollar sign,
+    /// the piece, and the tile, like `$Ne4`, and reserving and deploying are written
+    /// like `$N` and `N@e4`, the same as `Display` and `from_str`. Purchases and
+    /// deployments are marked with check and checkmate too, like `$Qe4+`. A combination
+    /// of moves is written one move after another, separated by spaces.
+    pub fn to_san(&self, board: &Board) -> String {
+        let player_move = self.normalize(board);
+        let san = match &player_move {
+            Self::FromTo { from, to, promotion } => {
+                let Some(piece) = board.get_piece(*from).map(|piece| piece.get_type()) else {
+                    return player_move.to_string();
+                };
+                let from_name = from.to_string();
+                let (file, rank) = from_name.split_at(1);
+                let capture = if player_move.is_capture(board) { "x" } else { "" };
+                let promotion = promotion.map(|p| format!("={}", char::from(p))).unwrap_or_default();
+
+                let hint = if piece == PieceType::Pawn {
+                    if capture.is_empty() { "" } else { file }
+                } else {
+                    // Other pieces of the same type that can reach the same tile
+                    let rivals: Vec<Tile> = board.legal_moves().into_iter().filter_map(|other| match other {
+                        Self::FromTo { from: other, to: dest, .. } if dest == *to && other != *from
+                            && board.get_piece(other).map(|piece| piece.get_type()) == Some(piece) => Some(other),
+                        _ => None,
+                    }).collect();
+                    if rivals.is_empty() {
+                        ""
+                    } else if rivals.iter().all(|other| !other.to_string().starts_with(file)) {
+                        file
+                    } else if rivals.iter().all(|other| !other.to_string().ends_with(rank)) {
+                        rank
+                    } else {
+                        &from_name
+                    }
+                };
+                let letter = if piece == PieceType::Pawn { String::new() } else { char::from(piece).to_string() };
+                format!("{letter}{hint}{capture}{to}{promotion}")
+            },
+            Self::Castling(side) => side.to_string(),
+            Self::Many(moves) => {
+                let mut copy = *board;
+                let turn = board.whose_turn();
+                return moves.iter().map(|player_move| {
+                    copy.set_turn(turn);
+                    let san = player_move.to_san(&copy);
+                    let _ = copy.apply(player_move.clone());
+                    san
+                }).collect::<Vec<_>>().join(" ");
+            },
+            // A purchase or deployment can give check like any other move
+            Self::Purchase { .. } | Self::Deploy { .. } => player_move.to_string(),
+            _ => return player_move.to_string(),
+        };
+
+        // Mark checks and checkmates
+        let color = board.whose_turn();
+        let mut after = *board;
+        if after.apply(player_move).is_err() {
+            san
+        } else if after.is_in_checkmate(!color) {
+            san + "#"
+        } else if after.is_in_check(!color) {
+            san + "+"
+        } else {
+            san
+        }
+    }
+
+    /// Parse the piece a pawn promotes to, in either case, like `Q` or `q`.
+    /// Pawns can't promote to kings or stay pawns, so those are errors.
+    fn parse_promotion(letter: &str) -> Result<PieceType, ()> {
+        let piece = PieceType::from_str(&letter.to_ascii_uppercase())?;
+        if !PieceType::PROMOTIONS.contains(&piece) {
+            return Err(());
+        }
+        Ok(piece)
+    }
+
+    /// Write this move in UCI long algebraic notation for the given board.
+    /// Piece moves and castling are resolved to coordinates, and a pass is `0000`.
+    /// Purchases, resignations, and multiple moves have no UCI form, so they return None.
+    pub fn to_uci(&self, board: &Board) -> Option<String> {
+        match self.normalize(board) {
+            Self::FromTo { from, to, promotion } => {
+                let promotion = promotion.map(|p| char::from(p).to_ascii_lowercase());
+                Some(format!("{from}{to}{}", promotion.map(String::from).unwrap_or_default()))
+            },
+            Self::Castling(side) => {
+                let color = board.whose_turn();
+                Some(format!("{}{}", Tile::king_start_position(color), Tile::castle_destination(color, side)))
+            },
+            Self::Pass => Some(String::from("0000")),
+            _ => None,
+        }
+    }
+
+    /// Do these two moves have the same effect on the given board?
+    /// Unlike `==`, this treats different encodings of the same move as equal.
+    pub fn same_effect(&self, other: &Self, board: &Board) -> bool {
+        self.normalize(board) == other.normalize(board)
+    }
+
+    /// Does this move capture one of the opponent's pieces on the given board?
+    /// Purchases never capture, but a combination of moves captures
+    /// if any of its moves do, like buying a piece and then recapturing.
+    pub fn is_capture(&self, board: &Board) -> bool {
+        match self.normalize(board) {
+            Self::FromTo { from, to, .. } => {
+                board.get_piece(to).is_some_and(|piece| piece.get_color() != board.whose_turn())
+                    || board.is_en_passant_capture(from, to)
+            },
+            Self::Many(moves) => {
+                let mut copy = *board;
+                let turn = board.whose_turn();
+                moves.into_iter().any(|player_move| {
+                    copy.set_turn(turn);
+                    let is_capture = player_move.is_capture(&copy);
+                    let _ = copy.apply(player_move);
+                    is_capture
+                })
+            },
+            _ => false,
+        }
+    }
+
+    /// How early this move should be searched, highest first, by most valuable
+    /// victim and least valuable attacker. Captures come before every other
+    /// move, taking the most valuable piece first, and then with the least
+    /// valuable piece. Purchases and promotions come next, by the value
+    /// of the piece they add to the board, and then every other move.
+    /// The pieces are priced by the market.
+    pub fn capture_priority(&self, board: &Board, market: &Market) -> i32 {
+        let cents = |piece: PieceType| market.get_piece_value(piece).get_amount();
+        match self.normalize(board) {
+            Self::FromTo { from, to, promotion } => {
+                let attacker = board.get_piece(from).map(|piece| cents(piece.get_type())).unwrap_or_default();
+                let promotion = promotion.map(cents).unwrap_or_default();
+                match board.get_piece(to) {
+                    Some(victim) if victim.get_color() != board.whose_turn() => {
+                        Self::CAPTURE_PRIORITY + cents(victim.get_type()) * 100 - attacker + promotion
+                    },
+                    _ if board.is_en_passant_capture(from, to) => {
+                        Self::CAPTURE_PRIORITY + cents(PieceType::Pawn) * 100 - attacker
+                    },
+                    _ => promotion,
+                }
+            },
+            Self::Purchase { piece, .. } | Self::Deploy { piece, .. } => cents(piece),
+            Self::Many(moves) => {
+                let mut copy = *board;
+                let turn = board.whose_turn();
+                moves.into_iter().map(|player_move| {
+                    copy.set_turn(turn);
+                    let priority = player_move.capture_priority(&copy, market);
+                    let _ = copy.apply(player_move);
+                    priority
+                }).max().unwrap_or_default()
+            },
+            _ => 0,
+        }
+    }
+
+    /// The number of actions this move takes.
+    /// A combination of moves takes one action for each of its moves.
+    pub fn action_count(&self) -> usize {
+        match self {
+            Self::Many(moves) => moves.len(),
+            _ => 1,
+        }
+    }
+
     /// Generate all the legal moves for a given player on the board
     pub fn legal_moves(board: &Board) -> Vec<Move> {
         let mut result = vec![];
+        if board.get_winner().is_some() || board.is_drawn() {
+            return result;
+        }
 
         let turn = board.whose_turn();
+        // Each candidate is tried and taken back on this one copy
+        let mut scratch = *board;
 
-        for tile in Tile::all() {
-            if let Some(piece) = board.get_piece(tile) {
-                if piece.get_color() == turn {
-                    for to in tile.get_moves(piece) {
-                        if board.is_legal_piece_move(tile, to) {
-                            if board.is_valid_promotion(tile, to) {
-                                for piece_type in PieceType::PROMOTIONS {
-                                    result.push(Move::new(tile, to, Some(piece_type)));
-                                }
-                            } else {
-                                result.push(Move::new(tile, to, None));
-                            }
+        for (tile, piece) in board.iter_pieces_of(turn) {
+            for to in tile.get_moves(piece) {
+                if scratch.is_legal_piece_move_in_place(tile, to) {
+                    if board.is_valid_promotion(tile, to) {
+                        for piece_type in PieceType::PROMOTIONS {
+                            result.push(Move::new(tile, to, Some(piece_type)));
                         }
+                    } else {
+                        result.push(Move::new(tile, to, None));
                     }
                 }
             }
@@ -77,16 +461,41 @@ impl Move {
         result
     }
 
-    /// Generate all the legal purchases for a given player on the board
+    /// Generate all the legal purchases for a given player on the board.
+    /// Only the piece types the market allows are purchased.
+    /// If the market buys pieces into the reserve, these are the pieces the
+    /// player can reserve, and the deployments of the pieces already reserved.
     pub fn legal_purchases(board: &Board, bank: &Bank) -> Vec<Move> {
         let mut result = vec![];
 
-        for to in Tile::all() {
-            if !board.has_piece_on(to) {
-                for piece in PieceType::PURCHASES {
-                    let player_move = Move::Purchase {piece, to};
-                    if to.get_sector().is_home_for(bank.get_color()) && bank.can_afford(&player_move) && !board.is_in_check(board.whose_turn()) {
-                        result.push(player_move);
+        let market = bank.get_market();
+        if market.get_purchase_into_reserve() {
+            for piece in market.get_purchasable_pieces() {
+                let player_move = Move::Reserve(piece);
+                if bank.can_afford(&player_move) && board.is_legal_move(&player_move) {
+                    result.push(player_move);
+                }
+            }
+        }
+
+        for sector in Sector::all().filter(|sector| market.can_purchase_in(board, *sector, bank.get_color())) {
+            for to in Tile::in_sector(sector) {
+                if !board.has_piece_on(to) {
+                    for piece in PieceType::PURCHASES {
+                        let player_move = if market.get_purchase_into_reserve() {
+                            if bank.get_reserve_count(piece) == 0 {
+                                continue;
+                            }
+                            Move::Deploy {piece, to}
+                        } else {
+                            if !market.can_purchase_piece(piece) {
+                                continue;
+                            }
+                            Move::Purchase {piece, to}
+                        };
+                        if bank.can_afford(&player_move) && board.is_legal_move(&player_move) {
+                            result.push(player_move);
+                        }
                     }
                 }
             }
@@ -96,17 +505,61 @@ impl Move {
     }
 }
 
+/// A reason a move in Standard Algebraic Notation couldn't be read.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum SanError {
+    /// The move isn't written in SAN.
+    Malformed,
+    /// No legal move on the board matches the notation.
+    Illegal,
+    /// More than one legal move matches the notation, like `Nd2` when both knights can reach it.
+    Ambiguous,
+}
+
+impl Display for SanError {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        match self {
+            Self::Malformed => write!(f, "the move is not written in SAN"),
+            Self::Illegal => write!(f, "no legal move matches"),
+            Self::Ambiguous => write!(f, "more than one legal move matches"),
+        }
+    }
+}
+
+/// Moves are serialized as they're written, like `"e2e4 $Nc3"`.
+#[cfg(feature = "serde")]
+impl serde::Serialize for Move {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for Move {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        use serde::de::Error;
+        let text = String::deserialize(deserializer)?;
+        Move::from_str(&text).map_err(|_| D::Error::custom(format!("invalid move {text:?}")))
+    }
+}
+
 impl Display for Move {
     fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
         match self {
             Self::FromTo { from, to, promotion } => {
-                write!(f, "{}{}{}", from, to, promotion.map(|p| char::from(p)).unwrap_or_default())
+                write!(f, "{}{}{}", from, to, promotion.map(|p| char::from(p).to_string()).unwrap_or_default())
             },
-            Self::PieceTo { piece, to, promotion } => {
-                write!(f, "{}{}{}", char::from(*piece), to, promotion.map(|p| char::from(p)).unwrap_or_default())
+            Self::PieceTo { piece, hint, to, promotion } => {
+                write!(f, "{}{}{}{}", char::from(*piece), hint, to, promotion.map(|p| char::from(p).to_string()).unwrap_or_default())
             },
             Self::Purchase { piece, to } => {
-                write!(f, "{}{}", char::from(*piece), to)
+                write!(f, "${}{}", char::from(*piece), to)
+            },
+            Self::Reserve(piece) => {
+                write!(f, "${}", char::from(*piece))
+            },
+            Self::Deploy { piece, to } => {
+                write!(f, "{}@{}", char::from(*piece), to)
             },
             Self::Castling(side) => {
                 write!(f, "{}", side)
@@ -114,6 +567,9 @@ impl Display for Move {
             Self::Resign => {
                 write!(f, "resign")
             },
+            Self::ClaimDraw => {
+                write!(f, "draw")
+            },
             Self::Pass => {
                 write!(f, "pass")
             },
@@ -140,8 +596,8 @@ impl Debug for Move {
                 }
             },
 
-            Self::PieceTo { piece, to, promotion } => {
-                write!(f, "move {} to {to}", char::from(*piece))?;
+            Self::PieceTo { piece, hint, to, promotion } => {
+                write!(f, "move {}{hint} to {to}", char::from(*piece))?;
                 if let Some(promotion) = promotion {
                     write!(f, " and promote to {}", char::from(*promotion))?;
                 }
@@ -151,6 +607,14 @@ impl Debug for Move {
                 write!(f, "purchase {} at {to}", char::from(*piece))?
             },
 
+            Self::Reserve(piece) => {
+                write!(f, "reserve {}", char::from(*piece))?
+            },
+
+            Self::Deploy { piece, to } => {
+                write!(f, "deploy {} at {to}", char::from(*piece))?
+            },
+
             Self::Castling(side) => {
                 write!(f, "castling {side}")?
             },
@@ -159,6 +623,10 @@ impl Debug for Move {
                 write!(f, "resign")?
             },
 
+            Self::ClaimDraw => {
+                write!(f, "claim a draw")?
+            },
+
             Self::Pass => {
                 write!(f, "pass")?
             },
@@ -183,28 +651,72 @@ impl FromStr for Move {
         let mut moves = Vec::new();
         let words = s.split_whitespace();
         for word in words {
+            // These can be part of a combination of moves, so they don't end the parse
             if word == "O-O" || word == "O-O-O" {
-                return Ok(Move::Castling(word.parse().unwrap()));
+                moves.push(Move::Castling(word.parse()?));
+                continue;
             }
 
             if word == "resign" {
-                return Ok(Move::Resign);
+                moves.push(Move::Resign);
+                continue;
             }
 
             if word == "pass" {
-                return Ok(Move::Pass);
+                moves.push(Move::Pass);
+                continue;
+            }
+
+            if word == "draw" {
+                moves.push(Move::ClaimDraw);
+                continue;
             }
 
-            if word.starts_with("$") {
-                let piece = PieceType::from_str(&word[1..2]).unwrap();
-                let to = Tile::from_str(&word[2..4]).unwrap();
+            // Moves are written in ASCII, so anything else can't be sliced into tiles
+            if !word.is_ascii() {
+                return Err(());
+            }
+
+            if word.starts_with("$") && word.len() == 2 {
+                let piece = PieceType::from_str(&word[1..2])?;
+                moves.push(Move::Reserve(piece));
+                continue;
+            }
+
+            if word.len() == 4 && word.as_bytes()[1] == b'@' {
+                let piece = PieceType::from_str(&word[0..1])?;
+                let to = Tile::from_str(&word[2..4])?;
+                moves.push(Move::Deploy {piece, to});
+                continue;
+            }
+
+            if word.starts_with("$") && word.len() == 4 {
+                let piece = PieceType::from_str(&word[1..2])?;
+                let to = Tile::from_str(&word[2..4])?;
                 moves.push(Move::Purchase {piece, to});
                 continue;
             }
 
+            // A piece move, as written by `Display`, like `Nf3`, `Nbd2`, or `Pe8Q`
+            if word.as_bytes()[0].is_ascii_uppercase() {
+                let piece = PieceType::from_str(&word[0..1])?;
+                let (rest, promotion) = match word[1..].strip_suffix(|c: char| c.is_ascii_uppercase()) {
+                    Some(rest) => (rest, Some(Self::parse_promotion(&word[word.len() - 1..])?)),
+                    None => (&word[1..], None),
+                };
+                let (hint, to) = rest.split_at(rest.len().checked_sub(2).ok_or(())?);
+                moves.push(Move::PieceTo {
+                    piece,
+                    hint: hint.parse()?,
+                    to: Tile::from_str(to)?,
+                    promotion,
+                });
+                continue;
+            }
+
             if word.len() == 4 {
-                let from = Tile::from_str(&word[0..2]).unwrap();
-                let to = Tile::from_str(&word[2..4]).unwrap();
+                let from = Tile::from_str(&word[0..2])?;
+                let to = Tile::from_str(&word[2..4])?;
                 moves.push(Move::FromTo {
                     from,
                     to,
@@ -213,22 +725,34 @@ impl FromStr for Move {
                 continue;
             }
 
-            if word.len() == 3 {
-                let piece = PieceType::from_str(&word[0..1]).unwrap();
-                let to = Tile::from_str(&word[1..3]).unwrap();
+            // A move with a promotion, as written by `Display`, like `e7e8Q`
+            if word.len() == 5 {
+                let from = Tile::from_str(&word[0..2])?;
+                let to = Tile::from_str(&word[2..4])?;
+                let promotion = Self::parse_promotion(&word[4..5])?;
+                moves.push(Move::new(from, to, Some(promotion)));
+                continue;
+            }
+
+            // A pawn move with a promotion, like `e8Q`
+            if word.len() == 3 && word.as_bytes()[0].is_ascii_lowercase() {
+                let to = Tile::from_str(&word[0..2])?;
+                let promotion = Self::parse_promotion(&word[2..3])?;
                 moves.push(Move::PieceTo {
-                    piece,
+                    piece: PieceType::Pawn,
+                    hint: Hint::default(),
                     to,
-                    promotion: None,
+                    promotion: Some(promotion),
                 });
                 continue;
             }
 
             if word.len() == 2 {
                 let piece = PieceType::Pawn;
-                let to = Tile::from_str(&word[0..2]).unwrap();
+                let to = Tile::from_str(&word[0..2])?;
                 moves.push(Move::PieceTo {
                     piece,
+                    hint: Hint::default(),
                     to,
                     promotion: None,
                 });
@@ -4,7 +4,7 @@
  * move to legal locations.
  */
 
-use eco_chess::*;
+use capitalist_chess::*;
 use std::str::FromStr;
 
 static mut ALREADY_INIT: bool = false;
@@ -127,6 +127,7 @@ fn pawn_promotion() -> Result<(), ()> {
     board.spawn_black_pawn(Tile::from_str("f2")?);
     board.apply(Move::PieceTo {
         piece: PieceType::Pawn,
+        hint: Hint::default(),
         to: Tile::from_str("e8")?,
         promotion: Some(PieceType::Queen),
     })?;
@@ -140,6 +141,7 @@ fn pawn_promotion() -> Result<(), ()> {
     board.spawn_black_pawn(Tile::from_str("f2")?);
     board.apply(Move::PieceTo {
         piece: PieceType::Pawn,
+        hint: Hint::default(),
         to: Tile::from_str("e8")?,
         promotion: Some(PieceType::Knight),
     })?;
@@ -349,4 +351,1291 @@ fn bishop_movement() -> Result<(), ()> {
     board.apply(Move::from_str("b4d6")?)?;
     
     Ok(())
-}
\ No newline at end of file
+}
+
+/// Test finding the moves that checkmate in one.
+#[test]
+fn checkmate_in_one() -> Result<(), ()> {
+    init();
+    let mut board = Board::empty();
+    board.spawn_white_king(Tile::from_str("e1")?);
+    board.spawn_white_rook(Tile::from_str("a1")?);
+    board.spawn_black_king(Tile::from_str("h8")?);
+    board.spawn_black_pawn(Tile::from_str("g7")?);
+    board.spawn_black_pawn(Tile::from_str("h7")?);
+
+    // Only the back rank mate works
+    assert_eq!(board.checkmate_in_one_moves(Color::White), vec![Move::from_str("a1a8")?]);
+    assert!(board.gives_checkmate(&Move::from_str("a1a8")?));
+
+    // Give the black king an escape square
+    board.remove_piece(Tile::from_str("h7")?);
+    assert!(board.checkmate_in_one_moves(Color::White).is_empty());
+
+    Ok(())
+}
+
+/// Test the board geometry constants.
+#[test]
+fn board_dimensions() {
+    assert_eq!(Tile::all().count(), Board::TILES);
+    assert_eq!(Board::TILES, Board::SIZE as usize * Board::SIZE as usize);
+}
+
+/// Test converting tiles to and from their indices.
+#[test]
+fn tile_indices() -> Result<(), ()> {
+    for (i, tile) in Tile::all().enumerate() {
+        assert_eq!(tile.index() as usize, i);
+        assert_eq!(Tile::try_from(tile.index())?, tile);
+    }
+    assert_eq!(Tile::from_str("a1")?.index(), 0);
+    assert_eq!(Tile::from_str("h8")?.index(), 63);
+    assert!(Tile::try_from(64).is_err());
+
+    Ok(())
+}
+
+/// Test hiding enemy pieces in a fog of war view.
+#[test]
+fn fog_of_war() -> Result<(), ()> {
+    init();
+    let mut board = Board::empty();
+    board.spawn_white_king(Tile::from_str("e1")?);
+    board.spawn_white_rook(Tile::from_str("a1")?);
+    board.spawn_black_king(Tile::from_str("e8")?);
+    board.spawn_black_pawn(Tile::from_str("a7")?);
+    board.spawn_black_knight(Tile::from_str("h5")?);
+
+    let view = board.visible_to(Color::White);
+    // The rook sees the pawn up the a-file
+    assert_eq!(view.get_piece(Tile::from_str("a7")?), Some(Piece::pawn(Color::Black)));
+    // Nothing sees the knight
+    assert_eq!(view.get_piece(Tile::from_str("h5")?), None);
+    // Friendly pieces are always visible
+    assert_eq!(view.get_piece(Tile::from_str("a1")?), Some(Piece::rook(Color::White)));
+
+    Ok(())
+}
+
+/// Test querying castling rights by color and side.
+#[test]
+fn castling_rights_queries() -> Result<(), ()> {
+    let mut rights = CastlingRights::default();
+    for color in [Color::White, Color::Black] {
+        assert_eq!(rights.for_color(color), (true, true));
+    }
+
+    rights.disable_castling(Tile::from_str("e1")?, Tile::from_str("h1")?);
+    assert!(!rights.has(Color::White, CastlingSide::King));
+    assert!(rights.has(Color::White, CastlingSide::Queen));
+    assert_eq!(rights.for_color(Color::White), (false, true));
+    assert_eq!(rights.for_color(Color::Black), (true, true));
+    assert_eq!(CastlingRights::none().for_color(Color::Black), (false, false));
+
+    Ok(())
+}
+
+/// Test the material view agrees with the board's material.
+#[test]
+fn material_view() -> Result<(), ()> {
+    let market = Market::default();
+    let mut board = Board::default();
+    board.remove_piece(Tile::from_str("d8")?);
+    board.remove_piece(Tile::from_str("a2")?);
+    let view = board.material_view();
+
+    for color in [Color::White, Color::Black] {
+        assert_eq!(view.market_value(color, &market), board.material(color, &market));
+    }
+    assert_eq!(view.count(Piece::queen(Color::Black)), 0);
+    assert_eq!(view.count(Piece::pawn(Color::White)), 7);
+    assert_eq!(view.count(Piece::pawn(Color::Black)), 8);
+    assert!(board.material(Color::White, &market) > board.material(Color::Black, &market));
+
+    Ok(())
+}
+
+/// Test that purchases can never put the buyer in check.
+#[test]
+fn purchase_never_self_checks() -> Result<(), ()> {
+    init();
+    let mut board = Board::empty();
+    board.spawn_white_king(Tile::from_str("e1")?);
+    board.spawn_black_king(Tile::from_str("e8")?);
+    board.spawn_black_rook(Tile::from_str("a8")?);
+    board.spawn_black_bishop(Tile::from_str("h5")?);
+
+    for to in Tile::all() {
+        for piece in [PieceType::Pawn, PieceType::Knight, PieceType::Bishop, PieceType::Rook, PieceType::Queen] {
+            let purchase = Move::Purchase { piece, to };
+            let mut copy = board;
+            assert_eq!(copy.is_legal_move(&purchase), !board.has_piece_on(to));
+            if copy.apply(purchase).is_ok() {
+                assert!(!copy.is_in_check(Color::White));
+            }
+        }
+    }
+
+    // A second king can't be bought onto an attacked tile
+    assert!(!board.is_in_check(Color::White));
+    assert!(!board.is_legal_move(&Move::Purchase { piece: PieceType::King, to: Tile::from_str("a2")? }));
+    let mut copy = board;
+    assert!(copy.apply(Move::Purchase { piece: PieceType::King, to: Tile::from_str("a2")? }).is_err());
+    assert!(board.is_legal_move(&Move::Purchase { piece: PieceType::King, to: Tile::from_str("b2")? }));
+
+    Ok(())
+}
+
+/// Test that a pawn on its promotion rank attacks nothing, since there are no tiles ahead of it.
+#[test]
+fn pawn_on_promotion_rank_attacks_nothing() -> Result<(), ()> {
+    init();
+    assert_eq!(Tile::from_str("e8")?.pawn_attacking_bits(Color::White), 0);
+    assert_eq!(Tile::from_str("e1")?.pawn_attacking_bits(Color::Black), 0);
+    assert_ne!(Tile::from_str("e7")?.pawn_attacking_bits(Color::White), 0);
+
+    // A pawn bought onto the far rank doesn't check the king beside it
+    let mut board = Board::empty();
+    board.spawn_white_king(Tile::from_str("a1")?);
+    board.spawn_black_king(Tile::from_str("e8")?);
+    board.spawn_white_pawn(Tile::from_str("d8")?);
+    assert!(!board.is_in_check(Color::Black));
+    Ok(())
+}
+
+/// Test that counting legal moves agrees with generating them.
+#[test]
+fn count_legal_moves() -> Result<(), ()> {
+    init();
+    let mut boards = vec![Board::default()];
+
+    // Stalemate
+    let mut board = Board::empty();
+    board.spawn_white_king(Tile::from_str("a2")?);
+    board.spawn_black_rook(Tile::from_str("h1")?);
+    board.spawn_black_rook(Tile::from_str("b8")?);
+    board.spawn_black_rook(Tile::from_str("g3")?);
+    boards.push(board);
+
+    // Checkmate
+    let mut board = Board::empty();
+    board.spawn_white_king(Tile::from_str("a1")?);
+    board.spawn_black_rook(Tile::from_str("h1")?);
+    board.spawn_black_rook(Tile::from_str("g2")?);
+    boards.push(board);
+
+    // Promotions and castling
+    let mut board = Board::empty();
+    board.spawn_white_king(Tile::from_str("e1")?);
+    board.spawn_white_rook(Tile::from_str("h1")?);
+    board.spawn_white_pawn(Tile::from_str("b7")?);
+    board.spawn_black_king(Tile::from_str("h8")?);
+    board.spawn_black_rook(Tile::from_str("a8")?);
+    boards.push(board);
+
+    for board in boards {
+        let moves = Move::legal_moves(&board);
+        assert_eq!(board.has_any_legal_move(board.whose_turn()), !moves.is_empty());
+        assert_eq!(board.count_legal_moves(board.whose_turn()), moves.len());
+    }
+
+    Ok(())
+}
+
+/// Test that different encodings of the same move have the same effect.
+#[test]
+fn same_effect() -> Result<(), ()> {
+    init();
+    let mut board = Board::default();
+    for player_move in ["e2e4", "e7e5", "g1f3", "b8c6", "f1c4", "g8f6"] {
+        board.apply(Move::from_str(player_move)?)?;
+    }
+
+    let castling = Move::Castling(CastlingSide::King);
+    for encoded in ["e1g1", "e1h1"] {
+        let encoded = Move::from_str(encoded)?;
+        assert!(encoded != castling);
+        assert!(encoded.same_effect(&castling, &board));
+    }
+
+    assert!(Move::from_str("Nc3")?.same_effect(&Move::from_str("b1c3")?, &board));
+    assert!(!Move::from_str("Nc3")?.same_effect(&Move::from_str("b1a3")?, &board));
+    assert!(!Move::from_str("e1e2")?.same_effect(&castling, &board));
+
+    Ok(())
+}
+
+/// Test which tiles are attacked and defended.
+#[test]
+fn attacked_and_defended() -> Result<(), ()> {
+    init();
+    let mut board = Board::empty();
+    board.spawn_white_king(Tile::from_str("a1")?);
+    board.spawn_white_pawn(Tile::from_str("d4")?);
+    board.spawn_white_knight(Tile::from_str("e5")?);
+    board.spawn_black_king(Tile::from_str("h8")?);
+    board.spawn_black_rook(Tile::from_str("e8")?);
+
+    // The pawn defends the knight diagonally
+    assert!(board.is_defended(Tile::from_str("e5")?, Color::White));
+    assert!(board.is_attacked(Tile::from_str("e5")?, Color::Black));
+    // The pawn doesn't defend the tile in front of it
+    assert!(!board.is_defended(Tile::from_str("d5")?, Color::White));
+    // The knight defends an empty tile, and the rook is blocked by the knight
+    assert!(board.is_defended(Tile::from_str("d3")?, Color::White));
+    assert!(!board.is_attacked(Tile::from_str("e4")?, Color::Black));
+
+    Ok(())
+}
+
+/// Test that capturing a rook on its starting tile takes away its castling rights.
+#[test]
+fn capture_rook_disables_castling() -> Result<(), ()> {
+    init();
+    let mut board = Board::default();
+    for player_move in ["g2g3", "b7b6", "f1g2", "g7g6", "g2a8"] {
+        board.apply(Move::from_str(player_move)?)?;
+    }
+    assert!(!board.get_castling_rights().has(Color::Black, CastlingSide::Queen));
+    assert!(board.get_castling_rights().has(Color::Black, CastlingSide::King));
+    assert!(board.sanity_check().is_ok());
+
+    Ok(())
+}
+
+/// Test that castling takes away both of the player's castling rights.
+#[test]
+fn castling_revokes_both_sides() -> Result<(), ()> {
+    init();
+    let mut board = Board::default();
+    for player_move in ["e2e4", "e7e5", "g1f3", "b8c6", "f1c4", "g8f6", "e1h1"] {
+        board.apply(Move::from_str(player_move)?)?;
+    }
+    assert!(!board.get_castling_rights().has(Color::White, CastlingSide::King));
+    assert!(!board.get_castling_rights().has(Color::White, CastlingSide::Queen));
+    assert!(board.get_castling_rights().has(Color::Black, CastlingSide::King));
+    assert!(board.sanity_check().is_ok());
+
+    Ok(())
+}
+
+/// Test that the mop-up score rises as the lone king is driven to a corner.
+#[test]
+fn mop_up_score() -> Result<(), ()> {
+    init();
+    let mut last_score = f64::NEG_INFINITY;
+    for losing_king in ["e5", "d6", "c7", "b8", "a8"] {
+        let mut board = Board::empty();
+        board.spawn_white_king(Tile::from_str("e1")?);
+        board.spawn_white_queen(Tile::from_str("h1")?);
+        board.spawn_black_king(Tile::from_str(losing_king)?);
+
+        assert!(board.material_view().is_lone_king_against_major(Color::Black));
+        let score = board.mop_up_score(Color::White);
+        assert!(score > last_score, "{losing_king} scored {score}, not more than {last_score}");
+        last_score = score;
+    }
+
+    assert_eq!(Tile::from_str("a8")?.distance(Tile::from_str("e1")?), 7);
+    assert_eq!(Tile::from_str("a8")?.manhattan_distance(Tile::from_str("e1")?), 11);
+    assert_eq!(Tile::from_str("d4")?.center_distance(), 0);
+    assert_eq!(Tile::from_str("h1")?.center_distance(), 6);
+    assert!(!Board::default().material_view().is_lone_king_against_major(Color::Black));
+
+    Ok(())
+}
+
+/// Test round-tripping moves through UCI notation.
+#[test]
+fn uci_round_trip() -> Result<(), ()> {
+    init();
+    let uci = |s: &str| Move::from_uci(s).unwrap();
+    let board = Board::default();
+    let e2e4 = uci("e2e4");
+    assert_eq!(e2e4, Move::new(Tile::from_str("e2")?, Tile::from_str("e4")?, None));
+    assert_eq!(e2e4.to_uci(&board).as_deref(), Some("e2e4"));
+    assert_eq!(Move::from_str("e4")?.to_uci(&board).as_deref(), Some("e2e4"));
+
+    let mut board = Board::empty();
+    board.spawn_white_king(Tile::from_str("a1")?);
+    board.spawn_white_pawn(Tile::from_str("e7")?);
+    board.spawn_black_king(Tile::from_str("a8")?);
+    let e7e8q = uci("e7e8q");
+    assert_eq!(e7e8q, Move::new(Tile::from_str("e7")?, Tile::from_str("e8")?, Some(PieceType::Queen)));
+    assert_eq!(e7e8q.to_uci(&board).as_deref(), Some("e7e8q"));
+
+    let mut board = Board::default();
+    for player_move in ["e2e4", "e7e5", "g1f3", "b8c6", "f1c4", "g8f6"] {
+        board.apply(uci(player_move))?;
+    }
+    let e1g1 = uci("e1g1");
+    assert!(e1g1.same_effect(&Move::Castling(CastlingSide::King), &board));
+    assert_eq!(e1g1.to_uci(&board).as_deref(), Some("e1g1"));
+    assert_eq!(Move::Castling(CastlingSide::King).to_uci(&board).as_deref(), Some("e1g1"));
+    assert_eq!(Move::from_str("e1h1")?.to_uci(&board).as_deref(), Some("e1g1"));
+
+    for invalid in ["e2", "Nf3", "e7e8Q", "e7e8k", "e2e4e5", "O-O"] {
+        assert!(Move::from_uci(invalid).is_none(), "{invalid} should not parse");
+    }
+
+    Ok(())
+}
+
+/// Test detecting doubled, isolated, and passed pawns.
+#[test]
+fn pawn_structure() -> Result<(), ()> {
+    init();
+    let tile = |name: &str| Tile::from_str(name).unwrap();
+    let mut board = Board::empty();
+    board.spawn_white_king(tile("g1"));
+    board.spawn_black_king(tile("g8"));
+    // A doubled pair on the c file, supported by the b pawn
+    board.spawn_white_pawn(tile("b2"));
+    board.spawn_white_pawn(tile("c2"));
+    board.spawn_white_pawn(tile("c3"));
+    // An isolated pawn on the f file, blocked by a black pawn on the e file
+    board.spawn_white_pawn(tile("f4"));
+    board.spawn_black_pawn(tile("e6"));
+    // A passed pawn on the h file, with no white pawns in front of it
+    board.spawn_black_pawn(tile("h3"));
+
+    let doubled = board.doubled_pawns(Color::White);
+    assert_eq!(doubled.len(), 2);
+    assert!(doubled.contains(tile("c2")) && doubled.contains(tile("c3")));
+    assert!(board.doubled_pawns(Color::Black).is_empty());
+
+    let isolated = board.isolated_pawns(Color::White);
+    assert_eq!(isolated.len(), 1);
+    assert!(isolated.contains(tile("f4")));
+
+    let passed = board.passed_pawns(Color::White);
+    assert!(!passed.contains(tile("f4")));
+    assert_eq!(passed.len(), 3);
+    assert!(passed.contains(tile("b2")) && passed.contains(tile("c2")) && passed.contains(tile("c3")));
+    let passed = board.passed_pawns(Color::Black);
+    assert_eq!(passed.len(), 1);
+    assert!(passed.contains(tile("h3")));
+
+    Ok(())
+}
+
+/// Test detecting open and half-open files.
+#[test]
+fn file_and_rank_occupancy() -> Result<(), ()> {
+    init();
+    let mut board = Board::default();
+    // Trade off the e pawns for the d pawns, clearing the e file
+    for player_move in ["e2e4", "d7d5", "e4d5", "d8d5", "d2d4", "e7e5", "d4e5", "d5e5"] {
+        board.apply(Move::from_str(player_move)?)?;
+    }
+
+    assert!(board.file_is_open(File::E));
+    assert!(!board.file_is_half_open(File::E, Color::White));
+    // Neither side has a d pawn left either
+    assert!(board.file_is_open(File::D));
+    assert!(!board.file_is_open(File::A));
+
+    let mut board = Board::default();
+    for player_move in ["e2e4", "d7d5", "e4d5", "e7e6"] {
+        board.apply(Move::from_str(player_move)?)?;
+    }
+    assert!(board.file_is_half_open(File::E, Color::White));
+    assert!(!board.file_is_half_open(File::E, Color::Black));
+    assert!(!board.file_is_open(File::E));
+
+    assert_eq!(board.pieces_on_rank(Rank::PAWN_STARTER_WHITE).len(), 7);
+    assert_eq!(board.pieces_on_rank(Rank::BACK_RANK_WHITE).len(), 8);
+    assert!(board.pieces_on_rank(Rank::PAWN_STARTER_WHITE).contains(Tile::from_str("d2")?));
+
+    Ok(())
+}
+
+/// Test picking the color-specific ranks.
+#[test]
+fn color_ranks() {
+    init();
+    assert_eq!(Rank::promotion_rank(Color::White), Rank::BACK_RANK_BLACK);
+    assert_eq!(Rank::promotion_rank(Color::Black), Rank::BACK_RANK_WHITE);
+    assert_eq!(Rank::back_rank(Color::White), Rank::BACK_RANK_WHITE);
+    assert_eq!(Rank::back_rank(Color::Black), Rank::BACK_RANK_BLACK);
+    assert_eq!(Rank::pawn_start(Color::White), Rank::PAWN_STARTER_WHITE);
+    assert_eq!(Rank::pawn_start(Color::Black), Rank::PAWN_STARTER_BLACK);
+}
+
+/// Test loading a FEN position and playing moves on it.
+#[test]
+fn fen_and_moves() -> Result<(), ()> {
+    init();
+    let start = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+    assert_eq!(Board::from_fen(start), Ok(Board::default()));
+
+    let board = Board::from_fen_and_moves("4k3/8/8/8/8/8/4P3/4K3 w - -", &["e2e4", "e8d7"]).unwrap();
+    assert_eq!(board.get_piece(Tile::from_str("e4")?), Some(Piece::new(PieceType::Pawn, Color::White)));
+    assert_eq!(board.get_piece(Tile::from_str("d7")?), Some(Piece::new(PieceType::King, Color::Black)));
+    assert_eq!(board.get_piece(Tile::from_str("e2")?), None);
+    assert_eq!(board.whose_turn(), Color::White);
+
+    assert_eq!(Board::from_fen_and_moves(start, &["e2e4", "e7e4"]), Err(FenError::InvalidMove(1)));
+    assert_eq!(Board::from_fen_and_moves(start, &["e2"]), Err(FenError::InvalidMove(0)));
+    assert_eq!(Board::from_fen("8/8/8 w - -"), Err(FenError::InvalidPiecePlacement));
+    assert_eq!(Board::from_fen("99999999999999999999999999999999/8/8/8/8/8/8/8 w - -"), Err(FenError::InvalidPiecePlacement));
+    assert_eq!(Board::from_fen("4k3/8/8/8/8/8/8/04K3 w - -"), Err(FenError::InvalidPiecePlacement));
+    assert_eq!(Board::from_fen("4k3/8/8/8/8/8/8/4K4 w - -"), Err(FenError::InvalidPiecePlacement));
+    assert_eq!(Board::from_fen("4k3/8/8/8/8/8/8/4K3 x - -"), Err(FenError::InvalidActiveColor));
+    assert_eq!(Board::from_fen("4k3/8/8/8/8/8/8/4K3 w K -"), Err(FenError::InvalidPosition));
+
+    Ok(())
+}
+
+/// Test finding the kings on the board.
+#[test]
+fn king_tiles() -> Result<(), ()> {
+    init();
+    let mut board = Board::default();
+    assert_eq!(board.king_tile(Color::White), Some(Tile::from_str("e1")?));
+    assert_eq!(board.king_tile(Color::Black), Some(Tile::from_str("e8")?));
+    assert_eq!(board.king_tiles(Color::White).len(), 1);
+
+    board.spawn_white_king(Tile::from_str("a3")?);
+    let kings = board.king_tiles(Color::White);
+    assert_eq!(kings.len(), 2);
+    assert!(kings.contains(Tile::from_str("a3")?) && kings.contains(Tile::from_str("e1")?));
+
+    assert_eq!(Board::empty().king_tile(Color::White), None);
+    Ok(())
+}
+
+/// Test moving pieces onto friendly pieces, which is only legal for castling.
+#[test]
+fn friendly_captures() -> Result<(), ()> {
+    init();
+    let mut board = Board::default();
+    for player_move in ["e2e4", "e7e5", "g1f3", "b8c6", "f1c4", "g8f6"] {
+        board.apply(Move::from_str(player_move)?)?;
+    }
+    let tile = |name: &str| Tile::from_str(name);
+
+    // Other pieces can never move onto a friendly piece
+    assert!(!board.is_legal_piece_move(tile("h1")?, tile("e1")?));
+    assert!(!board.is_legal_piece_move(tile("d1")?, tile("e1")?));
+    assert!(!board.is_legal_piece_move(tile("f3")?, tile("h2")?));
+    assert!(!board.is_legal_piece_move(tile("d2")?, tile("c1")?));
+    // The black king can't castle on white's turn
+    assert!(!board.is_legal_piece_move(tile("e8")?, tile("h8")?));
+
+    // The king moving onto its own rook castles
+    assert!(board.is_legal_piece_move(tile("e1")?, tile("h1")?));
+    board.apply(Move::from_str("e1h1")?)?;
+    assert_eq!(board.get_piece(tile("g1")?), Some(Piece::new(PieceType::King, Color::White)));
+    assert_eq!(board.get_piece(tile("f1")?), Some(Piece::new(PieceType::Rook, Color::White)));
+
+    // A king off its start square can't move onto a friendly piece
+    board.apply(Move::from_str("a7a6")?)?;
+    assert!(!board.is_legal_piece_move(tile("g1")?, tile("f1")?));
+    assert!(!board.is_legal_piece_move(tile("g1")?, tile("h2")?));
+    assert!(!board.is_legal_piece_move(tile("g1")?, tile("a1")?));
+
+    Ok(())
+}
+
+/// Test iterating over the tiles of a sector and of a color's home.
+#[test]
+fn sector_tiles() -> Result<(), ()> {
+    init();
+    let sector = Tile::from_str("c3")?.get_sector();
+    let tiles: Vec<Tile> = Tile::in_sector(sector).collect();
+    assert_eq!(tiles.len(), 4);
+    for name in ["c3", "d3", "c4", "d4"] {
+        assert!(tiles.contains(&Tile::from_str(name)?));
+    }
+
+    let white_home: Vec<Tile> = Tile::home_tiles(Color::White).collect();
+    assert_eq!(white_home.len(), 16);
+    for tile in Tile::all() {
+        let is_home = tile.get_sector().is_home_for(Color::White);
+        assert_eq!(white_home.contains(&tile), is_home);
+        assert_eq!(is_home, tile.get_rank() == Rank::BACK_RANK_WHITE || tile.get_rank() == Rank::pawn_start(Color::White));
+    }
+    assert!(Tile::home_tiles(Color::Black).all(|tile| tile.get_sector().is_home_for(Color::Black)));
+    Ok(())
+}
+
+/// Test that a player can't move their opponent's pieces.
+#[test]
+fn out_of_turn_moves() -> Result<(), ()> {
+    init();
+    let mut board = Board::default();
+    let before = board;
+    assert!(board.apply(Move::from_str("e7e5")?).is_err());
+    assert_eq!(board, before);
+    assert_eq!(board.whose_turn(), Color::White);
+
+    board.apply(Move::from_str("e2e4")?)?;
+    assert!(board.apply(Move::from_str("d2d4")?).is_err());
+    assert_eq!(board.whose_turn(), Color::Black);
+    board.apply(Move::from_str("e7e5")?)?;
+
+    Ok(())
+}
+
+/// Test the pieces and their values in a sector.
+#[test]
+fn sector_pieces() -> Result<(), ()> {
+    init();
+    let mut board = Board::default();
+    for player_move in ["e2e4", "d7d5", "e4d5", "d8d5", "b1c3", "c8g4"] {
+        board.apply(Move::from_str(player_move)?)?;
+    }
+
+    // The black queen is alone in white's half
+    let sector = Tile::from_str("d5")?.get_sector();
+    assert_eq!(board.pieces_in_sector(sector, Color::Black), vec![(Tile::from_str("d5")?, Piece::new(PieceType::Queen, Color::Black))]);
+    assert!(board.pieces_in_sector(sector, Color::White).is_empty());
+
+    let sector = Tile::from_str("c1")?.get_sector();
+    let pieces = board.pieces_in_sector(sector, Color::White);
+    assert_eq!(pieces.len(), 4);
+    assert!(pieces.iter().all(|(tile, piece)| tile.get_sector() == sector && piece.get_color() == Color::White));
+    let market = Market::default();
+    assert_eq!(board.least_valuable_piece_on(sector, Color::White, &market), Some((Tile::from_str("c2")?, Piece::new(PieceType::Pawn, Color::White))));
+    assert_eq!(board.least_valuable_piece_on(sector, Color::Black, &market), None);
+    // The least valuable piece is the cheapest one in the market
+    let cheap_bishops = market.with_bishop_value(Currency::penny());
+    assert_eq!(board.least_valuable_piece_on(sector, Color::White, &cheap_bishops), Some((Tile::from_str("c1")?, Piece::new(PieceType::Bishop, Color::White))));
+
+    // With the default prices, the higher value controls the sector
+    let market = Market::default();
+    for sector in Sector::all() {
+        let white = board.sector_value(sector, Color::White, &market);
+        let black = board.sector_value(sector, Color::Black, &market);
+        let controller = if white > black {
+            Some(Color::White)
+        } else if black > white {
+            Some(Color::Black)
+        } else {
+            None
+        };
+        assert_eq!(market.who_controls_sector(&board, sector), controller);
+    }
+    Ok(())
+}
+
+/// Test that an en passant capture can't expose the king along the rank.
+#[test]
+fn en_passant_pin() -> Result<(), ()> {
+    init();
+    // Both pawns leave the fifth rank, so the rook would see the king
+    let pinned = Board::from_fen_and_moves("7k/2p5/8/KP5r/8/8/8/8 b - - 0 1", &["c7c5"]).map_err(|_| ())?;
+    assert!(!pinned.is_legal_move(&Move::from_str("b5c6")?));
+    assert!(pinned.is_legal_move(&Move::from_str("b5b6")?));
+    assert!(!Move::legal_moves(&pinned).contains(&Move::from_str("b5c6")?));
+
+    // Without the rook, the capture is fine
+    let mut board = Board::from_fen_and_moves("7k/2p5/8/KP6/8/8/8/8 b - - 0 1", &["c7c5"]).map_err(|_| ())?;
+    board.apply(Move::from_str("b5c6")?)?;
+    assert!(board.get_piece(Tile::from_str("c5")?).is_none());
+    Ok(())
+}
+
+/// Test the tiles a single piece attacks.
+#[test]
+fn piece_attacks() -> Result<(), ()> {
+    init();
+    let tile = |name: &str| Tile::from_str(name);
+    let board = Board::from_fen("4k3/8/8/8/1p1R2P1/8/8/4K3 w - - 0 1").map_err(|_| ())?;
+    let attacks = board.piece_attacks_from(tile("d4")?);
+
+    let mut expected = TileSet::default();
+    for name in ["c4", "b4", "e4", "f4", "g4", "d1", "d2", "d3", "d5", "d6", "d7", "d8"] {
+        expected.insert(tile(name)?);
+    }
+    // The rook stops at the enemy pawn it can capture, and at its own pawn
+    assert_eq!(attacks, expected);
+    assert!(!attacks.contains(tile("a4")?) && !attacks.contains(tile("h4")?));
+
+    assert!(board.piece_attacks_from(tile("a1")?).is_empty());
+    assert_eq!(board.piece_attacks_from(tile("b4")?).len(), 2);
+    Ok(())
+}
+
+/// Test that the Zobrist hash only depends on the position.
+#[test]
+fn zobrist_hash() -> Result<(), ()> {
+    init();
+    let play = |moves: &[&str]| Board::from_fen_and_moves("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1", moves).map_err(|_| ());
+    let a = play(&["g1f3", "g8f6", "b1c3"])?;
+    let b = play(&["b1c3", "g8f6", "g1f3"])?;
+    assert_eq!(a.zobrist_hash(), b.zobrist_hash());
+    assert_ne!(a.zobrist_hash(), play(&["g1f3", "g8f6"])?.zobrist_hash());
+    assert_ne!(Board::default().zobrist_hash(), play(&["e2e4"])?.zobrist_hash());
+    Ok(())
+}
+
+/// Test the ASCII letters of the pieces.
+#[test]
+fn piece_ascii() {
+    init();
+    assert_eq!(Piece::knight(Color::Black).to_ascii(), 'n');
+    assert_eq!(Piece::king(Color::White).to_ascii(), 'K');
+    assert_eq!(Piece::pawn(Color::White).to_fen_char(), 'P');
+    assert_eq!(Piece::queen(Color::Black).to_fen_char(), 'q');
+}
+
+/// Test that the board shades the sectors the player who just moved controls.
+#[test]
+fn display_shades_sectors() -> Result<(), ()> {
+    init();
+    // Red and blue tiles are controlled by the player who just moved
+    let controlled = "\x1b[0;41m";
+    assert!(!Board::empty().to_string().contains(controlled));
+    let mut board = Board::default();
+    board.apply(Move::from_str("e2e4")?)?;
+    assert!(board.to_string().contains(controlled));
+    Ok(())
+}
+
+/// This tests the attack generation against golden bitboards.
+#[test]
+fn attacking_bits() -> Result<(), ()> {
+    init();
+    let board = Board::from_fen("4k3/8/8/3p4/4P3/2N5/8/R3K3 w Q - 0 1").map_err(|_| ())?;
+    assert_eq!(format_bitboard(board.white_attacking_bits()), "\
+10000000
+10000000
+10000000
+11010100
+10001000
+10000000
+10011100
+01111100
+");
+    assert_eq!(format_bitboard(board.black_attacking_bits()), "\
+00010100
+00011100
+00000000
+00000000
+00101000
+00000000
+00000000
+00000000
+");
+
+    // A rook sees its own rank and file, but doesn't attack its own tile
+    assert!(!TileSet::from_bits(board.white_attacking_bits()).contains(Tile::from_str("a1")?));
+    Ok(())
+}
+
+/// This tests finding pieces on the board.
+#[test]
+fn find_pieces() -> Result<(), ()> {
+    init();
+    let board = Board::default();
+    let rooks = board.find_pieces(Piece::rook(Color::White));
+    assert_eq!(rooks.len(), 2);
+    assert!(rooks.contains(Tile::from_str("a1")?));
+    assert!(rooks.contains(Tile::from_str("h1")?));
+
+    assert_eq!(board.find_piece(Piece::rook(Color::White)), Some(Tile::from_str("a1")?));
+    assert_eq!(board.find_piece(Piece::new(PieceType::Queen, Color::Black)), Some(Tile::from_str("d8")?));
+
+    let board = Board::from_fen("4k3/8/8/8/8/8/8/4K3 w - - 0 1").map_err(|_| ())?;
+    assert!(board.find_pieces(Piece::rook(Color::White)).is_empty());
+    assert_eq!(board.find_piece(Piece::rook(Color::White)), None);
+    Ok(())
+}
+
+/// This tests describing what a move did to the board.
+#[test]
+fn apply_detailed() -> Result<(), ()> {
+    init();
+    let mut board = Board::default();
+    for player_move in ["e2e4", "d7d5"] {
+        board.apply(Move::from_str(player_move)?)?;
+    }
+
+    let outcome = board.apply_detailed(Move::from_str("e4d5")?).map_err(|_| ())?;
+    assert_eq!(outcome.captured, vec![Piece::new(PieceType::Pawn, Color::Black)]);
+    assert!(!outcome.was_castle);
+    assert_eq!(outcome.promotion, None);
+    assert!(!outcome.gave_check);
+
+    // A quiet move captures nothing
+    let outcome = board.apply_detailed(Move::from_str("g8f6")?).map_err(|_| ())?;
+    assert!(outcome.captured.is_empty());
+
+    // Castling, promotion, and check are reported too
+    let mut board = Board::from_fen("4k3/1P6/8/8/8/8/8/4K2R w K - 0 1").map_err(|_| ())?;
+    let outcome = board.apply_detailed(Move::Castling(CastlingSide::King)).map_err(|_| ())?;
+    assert!(outcome.was_castle);
+    board.apply(Move::from_str("e8d7")?)?;
+    let outcome = board.apply_detailed(Move::new(Tile::from_str("b7")?, Tile::from_str("b8")?, Some(PieceType::Queen))).map_err(|_| ())?;
+    assert_eq!(outcome.promotion, Some(PieceType::Queen));
+    board.apply(Move::from_str("d7e7")?)?;
+    let outcome = board.apply_detailed(Move::from_str("b8e8")?).map_err(|_| ())?;
+    assert!(outcome.gave_check);
+
+    // Every capture of a turn with several moves is reported
+    let mut board = Board::from_fen("4k3/8/8/3p1n2/4P1P1/8/8/4K3 w - - 0 1").map_err(|_| ())?;
+    let outcome = board.apply_detailed(Move::from_str("e4d5 g4f5")?).map_err(|_| ())?;
+    assert_eq!(outcome.captured, vec![Piece::new(PieceType::Pawn, Color::Black), Piece::new(PieceType::Knight, Color::Black)]);
+
+    // Moves that can't be played are reported as errors
+    assert_eq!(board.apply_detailed(Move::from_str("e7e5")?), Err(MoveError::Illegal));
+    assert_eq!(board.apply_detailed(Move::from_str("Qh5")?), Err(MoveError::NoEligiblePiece));
+    Ok(())
+}
+
+/// This tests castling with the dedicated castling method.
+#[test]
+fn try_castle() -> Result<(), ()> {
+    init();
+    let mut board = Board::from_fen("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1").map_err(|_| ())?;
+    board.try_castle(CastlingSide::King).map_err(|_| ())?;
+    assert_eq!(board.get_piece(Tile::from_str("g1")?), Some(Piece::new(PieceType::King, Color::White)));
+    assert_eq!(board.get_piece(Tile::from_str("f1")?), Some(Piece::rook(Color::White)));
+    assert_eq!(board.whose_turn(), Color::Black);
+
+    // Black can't castle once the rights are gone
+    let mut board = Board::from_fen("r3k2r/8/8/8/8/8/8/R3K2R b KQ - 0 1").map_err(|_| ())?;
+    assert_eq!(board.try_castle(CastlingSide::Queen), Err(MoveError::CannotCastle));
+    assert_eq!(board.whose_turn(), Color::Black);
+    Ok(())
+}
+
+/// This tests the colors of the squares, and counting bishops on them.
+#[test]
+fn square_colors() -> Result<(), ()> {
+    init();
+    assert!(Tile::from_str("a1")?.is_dark_square());
+    assert!(Tile::from_str("h1")?.is_light_square());
+    assert!(Tile::from_str("d1")?.is_light_square());
+    assert!(Tile::from_str("d8")?.is_dark_square());
+    assert_eq!(Tile::all().filter(Tile::is_light_square).count(), 32);
+
+    let board = Board::default();
+    assert_eq!(board.bishops_on_color(Color::White, true), 1);
+    assert_eq!(board.bishops_on_color(Color::White, false), 1);
+
+    let board = Board::from_fen("4k3/8/8/8/8/8/8/2B1KB2 w - - 0 1").map_err(|_| ())?;
+    assert_eq!(board.bishops_on_color(Color::White, true), 1);
+    assert_eq!(board.bishops_on_color(Color::White, false), 1);
+    assert_eq!(board.bishops_on_color(Color::Black, true), 0);
+
+    // A purchased bishop can share its square color with another bishop
+    let board = Board::from_fen("4k3/8/8/8/8/8/8/3BKB2 w - - 0 1").map_err(|_| ())?;
+    assert_eq!(board.bishops_on_color(Color::White, true), 2);
+    assert_eq!(board.bishops_on_color(Color::White, false), 0);
+    Ok(())
+}
+
+/// This tests converting a board to and from an array of tiles.
+#[test]
+fn board_array() -> Result<(), ()> {
+    init();
+    let board = Board::default();
+    let pieces = board.to_array();
+    assert_eq!(pieces[0], Some(Piece::rook(Color::White)));
+    assert_eq!(pieces[Tile::from_str("e8")?.index() as usize], Some(Piece::new(PieceType::King, Color::Black)));
+    assert_eq!(pieces.iter().filter(|piece| piece.is_none()).count(), 32);
+    assert_eq!(Board::from_array(&pieces), board);
+
+    // Castling is only allowed for kings and rooks on their starting tiles
+    let board = Board::from_fen("r3k3/8/8/8/8/8/8/4K2R w - - 0 1").map_err(|_| ())?;
+    let board = Board::from_array(&board.to_array());
+    assert_eq!(board, Board::from_fen("r3k3/8/8/8/8/8/8/4K2R w Kq - 0 1").map_err(|_| ())?);
+    Ok(())
+}
+
+/// This tests finding pieces the opponent can win by capturing.
+#[test]
+fn is_hanging() -> Result<(), ()> {
+    init();
+    let d5 = Tile::from_str("d5")?;
+    let market = Market::default();
+
+    // An undefended knight attacked by a pawn is hanging
+    let board = Board::from_fen("4k3/8/4p3/3N4/8/8/8/4K3 w - - 0 1").map_err(|_| ())?;
+    assert!(board.is_hanging(d5, &market));
+    assert!(!board.is_hanging(Tile::from_str("e6")?, &market));
+
+    // A knight defended by a pawn isn't worth a rook
+    let board = Board::from_fen("4k3/8/8/r2N4/4P3/8/8/4K3 w - - 0 1").map_err(|_| ())?;
+    assert!(!board.is_hanging(d5, &market));
+    assert_eq!(board.see(d5, Color::Black, &market), Currency::zero());
+
+    // Unless the market prices knights above rooks
+    let market = market.with_knight_value(market.get_piece_value(PieceType::Rook) * 2);
+    assert!(board.is_hanging(d5, &market));
+    assert_eq!(board.see(d5, Color::Black, &market), market.get_piece_value(PieceType::Rook));
+    let market = Market::default();
+
+    // Without the pawn, the rook wins the knight
+    let board = Board::from_fen("4k3/8/8/r2N4/8/8/8/4K3 w - - 0 1").map_err(|_| ())?;
+    assert!(board.is_hanging(d5, &market));
+    assert!(!board.is_hanging(Tile::from_str("d4")?, &market));
+    Ok(())
+}
+
+/// This tests generating legal moves from the board itself.
+#[test]
+fn board_legal_moves() -> Result<(), ()> {
+    init();
+    for fen in [
+        "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+        "r3k2r/8/8/8/8/8/8/R3K2R b KQkq - 0 1",
+        "4k3/1P6/8/8/8/8/8/4K3 w - - 0 1",
+        "4k3/8/4p3/3N4/8/8/8/4K3 w - - 0 1",
+    ] {
+        let board = Board::from_fen(fen).map_err(|_| ())?;
+        assert!(!board.legal_moves().is_empty());
+        assert_eq!(board.legal_moves(), Move::legal_moves(&board));
+    }
+    Ok(())
+}
+
+/// Test that the position key only covers the playable state of the board.
+#[test]
+fn position_key() -> Result<(), ()> {
+    init();
+    let start = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+
+    // After the fool's mate, black has won, but the position is the same
+    let mated = Board::from_fen_and_moves(start, &["f2f3", "e7e5", "g2g4", "d8h4"]).map_err(|_| ())?;
+    let position = Board::from_fen("rnb1kbnr/pppp1ppp/8/4p3/6Pq/5P2/PPPPP2P/RNBQKBNR w KQkq - 1 3").map_err(|_| ())?;
+    assert_eq!(mated.get_winner(), Some(Color::Black));
+    assert_eq!(position.get_winner(), None);
+    assert_ne!(mated, position);
+    assert_eq!(mated.position_key(), position.position_key());
+    assert_eq!(mated.zobrist_hash(), position.zobrist_hash());
+
+    // An en passant tile only matters if a pawn can capture on it
+    let no_capture = Board::from_fen("rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1").map_err(|_| ())?;
+    let no_tile = Board::from_fen("rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq - 0 1").map_err(|_| ())?;
+    assert_eq!(no_capture.position_key(), no_tile.position_key());
+    let capture = Board::from_fen("4k3/8/8/8/3pP3/8/8/4K3 b - e3 0 1").map_err(|_| ())?;
+    let no_tile = Board::from_fen("4k3/8/8/8/3pP3/8/8/4K3 b - - 0 1").map_err(|_| ())?;
+    assert_ne!(capture.position_key(), no_tile.position_key());
+    Ok(())
+}
+
+/// Test that taking back a move restores the board exactly.
+#[test]
+fn make_unmake_move() -> Result<(), ()> {
+    init();
+    let tile = |name: &str| Tile::from_str(name);
+    // A busy position with captures, checks, and castling
+    let kiwipete = Board::from_fen("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1").map_err(|_| ())?;
+    assert_eq!(kiwipete.legal_moves().len(), 48);
+
+    let mut board = kiwipete;
+    for player_move in kiwipete.legal_moves() {
+        let Move::FromTo { from, to, .. } = player_move else {
+            continue;
+        };
+        let undo = board.make_move(from, to).ok_or(())?;
+        assert_eq!(undo.get_captured(), kiwipete.get_piece(to));
+        // A legal move never leaves the mover's own king in check
+        assert!(!board.is_in_check(Color::White), "after {player_move}");
+        board.unmake_move(undo);
+        assert_eq!(board, kiwipete, "after taking back {player_move}");
+    }
+    assert_eq!(board.legal_moves(), kiwipete.legal_moves());
+
+    // An en passant capture takes the passed pawn, and puts it back
+    let en_passant = Board::from_fen("4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 1").map_err(|_| ())?;
+    let mut board = en_passant;
+    let undo = board.make_move(tile("e5")?, tile("d6")?).ok_or(())?;
+    assert_eq!(undo.get_captured(), Some(Piece::new(PieceType::Pawn, Color::Black)));
+    assert!(!board.has_piece_on(tile("d5")?));
+    board.unmake_move(undo);
+    assert_eq!(board, en_passant);
+
+    assert!(board.make_move(tile("a1")?, tile("a2")?).is_none());
+    assert_eq!(board, en_passant);
+    Ok(())
+}
+/// Test writing boards as FEN and loading them back.
+#[test]
+fn fen_round_trip() -> Result<(), ()> {
+    init();
+    let start = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+    assert_eq!(Board::default().to_fen(), start);
+    assert_eq!(Board::empty().to_fen(), "8/8/8/8/8/8/8/8 w - - 0 1");
+
+    let after_moves = Board::from_fen_and_moves(start, &["e2e4", "g8f6", "e4e5", "d7d5", "g1f3", "f6g8"]).map_err(|_| ())?;
+    let kiwipete = Board::from_fen("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1").map_err(|_| ())?;
+    let en_passant = Board::from_fen_and_moves(start, &["e2e4", "g8f6", "e4e5", "d7d5"]).map_err(|_| ())?;
+    assert_eq!(en_passant.to_fen(), "rnbqkb1r/ppp1pppp/5n2/3pP3/8/8/PPPP1PPP/RNBQKBNR w KQkq d6 0 1");
+    let won = Board::from_fen("4k3/8/8/8/8/8/8/4K3 b - - 3 1 1-0").map_err(|_| ())?;
+    assert_eq!(won.get_winner(), Some(Color::White));
+    for board in [Board::default(), after_moves, kiwipete, en_passant, won] {
+        assert_eq!(Board::from_fen(&board.to_fen()), Ok(board), "{}", board.to_fen());
+    }
+    assert_eq!(after_moves.to_fen(), "rnbqkbnr/ppp1pppp/8/3pP3/8/5N2/PPPP1PPP/RNBQKB1R w KQkq - 2 1");
+
+    assert_eq!(Board::from_fen("4k3/8/8/8/8/8/8/4K3 w - - x 1"), Err(FenError::InvalidHalfmoveClock));
+    assert_eq!(Board::from_fen("4k3/8/8/8/8/8/8/4K3 w - - 0 1 1-1"), Err(FenError::InvalidWinner));
+    assert_eq!(Board::from_fen("4k3/8/8/8/8/8/8/4K3 w - - 0"), Err(FenError::WrongFieldCount));
+    assert_eq!(Board::from_fen("4k3/8/8/8/8/8/8/4K3 w - z9 0 1"), Err(FenError::InvalidEnPassant));
+    assert_eq!(Board::from_fen("4k3/8/8/8/8/8/8/4K3 w KX - 0 1"), Err(FenError::InvalidCastling));
+    assert_eq!(Board::from_fen("4k3/8/8/8/8/8/8/4K2X w - - 0 1"), Err(FenError::InvalidPiecePlacement));
+    assert_eq!(Board::from_fen("4k3/8/8/8/8/8/8/4K35 w - - 0 1"), Err(FenError::InvalidPiecePlacement));
+    assert_eq!(Board::from_fen(""), Err(FenError::WrongFieldCount));
+
+    // Kings can be bought, so any number of them round-trips, even none at all
+    for fen in ["8/8/8/8/8/8/8/8 w - - 0 1", "4k3/8/8/8/8/8/8/8 w - - 0 1", "4k2k/8/8/8/8/8/8/K3K3 w - - 0 1"] {
+        let board = Board::from_fen(fen).map_err(|_| ())?;
+        assert_eq!(board.to_fen(), fen);
+        assert_eq!(Board::from_fen(&board.to_fen()), Ok(board));
+    }
+    assert_eq!(Board::from_fen(&Board::empty().to_fen()), Ok(Board::empty()));
+    Ok(())
+}
+
+
+/// Test the halfmove clock and the fifty-move rule.
+#[test]
+fn fifty_move_rule() -> Result<(), ()> {
+    init();
+    let mut board = Board::from_fen("7k/4p3/8/8/8/8/8/R3K1N1 w Q - 0 1").map_err(|_| ())?;
+    assert_eq!(board.halfmove_clock(), 0);
+
+    // Quiet moves and castling count, pawn moves, captures, and purchases reset the clock
+    for (player_move, clock) in [("g1f3", 1), ("h8g8", 2), ("O-O-O", 3), ("e7e5", 0), ("c1b1", 1), ("g8h8", 2), ("f3e5", 0), ("h8g8", 1)] {
+        board.apply(Move::from_str(player_move)?)?;
+        assert_eq!(board.halfmove_clock(), clock, "after {player_move}");
+    }
+    board.apply(Move::Purchase { piece: PieceType::Knight, to: Tile::from_str("a2")? })?;
+    assert_eq!(board.halfmove_clock(), 0);
+    assert!(!board.is_fifty_move_draw());
+
+    // The clock is read from FEN, and the game is drawn once it reaches a hundred
+    let mut board = Board::from_fen("4k3/8/8/8/8/8/8/4K1N1 w - - 99 80").map_err(|_| ())?;
+    assert_eq!(board.halfmove_clock(), 99);
+    assert!(!board.is_fifty_move_draw());
+    board.apply(Move::from_str("g1f3")?)?;
+    assert!(board.is_fifty_move_draw());
+    assert_eq!(Board::from_fen("4k3/8/8/8/8/8/8/4K1N1 w - - x 80"), Err(FenError::InvalidHalfmoveClock));
+    Ok(())
+}
+
+/// Test reading moves in Standard Algebraic Notation.
+#[test]
+fn san_moves() -> Result<(), ()> {
+    init();
+    let board = Board::default();
+    assert_eq!(Move::from_san("Nf3", &board).ok(), Move::from_uci("g1f3"));
+    assert_eq!(Move::from_san("e4", &board).ok(), Move::from_uci("e2e4"));
+    assert_eq!(Move::from_san("e5", &board), Err(SanError::Illegal));
+
+    // Captures must capture, and must be marked
+    let board = Board::from_fen_and_moves("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1", &["e2e4", "d7d5"]).map_err(|_| ())?;
+    assert_eq!(Move::from_san("exd5", &board).ok(), Move::from_uci("e4d5"));
+    assert_eq!(Move::from_san("ed5", &board), Err(SanError::Illegal));
+    assert_eq!(Move::from_san("Nxf3", &board), Err(SanError::Illegal));
+    assert_eq!(Move::from_san("exe5", &board), Err(SanError::Illegal));
+    let board = Board::from_fen("4k3/8/8/4p3/8/5N2/8/4K3 w - - 0 1").map_err(|_| ())?;
+    assert_eq!(Move::from_san("Nxe5", &board).ok(), Move::from_uci("f3e5"));
+    assert_eq!(Move::from_san("Ne5", &board), Err(SanError::Illegal));
+    // En passant captures are marked like any other
+    let board = Board::from_fen_and_moves("4k3/3p4/8/4P3/8/8/8/4K3 b - - 0 1", &["d7d5"]).map_err(|_| ())?;
+    assert_eq!(Move::from_san("exd6", &board).ok(), Move::from_uci("e5d6"));
+    assert_eq!(Move::from_san("ed6", &board), Err(SanError::Illegal));
+
+    // Checks are allowed, but not needed
+    let board = Board::from_fen("4k3/4p3/8/8/8/8/8/4QK2 w - - 0 1").map_err(|_| ())?;
+    assert_eq!(Move::from_san("Qxe7+", &board).ok(), Move::from_uci("e1e7"));
+    assert_eq!(Move::from_san("Qxe7#", &board).ok(), Move::from_uci("e1e7"));
+
+    // Promotions
+    let board = Board::from_fen("4k3/P7/8/8/8/8/8/4K3 w - - 0 1").map_err(|_| ())?;
+    assert_eq!(Move::from_san("a8=Q", &board).ok(), Move::from_uci("a7a8q"));
+    assert_eq!(Move::from_san("a8N", &board).ok(), Move::from_uci("a7a8n"));
+    assert_eq!(Move::from_san("a8", &board), Err(SanError::Illegal));
+
+    // Either knight could move, so the move needs a file
+    let board = Board::from_fen("4k3/8/8/8/8/8/8/1N2KN2 w - - 0 1").map_err(|_| ())?;
+    assert_eq!(Move::from_san("Nd2", &board), Err(SanError::Ambiguous));
+    assert_eq!(Move::from_san("Nbd2", &board).ok(), Move::from_uci("b1d2"));
+    assert_eq!(Move::from_san("Nfd2", &board).ok(), Move::from_uci("f1d2"));
+
+    let board = Board::from_fen("4k3/8/8/8/8/8/8/4K2R w K - 0 1").map_err(|_| ())?;
+    assert_eq!(Move::from_san("O-O", &board), Ok(Move::Castling(CastlingSide::King)));
+    // Castling has to be legal, like any other move
+    assert_eq!(Move::from_san("O-O-O", &board), Err(SanError::Illegal));
+    let attacked = Board::from_fen("4kr2/8/8/8/8/8/8/4K2R w K - 0 1").map_err(|_| ())?;
+    assert_eq!(Move::from_san("O-O", &attacked), Err(SanError::Illegal));
+
+    // Purchases, reserves, deployments, and combinations are read the way `to_san` writes them
+    let queen = PieceType::Queen;
+    assert_eq!(Move::from_san("$Qe4+", &board), Ok(Move::Purchase { piece: queen, to: Tile::from_str("e4")? }));
+    assert_eq!(Move::from_san("$Qe1", &board), Err(SanError::Illegal));
+    assert_eq!(Move::from_san("$Q", &board), Ok(Move::Reserve(queen)));
+    assert_eq!(Move::from_san("Q@e4", &board), Ok(Move::Deploy { piece: queen, to: Tile::from_str("e4")? }));
+    assert_eq!(Move::from_san("pass", &board), Ok(Move::Pass));
+    assert_eq!(Move::from_san("Kf1 Kg1", &board), Move::from_str("e1f1 f1g1").map_err(|_| SanError::Malformed));
+    assert_eq!(Move::from_san("Kf1 Kf1", &board), Err(SanError::Illegal));
+    assert_eq!(Move::from_san("$Qz9", &board), Err(SanError::Malformed));
+
+    // Malformed moves are errors, not panics
+    for san in ["", "x", "Nz9", "Ke9+", "Nbbd2", "é4"] {
+        assert!(Move::from_san(san, &board).is_err(), "{san}");
+    }
+    for word in ["$", "$Pz9", "$X", "Z@e4", "é4", "e2e9", "e7e8X", "O-O-O-O"] {
+        assert_eq!(Move::from_str(word), Err(()), "{word}");
+    }
+    Ok(())
+}
+
+/// Test writing moves in Standard Algebraic Notation.
+#[test]
+fn san_output() -> Result<(), ()> {
+    init();
+    let start = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+    let board = Board::default();
+    assert_eq!(Move::from_str("g1f3")?.to_san(&board), "Nf3");
+    assert_eq!(Move::from_str("e4")?.to_san(&board), "e4");
+
+    let board = Board::from_fen_and_moves(start, &["e2e4", "d7d5"]).map_err(|_| ())?;
+    assert_eq!(Move::from_str("e4d5")?.to_san(&board), "exd5");
+
+    // Scholar's mate
+    let board = Board::from_fen_and_moves(start, &["e2e4", "e7e5", "f1c4", "b8c6", "d1h5", "g8f6"]).map_err(|_| ())?;
+    assert_eq!(Move::from_str("h5f7")?.to_san(&board), "Qxf7#");
+    let board = Board::from_fen("4k3/4p3/8/8/8/8/8/4QK2 w - - 0 1").map_err(|_| ())?;
+    assert_eq!(Move::from_str("e1e7")?.to_san(&board), "Qxe7+");
+
+    let board = Board::from_fen("4k3/P7/8/8/8/8/8/4K3 w - - 0 1").map_err(|_| ())?;
+    assert_eq!(Move::new(Tile::from_str("a7")?, Tile::from_str("a8")?, Some(PieceType::Queen)).to_san(&board), "a8=Q+");
+
+    // Knights on the same rank need a file, and on the same file need a rank
+    let board = Board::from_fen("4k3/8/8/8/8/8/8/1N2KN2 w - - 0 1").map_err(|_| ())?;
+    assert_eq!(Move::from_str("b1d2")?.to_san(&board), "Nbd2");
+    assert_eq!(Move::from_str("f1g3")?.to_san(&board), "Ng3");
+    let board = Board::from_fen("4k3/8/8/1N6/8/8/8/1N2K3 w - - 0 1").map_err(|_| ())?;
+    assert_eq!(Move::from_str("b1c3")?.to_san(&board), "N1c3");
+
+    let board = Board::from_fen("4k3/8/8/8/8/8/8/4K2R w K - 0 1").map_err(|_| ())?;
+    assert_eq!(Move::from_str("e1g1")?.to_san(&board), "O-O");
+    assert_eq!(Move::from_str("$Ne4")?.to_san(&board), "$Ne4");
+    // Purchases and deployments that give check are marked like any other move
+    assert_eq!(Move::from_str("$Qe4")?.to_san(&board), "$Qe4+");
+    assert_eq!(Move::from_str("Q@e4")?.to_san(&board), "Q@e4+");
+    for san in ["$Qe4+", "Q@e4+", "$N", "pass", "Kf1 Kg1"] {
+        assert_eq!(Move::from_san(san, &board).map(|player_move| player_move.to_san(&board)), Ok(san.to_string()));
+    }
+
+    // Every legal move reads back as itself
+    let board = Board::from_fen_and_moves(start, &["e2e4", "d7d5", "g1f3", "b8c6"]).map_err(|_| ())?;
+    for player_move in board.legal_moves() {
+        let san = player_move.to_san(&board);
+        assert!(Move::from_san(&san, &board).is_ok_and(|parsed| parsed.same_effect(&player_move, &board)), "{san}");
+    }
+    Ok(())
+}
+
+/// Test reading promotions written after a move.
+#[test]
+fn promotion_suffix() -> Result<(), ()> {
+    init();
+    let board = Board::from_fen("4k3/P7/8/8/8/8/8/4K3 w - - 0 1").map_err(|_| ())?;
+    for promotion in PieceType::PROMOTIONS {
+        let letter = char::from(promotion);
+        let expected = Move::new(Tile::from_str("a7")?, Tile::from_str("a8")?, Some(promotion));
+        assert_eq!(Move::from_str(&format!("a7a8{letter}"))?, expected);
+        assert_eq!(Move::from_str(&format!("a7a8{}", letter.to_ascii_lowercase()))?, expected);
+        for word in [format!("a8{letter}"), format!("Pa8{letter}")] {
+            let player_move = Move::from_str(&word)?;
+            assert!(player_move.same_effect(&expected, &board), "{word}");
+            assert!(board.is_legal_move(&player_move), "{word}");
+        }
+        // Promotions read back as they're written
+        assert_eq!(Move::from_str(&expected.to_string())?, expected);
+    }
+
+    for word in ["a7a8K", "a7a8P", "a7a8k", "a8K", "Pa8P", "a7a8X"] {
+        assert_eq!(Move::from_str(word), Err(()), "{word}");
+    }
+    Ok(())
+}
+
+/// Test choosing which of two pieces moves with a file or rank hint.
+#[test]
+fn disambiguation() -> Result<(), ()> {
+    init();
+    // Two rooks on the same rank can both reach d1
+    let board = Board::from_fen("4k3/8/8/8/8/8/4K3/R6R w - - 0 1").map_err(|_| ())?;
+    assert!(!board.is_legal_move(&Move::from_str("Rd1")?));
+    assert!(Move::from_str("Rad1")?.same_effect(&Move::from_str("a1d1")?, &board));
+    assert!(Move::from_str("Rhd1")?.same_effect(&Move::from_str("h1d1")?, &board));
+    assert!(board.is_legal_move(&Move::from_str("Rad1")?));
+    // A rank doesn't help when both rooks are on it
+    assert!(!board.is_legal_move(&Move::from_str("R1d1")?));
+    // Only one rook can reach a2
+    assert!(Move::from_str("Ra2")?.same_effect(&Move::from_str("a1a2")?, &board));
+
+    // Two knights on the same file can both reach c3
+    let board = Board::from_fen("4k3/8/8/1N6/8/8/8/1N2K3 w - - 0 1").map_err(|_| ())?;
+    assert!(!board.is_legal_move(&Move::from_str("Nc3")?));
+    assert!(Move::from_str("N1c3")?.same_effect(&Move::from_str("b1c3")?, &board));
+    assert!(Move::from_str("N5c3")?.same_effect(&Move::from_str("b5c3")?, &board));
+    assert!(Move::from_str("Nb1c3")?.same_effect(&Move::from_str("b1c3")?, &board));
+    // A hint that matches no knight is illegal
+    assert!(!board.is_legal_move(&Move::from_str("Nac3")?));
+
+    let mut board = board;
+    board.apply(Move::from_str("N1c3")?)?;
+    assert_eq!(board.get_piece(Tile::from_str("c3")?), Some(Piece::new(PieceType::Knight, Color::White)));
+    assert_eq!(board.get_piece(Tile::from_str("b5")?), Some(Piece::new(PieceType::Knight, Color::White)));
+
+    // Hints read back as they're written
+    for word in ["Nbd2", "N1d2", "Nb1d2", "Pe8Q", "Pae8Q"] {
+        assert_eq!(Move::from_str(word)?.to_string(), word);
+    }
+    assert_eq!(Move::from_str("Nxd2"), Err(()));
+    Ok(())
+}
+
+/// Test that malformed moves are errors instead of panics.
+#[test]
+fn malformed_moves() -> Result<(), ()> {
+    init();
+    let garbage = [
+        "$Ze4", "z9", "$", "$Pe", "$Pe44", "$pe4", "@", "P@", "Z@e4", "P@z9",
+        "e", "i1", "a0", "a9", "e2e", "e2z9", "e2e4X", "e2e4K", "e2e4e2", "Xe4", "Ne", "N",
+        "Nz9d2", "Nbbd2", "O-O-", "O-O-O-O", "0-0", "resign!", "é", "e2é4", "$Pé4", "♞f3",
+        "e2e4 z9", "pass $Ze4",
+    ];
+    for word in garbage {
+        assert_eq!(Move::from_str(word), Err(()), "{word}");
+    }
+    Ok(())
+}
+
+/// Test iterating over the pieces on the board.
+#[test]
+fn iter_pieces() -> Result<(), ()> {
+    init();
+    let boards = [
+        Board::default(),
+        Board::empty(),
+        Board::from_fen("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1").map_err(|_| ())?,
+        Board::from_fen("7k/8/8/8/8/8/8/7K w - - 0 1").map_err(|_| ())?,
+    ];
+    for board in boards {
+        let naive: Vec<(Tile, Piece)> = Tile::all()
+            .filter_map(|tile| board.get_piece(tile).map(|piece| (tile, piece)))
+            .collect();
+        assert_eq!(board.iter_pieces().collect::<Vec<_>>(), naive);
+        for color in [Color::White, Color::Black] {
+            let naive: Vec<(Tile, Piece)> = naive.iter().copied().filter(|(_, piece)| piece.get_color() == color).collect();
+            assert_eq!(board.iter_pieces_of(color).collect::<Vec<_>>(), naive);
+        }
+    }
+    assert_eq!(Board::default().iter_pieces().count(), 32);
+    Ok(())
+}
+
+/// Test combining tile sets with set operations.
+#[test]
+fn tile_set_algebra() -> Result<(), ()> {
+    init();
+    let board = Board::default();
+    let white = board.find_pieces(Piece::new(PieceType::Pawn, Color::White)) | board.king_tiles(Color::White);
+    let scattered: TileSet = ["a2", "b2", "a5", "e1", "h8"].iter().map(|tile| Tile::from_str(tile)).collect::<Result<_, _>>()?;
+    let empty = TileSet::default();
+    let everything = !empty;
+    assert_eq!(everything.len(), Board::TILES);
+    assert_eq!(white.len(), 9);
+
+    for (a, b) in [(white, scattered), (scattered, white), (white, empty), (everything, scattered)] {
+        assert_eq!(a | b, b | a);
+        assert_eq!(a & b, b & a);
+        assert_eq!(a ^ b, (a | b) & !(a & b));
+        assert_eq!(!(a | b), !a & !b);
+        assert_eq!(!(a & b), !a | !b);
+        assert_eq!(a & !a, empty);
+        assert_eq!(a | !a, everything);
+        assert_eq!(a ^ a, empty);
+        assert_eq!(!!a, a);
+        for tile in Tile::all() {
+            assert_eq!((a & b).contains(tile), a.contains(tile) && b.contains(tile));
+            assert_eq!((a | b).contains(tile), a.contains(tile) || b.contains(tile));
+        }
+    }
+
+    let both: Vec<Tile> = (white & scattered).into_iter().collect();
+    assert_eq!(both, vec![Tile::from_str("e1")?, Tile::from_str("a2")?, Tile::from_str("b2")?]);
+    assert_eq!(TileSet::from_bits(white.to_bits()), white);
+    assert_eq!(white.into_iter().collect::<TileSet>(), white);
+    let mut count = 0;
+    for tile in scattered {
+        assert!(scattered.contains(tile));
+        count += 1;
+    }
+    assert_eq!(count, scattered.len());
+    Ok(())
+}
+
+/// Test getting the attacked and occupied tiles as tile sets.
+#[test]
+fn attack_sets() -> Result<(), ()> {
+    init();
+    let board = Board::from_fen("4k3/8/8/8/P7/8/8/R3K3 w - - 0 1").map_err(|_| ())?;
+    let white = board.attacks_by(Color::White);
+    for tile in Tile::all() {
+        assert_eq!(white.contains(tile), board.is_attacked(tile, Color::White), "{tile}");
+        assert_eq!(board.attacks_by(Color::Black).contains(tile), board.is_attacked(tile, Color::Black), "{tile}");
+    }
+
+    // The pawn blocks the rook, but the pawn's tile is still attacked
+    for tile in ["a2", "a3", "a4", "b1", "d1", "b5"] {
+        assert!(white.contains(Tile::from_str(tile)?), "{tile}");
+    }
+    for tile in ["a5", "a8", "a1", "c3"] {
+        assert!(!white.contains(Tile::from_str(tile)?), "{tile}");
+    }
+
+    let occupied: TileSet = ["a1", "e1", "a4", "e8"].iter().map(|tile| Tile::from_str(tile)).collect::<Result<_, _>>()?;
+    assert_eq!(board.occupied(), occupied);
+    assert_eq!(board.occupied_by(Color::White) | board.occupied_by(Color::Black), occupied);
+    assert_eq!(board.occupied_by(Color::Black), board.king_tiles(Color::Black));
+    assert_eq!((board.attacks_by(Color::White) & board.occupied_by(Color::White)).len(), 2);
+    Ok(())
+}
+
+/// Test finding the pieces that attack a tile, when one of them is blocked by a pinned piece.
+#[test]
+fn attackers_of() -> Result<(), ()> {
+    init();
+    // The bishop on e2 is pinned to the king by the rook on e8,
+    // and the queen on a5 gives check along the diagonal
+    let board = Board::from_fen("4r1k1/8/8/q7/8/8/4B3/4K3 w - - 0 1").map_err(|_| ())?;
+    let tiles = |tiles: &[&str]| tiles.iter().map(|tile| Tile::from_str(tile)).collect::<Result<TileSet, _>>();
+    let king = Tile::from_str("e1")?;
+    let bishop = Tile::from_str("e2")?;
+
+    // The rook's attack stops at the pinned bishop
+    assert_eq!(board.attackers_of(king, Color::Black), tiles(&["a5"])?);
+    assert_eq!(board.attackers_of(bishop, Color::Black), tiles(&["e8"])?);
+    assert_eq!(board.attackers_of(bishop, Color::White), tiles(&["e1"])?);
+    assert_eq!(board.checkers(Color::White), tiles(&["a5"])?);
+    assert!(board.is_in_check(Color::White));
+    assert!(board.checkers(Color::Black).is_empty());
+
+    // Without the bishop, both pieces attack the king
+    let mut unpinned = board;
+    unpinned.remove_piece(bishop);
+    assert_eq!(unpinned.attackers_of(king, Color::Black), tiles(&["a5", "e8"])?);
+
+    // A tile has attackers exactly when it is in the aggregate attacks
+    for tile in Tile::all() {
+        let attackers = board.attackers_of(tile, Color::Black);
+        assert_eq!(attackers.is_empty(), !board.attacks_by(Color::Black).contains(tile), "{tile}");
+        for from in attackers {
+            assert!(board.piece_attacks_from(from).contains(tile));
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,1173 @@
+/*
+ * This is a test of the economy.
+ * It plays moves on a board with banks attached, and confirms
+ * the banks and markets enforce the rules of the game.
+ */
+
+use capitalist_chess::*;
+use std::str::FromStr;
+
+static mut ALREADY_INIT: bool = false;
+
+fn init() {
+    unsafe {
+        if ALREADY_INIT {
+            return;
+        }
+        ALREADY_INIT = true;
+    }
+    let _ = env_logger::builder().is_test(true).try_init();
+}
+
+/// This tests purchasing in a controlled sector outside of the home sectors.
+#[test]
+fn purchase_in_controlled_sector() -> Result<(), ()> {
+    init();
+    let purchase = Move::from_str("$Pc3")?;
+
+    for allowed in [true, false] {
+        let market = Market::default().with_purchase_in_controlled_sectors(allowed);
+        let mut board = StateCapitalistBoard::new(market);
+        // Take control of the center sector with c3 in it
+        board.apply(Move::from_str("d2d4")?)?;
+        board.apply(Move::from_str("a7a6")?)?;
+        assert_eq!(board.get_market().who_controls_sector(board.board(), Tile::from_str("c3")?.get_sector()), Some(Color::White));
+
+        assert_eq!(board.is_legal_move(&purchase), allowed);
+        assert_eq!(board.legal_moves().contains(&purchase), allowed);
+        assert_eq!(board.apply(purchase.clone()).is_ok(), allowed);
+    }
+
+    Ok(())
+}
+
+/// This tests which pieces a player can afford.
+#[test]
+fn affordable_pieces() -> Result<(), ()> {
+    init();
+    let cents = Currency::penny();
+    let market = Market::default().with_piece_values(cents * 10, cents * 30, cents * 35, cents * 50, cents * 90, cents * 200);
+    let board = StateCapitalistBoard::new(market);
+
+    // White starts with the income of its four home sectors
+    assert_eq!(board.get_balance(Color::White), Currency::doubloon() * 4);
+    assert_eq!(board.affordable_pieces(Color::White), vec![PieceType::Pawn, PieceType::Knight, PieceType::Bishop]);
+    assert!(board.affordable_pieces(Color::Black).is_empty());
+
+    Ok(())
+}
+
+/// This tests validating a market's configuration.
+#[test]
+fn market_validation() {
+    init();
+    assert_eq!(Market::default().validate(), Ok(()));
+    assert!(StateCapitalistBoard::try_new(Market::default()).is_ok());
+
+    let free_moves = Market::default().with_base_move_cost(Currency::zero());
+    assert_eq!(free_moves.validate(), Err(MarketError::NonPositiveBaseMoveCost));
+    assert_eq!(StateCapitalistBoard::try_new(free_moves), Err(MarketError::NonPositiveBaseMoveCost));
+
+    let negative_rook = Market::default().with_rook_value(Currency::penny() * -1);
+    assert_eq!(negative_rook.validate(), Err(MarketError::NegativePieceValue(PieceType::Rook)));
+}
+
+/// This tests which player collects income after a move, for each income timing.
+#[test]
+fn income_timing() -> Result<(), ()> {
+    init();
+    let cents = Currency::penny();
+    let base = Market::default().with_base_move_cost(cents);
+
+    // Income on your turn start: the mover pays, and the opponent collects
+    let mut board = StateCapitalistBoard::new(base.with_income_timing(IncomeTiming::TurnStart));
+    assert_eq!(board.get_balance(Color::White), cents * 40);
+    assert_eq!(board.get_balance(Color::Black), Currency::zero());
+    board.apply(Move::from_str("e2e4")?)?;
+    assert_eq!(board.get_balance(Color::White), cents * 39);
+    assert_eq!(board.get_balance(Color::Black), cents * 40);
+
+    // Income after your move: the mover pays, then collects
+    let mut board = StateCapitalistBoard::new(base.with_income_timing(IncomeTiming::AfterMove));
+    assert_eq!(board.get_balance(Color::White), cents * 40);
+    assert_eq!(board.get_balance(Color::Black), cents * 40);
+    board.apply(Move::from_str("e2e4")?)?;
+    // The pawn on e4 takes a center sector, which pays out immediately
+    assert_eq!(board.get_balance(Color::White), cents * 99);
+    assert_eq!(board.get_balance(Color::Black), cents * 40);
+
+    Ok(())
+}
+
+/// This tests borrowing the underlying board.
+#[test]
+fn borrow_board() -> Result<(), ()> {
+    init();
+    let mut board = StateCapitalistBoard::default();
+    assert_eq!(board.board().whose_turn(), board.whose_turn());
+    board.apply(Move::from_str("e2e4")?)?;
+    assert_eq!(board.board().whose_turn(), board.whose_turn());
+    assert_eq!(*board.board(), Board::from(board));
+
+    Ok(())
+}
+
+/// This tests that only the promotions a player can afford are offered.
+#[test]
+fn affordable_promotions() -> Result<(), ()> {
+    init();
+    let cents = Currency::penny();
+    let expensive = cents * 10000;
+    let market = Market::default()
+        .with_base_move_cost(cents)
+        .with_piece_values(cents, cents, expensive, expensive, expensive, expensive)
+        .with_promotion_surcharge_rate(1.0);
+    let mut board = StateCapitalistBoard::new(market);
+
+    // Walk the a-pawn up to a7, where it can capture the knight on b8
+    for player_move in ["a2a4", "b7b5", "a4b5", "a7a6", "b5a6", "h7h6", "a6a7", "h6h5"] {
+        board.apply(Move::from_str(player_move)?)?;
+    }
+
+    let from = Tile::from_str("a7")?;
+    let to = Tile::from_str("b8")?;
+    let promotions: Vec<Move> = board.legal_moves()
+        .into_iter()
+        .filter(|player_move| matches!(player_move, Move::FromTo { from: f, to: t, .. } if *f == from && *t == to))
+        .collect();
+    assert_eq!(promotions, vec![Move::new(from, to, Some(PieceType::Knight))]);
+
+    // Promoting without a choice defaults to a queen, and is charged like one
+    assert!(!board.is_legal_move(&Move::new(from, to, Some(PieceType::Queen))));
+    assert!(!board.is_legal_move(&Move::new(from, to, None)));
+    assert!(board.apply(Move::new(from, to, None)).is_err());
+    board.apply(Move::new(from, to, Some(PieceType::Knight)))?;
+
+    Ok(())
+}
+
+/// This tests taking percentages and fractions of currency.
+#[test]
+fn currency_fractions() {
+    init();
+    let cents = |amount: i32| Currency::penny() * amount;
+    assert_eq!(cents(5).percent(50), cents(3));
+    assert_eq!(cents(7).percent(50), cents(4));
+    assert_eq!(cents(4).percent(50), cents(2));
+    assert_eq!(cents(-5).percent(50), cents(-3));
+    assert_eq!(cents(10).fraction(1, 3), Some(cents(3)));
+    assert_eq!(cents(20).fraction(1, 3), Some(cents(7)));
+    assert_eq!(cents(20).fraction(-1, 3), Some(cents(-7)));
+    assert_eq!(cents(20).fraction(1, 0), None);
+    assert_eq!(cents(5) * 0.5, cents(5).percent(50));
+
+    // Multiplying rounds to the nearest penny instead of truncating
+    assert_eq!(cents(5) * 0.5, cents(3));
+    assert_eq!(cents(9) * 0.5, cents(5));
+    assert_eq!(cents(-5) * 0.5, cents(-3));
+    assert_eq!(cents(10) * 0.19, cents(2));
+    assert_eq!(cents(10) * 0.14, cents(1));
+}
+
+/// This tests the reason each illegal purchase is rejected.
+#[test]
+fn purchase_errors() -> Result<(), ()> {
+    init();
+    let cents = Currency::penny();
+    let market = Market::default()
+        .with_king_value(cents * 10)
+        .with_purchasable_pieces(&PieceType::PURCHASES);
+    let board = StateCapitalistBoard::new(market);
+    let tile = |name: &str| Tile::from_str(name).unwrap();
+
+    assert_eq!(board.validate_purchase(PieceType::Pawn, tile("e2")), Err(PurchaseError::Occupied));
+    assert_eq!(board.validate_purchase(PieceType::Pawn, tile("e4")), Err(PurchaseError::NotPurchasableSector));
+
+    // The black queen checks the white king
+    let mut checked = board;
+    for player_move in ["f2f3", "e7e6", "a2a3", "d8h4"] {
+        checked.apply(Move::from_str(player_move)?)?;
+    }
+    assert_eq!(checked.validate_purchase(PieceType::Pawn, tile("f2")), Err(PurchaseError::InCheck));
+
+    // The black bishop attacks e2, but not the white king
+    let mut pinned = board;
+    for player_move in ["e2e3", "b7b6", "a2a3", "c8a6"] {
+        pinned.apply(Move::from_str(player_move)?)?;
+    }
+    assert_eq!(pinned.validate_purchase(PieceType::Queen, tile("e2")), Err(PurchaseError::CannotAfford));
+    assert_eq!(pinned.validate_purchase(PieceType::King, tile("e2")), Err(PurchaseError::LeavesKingInCheck));
+    assert_eq!(pinned.validate_purchase(PieceType::Pawn, tile("e2")), Ok(()));
+    assert!(!pinned.is_legal_move(&Move::Purchase { piece: PieceType::King, to: tile("e2") }));
+
+    Ok(())
+}
+
+/// This tests the events reported while applying moves.
+#[test]
+fn observe_events() -> Result<(), ()> {
+    init();
+    let mut board = StateCapitalistBoard::default();
+    board.apply(Move::from_str("e2e4")?)?;
+    board.apply(Move::from_str("d7d5")?)?;
+
+    let mut events = Vec::new();
+    board.apply_with_observer(Move::from_str("e4d5")?, Some(&mut |event| events.push(event))).map_err(|_| ())?;
+    assert!(events.contains(&GameEvent::Captured {
+        piece: Piece::new(PieceType::Pawn, Color::Black),
+        tile: Tile::from_str("d5")?,
+    }));
+    assert_eq!(events.iter().filter(|event| matches!(event, GameEvent::Captured { .. })).count(), 1);
+    assert!(events.iter().any(|event| matches!(event, GameEvent::IncomeGranted { color: Color::Black, .. })));
+    assert!(!events.iter().any(|event| matches!(event, GameEvent::CheckDelivered { .. } | GameEvent::GameOver(_))));
+
+    // Illegal moves report why they failed, and nothing happens
+    let mut events = Vec::new();
+    assert_eq!(board.apply_with_observer(Move::from_str("a2a5")?, Some(&mut |event| events.push(event))), Err(MoveError::Illegal));
+    assert!(events.is_empty());
+
+    Ok(())
+}
+
+/// This tests that center sectors are harder to control with a center control weight.
+#[test]
+fn center_control_weight() -> Result<(), ()> {
+    init();
+    let tile = |name: &str| Tile::from_str(name).unwrap();
+    let mut board = Board::empty();
+    board.spawn_white_king(tile("h1"));
+    board.spawn_black_king(tile("h8"));
+    // Two pawns against one in a center sector
+    board.spawn_white_pawn(tile("c3"));
+    board.spawn_white_pawn(tile("d3"));
+    board.spawn_black_pawn(tile("d4"));
+    // Two pawns against one in an outer sector
+    board.spawn_white_pawn(tile("a2"));
+    board.spawn_white_pawn(tile("b2"));
+    board.spawn_black_pawn(tile("a1"));
+
+    let center = tile("c3").get_sector();
+    let outer = tile("a2").get_sector();
+    assert!(center.is_center() && outer.is_outer());
+
+    // Exactly twice the value isn't more than twice the value
+    let market = Market::default().with_center_control_weight(2.0);
+    assert_eq!(market.validate(), Ok(()));
+    assert_eq!(market.who_controls_sector(&board, center), None);
+    assert_eq!(market.who_controls_sector(&board, outer), Some(Color::White));
+    let market = Market::default().with_center_control_weight(1.9);
+    assert_eq!(market.who_controls_sector(&board, center), Some(Color::White));
+
+    let market = Market::default();
+    assert_eq!(market.who_controls_sector(&board, center), Some(Color::White));
+    assert_eq!(market.who_controls_sector(&board, outer), Some(Color::White));
+
+    let market = Market::default().with_center_control_weight(0.5);
+    assert_eq!(market.validate(), Err(MarketError::InvalidCenterControlWeight));
+
+    Ok(())
+}
+
+/// This tests that purchases destroy money instead of transferring it.
+#[test]
+fn total_money() -> Result<(), ()> {
+    init();
+    let mut board = StateCapitalistBoard::default();
+    let market = *board.get_market();
+    assert_eq!(board.total_money(), board.get_balance(Color::White) + board.get_balance(Color::Black));
+
+    let purchase = Move::Purchase { piece: PieceType::Pawn, to: Tile::from_str("e2")? };
+    for player_move in [Move::from_str("e2e4")?, Move::from_str("e7e5")?, purchase] {
+        let total = board.total_money();
+        let opponent_balance = board.get_balance(!board.whose_turn());
+        let mut income = Currency::zero();
+        board.apply_with_observer(player_move.clone(), Some(&mut |event| {
+            if let GameEvent::IncomeGranted { amount, .. } = event {
+                income += amount;
+            }
+        })).map_err(|_| ())?;
+
+        // The cost is destroyed, and only income is created
+        assert_eq!(board.total_money(), total - market.get_move_value(&player_move) + income);
+        // The opponent only gains their income
+        assert_eq!(board.get_balance(board.whose_turn()), opponent_balance + income);
+    }
+
+    Ok(())
+}
+
+/// This tests limiting the number of actions in a turn.
+#[test]
+fn actions_per_turn() -> Result<(), ()> {
+    init();
+    let cents = Currency::penny();
+    let market = Market::default()
+        .with_base_move_cost(cents)
+        .with_max_actions_per_turn(2);
+    let board = StateCapitalistBoard::new(market);
+
+    let two_moves = Move::from_str("e2e4 g1f3")?;
+    let three_moves = Move::from_str("e2e4 g1f3 d2d4")?;
+    assert_eq!(two_moves.action_count(), 2);
+    assert!(board.is_legal_move(&Move::from_str("e2e4")?));
+    assert!(board.is_legal_move(&two_moves));
+    assert!(!board.is_legal_move(&three_moves));
+    assert!(!board.is_legal_move(&Move::Many(vec![])));
+    assert!(board.clone().apply(three_moves).is_err());
+
+    // Every turn must include a second action
+    let market = market.with_min_actions_per_turn(2);
+    let board = StateCapitalistBoard::new(market);
+    assert!(!board.is_legal_move(&Move::from_str("e2e4")?));
+    assert!(board.is_legal_move(&two_moves));
+    assert!(board.is_legal_move(&Move::Pass));
+    let moves = board.legal_moves();
+    assert!(!moves.is_empty());
+    assert!(moves.iter().all(|player_move| *player_move == Move::Pass || player_move.action_count() == 2));
+    // The same actions can be played in either order, and a piece can act twice
+    assert!(moves.contains(&Move::from_str("g1f3 e2e4")?));
+    assert!(moves.contains(&Move::from_str("e2e4 g1f3")?));
+    assert!(moves.contains(&Move::from_str("e2e3 e3e4")?));
+    assert!(!moves.contains(&Move::from_str("e2e4 e4e5 e5e6")?));
+
+    assert_eq!(market.with_min_actions_per_turn(3).validate(), Err(MarketError::InvalidActionsPerTurn));
+    assert_eq!(market.with_min_actions_per_turn(0).validate(), Err(MarketError::InvalidActionsPerTurn));
+
+    Ok(())
+}
+
+/// This tests that turns of every allowed length are generated, like buying a piece and then moving it.
+#[test]
+fn buy_then_move() -> Result<(), ()> {
+    init();
+    let market = Market::default()
+        .with_base_move_cost(Currency::penny())
+        .with_purchasable_pieces(&[PieceType::Pawn])
+        .with_max_actions_per_turn(2);
+    let mut board = StateCapitalistBoard::new(market);
+    *board.board_mut() = Board::from_fen("4k3/8/8/8/8/8/8/4K3 w - - 0 1").map_err(|_| ())?;
+
+    let moves = board.legal_moves();
+    assert!(moves.contains(&Move::from_str("e1d1")?));
+    assert!(moves.contains(&Move::from_str("$Pe2")?));
+    assert!(moves.contains(&Move::from_str("$Pe2 e2e4")?));
+    assert!(moves.iter().all(|player_move| player_move.action_count() <= 2));
+    // Single actions come first, and passing comes last
+    let first_turn = moves.iter().position(|player_move| player_move.action_count() == 2).ok_or(())?;
+    assert!(moves[..first_turn].iter().all(|player_move| player_move.action_count() == 1));
+    assert_eq!(moves.last(), Some(&Move::Pass));
+
+    // A player who can't afford a second action only gets turns of one
+    let market = market.with_base_move_cost(Currency::doubloon() * 4);
+    let board = StateCapitalistBoard::new(market);
+    assert!(board.legal_moves().iter().all(|player_move| player_move.action_count() <= 1));
+    Ok(())
+}
+
+/// This tests running a census after setting up the board by hand.
+#[test]
+fn run_census() -> Result<(), ()> {
+    init();
+    let mut board = StateCapitalistBoard::default();
+    let market = *board.get_market();
+    let outer = Currency::doubloon();
+    assert_eq!(market.get_sector_value(Tile::from_str("a5")?.get_sector()), outer);
+
+    // White starts with its four home sectors
+    let white = board.get_balance(Color::White);
+    let black = board.get_balance(Color::Black);
+    board.run_census(Color::White);
+    assert_eq!(board.get_balance(Color::White), white + outer * 4);
+    assert_eq!(board.get_balance(Color::Black), black);
+
+    // Seize an empty outer sector
+    board.board_mut().spawn_white_knight(Tile::from_str("a5")?);
+    let white = board.get_balance(Color::White);
+    board.run_census(Color::White);
+    assert_eq!(board.get_balance(Color::White), white + outer * 5);
+
+    let white = board.get_balance(Color::White);
+    board.run_census_all();
+    assert_eq!(board.get_balance(Color::White), white + outer * 5);
+    assert_eq!(board.get_balance(Color::Black), black + outer * 4);
+    Ok(())
+}
+
+/// This tests that the legal moves come out in a stable order.
+#[test]
+fn legal_move_order() -> Result<(), ()> {
+    init();
+    let cents = Currency::penny();
+    let market = Market::default()
+        .with_base_move_cost(cents)
+        .with_piece_values(cents, cents, cents, cents, cents, cents)
+        .with_max_actions_per_turn(1);
+    let mut board = StateCapitalistBoard::new(market);
+    for player_move in ["e2e4", "e7e5", "g1f3", "b8c6", "f1c4", "g8f6"] {
+        board.apply(Move::from_str(player_move)?)?;
+    }
+
+    let moves = board.legal_moves();
+    assert_eq!(moves, board.legal_moves());
+    let golden = |names: &[&str]| names.iter().map(|name| Move::from_str(name)).collect::<Result<Vec<_>, _>>();
+    // Purchases by piece, then tile
+    assert_eq!(moves[..6], golden(&["$Pf1", "$Pg1", "$Pe2", "$Nf1", "$Ng1", "$Ne2"])?);
+    // Board moves by from tile, then to tile
+    assert_eq!(moves[15..22], golden(&["b1a3", "b1c3", "d1e2", "e1f1", "e1e2", "h1f1", "h1g1"])?);
+    // Castling, then passing
+    assert_eq!(moves[moves.len() - 2..], golden(&["O-O", "pass"])?);
+    let mut unsorted = board.legal_moves_in_order(false);
+    assert_eq!(unsorted.len(), moves.len());
+    unsorted.retain(|player_move| !moves.contains(player_move));
+    assert!(unsorted.is_empty());
+    Ok(())
+}
+
+/// This tests saving a market to a config string and reading it back.
+#[test]
+fn market_config_round_trip() {
+    init();
+    let cents = Currency::penny();
+    let market = Market::default()
+        .with_piece_values(cents * 3, cents * 7, cents * 8, cents * 12, cents * 25, cents * 400)
+        .with_base_move_cost(cents * 4)
+        .with_castling_value(cents * 6)
+        .with_center_sector_income_value(cents * 30)
+        .with_outer_sector_income_value(cents * 11)
+        .with_interest_rate(1.37)
+        .with_promotion_surcharge_rate(0.1)
+        .with_purchase_in_controlled_sectors(true)
+        .with_income_timing(IncomeTiming::AfterMove)
+        .with_center_control_weight(1.5)
+        .with_min_actions_per_turn(1)
+        .with_max_actions_per_turn(3);
+    let config = market.to_config_string();
+    assert!(config.contains("move_interest_rate=1.37\n"));
+    assert_eq!(Market::from_config_string(&config), Ok(market));
+    assert_eq!(Market::from_config_string(&Market::default().to_config_string()), Ok(Market::default()));
+
+    // Comments and missing settings are allowed
+    let market = Market::from_config_string("# A cheap game\n\nbase_move_cost = 1\n").unwrap();
+    assert_eq!(market, Market::default().with_base_move_cost(cents));
+
+    assert_eq!(Market::from_config_string("pawn_value"), Err(MarketConfigError::InvalidLine(1)));
+    assert_eq!(Market::from_config_string("\nbananas=3"), Err(MarketConfigError::UnknownKey(2)));
+    assert_eq!(Market::from_config_string("income_timing=never"), Err(MarketConfigError::InvalidValue(1)));
+    assert_eq!(Market::from_config_string("base_move_cost=0"), Err(MarketConfigError::InvalidMarket(MarketError::NonPositiveBaseMoveCost)));
+}
+
+/// This tests counting the plies since the last capture, pawn move, or purchase.
+#[test]
+fn plies_since_progress() -> Result<(), ()> {
+    init();
+    let cents = Currency::penny();
+    let market = Market::default()
+        .with_base_move_cost(cents)
+        .with_piece_values(cents, cents, cents, cents, cents, cents);
+    let mut board = StateCapitalistBoard::new(market);
+
+    for (player_move, plies) in [
+        ("e2e4", 0),
+        ("d7d5", 0),
+        // Quiet king moves
+        ("e1e2", 1),
+        ("e8d7", 2),
+        // A capture
+        ("e4d5", 0),
+        ("d7d6", 1),
+        ("e2e3", 2),
+        // A purchase
+        ("$Pe8", 0),
+        ("pass", 1),
+        // A turn of several quiet moves is still one ply
+        ("d6d7 d7d6", 2),
+        // A turn is progress if any of its moves are
+        ("e3f3 a2a3", 0),
+    ] {
+        board.apply(Move::from_str(player_move)?)?;
+        assert_eq!(board.board().plies_since_progress(), plies, "after {player_move}");
+    }
+
+    Ok(())
+}
+
+/// This tests claiming a draw after fifty moves by each player without progress.
+#[test]
+fn claim_draw() -> Result<(), ()> {
+    init();
+    let mut board = StateCapitalistBoard::default();
+    *board.board_mut() = Board::from_fen("4k3/8/8/8/8/8/8/4K3 w - - 99 1").map_err(|_| ())?;
+    assert!(!board.is_legal_move(&Move::ClaimDraw));
+    assert!(!board.legal_moves().contains(&Move::ClaimDraw));
+
+    board.apply(Move::from_str("e1e2")?)?;
+    assert!(board.is_game_over().is_none());
+    assert!(board.is_legal_move(&Move::ClaimDraw));
+    board.apply(Move::from_str("draw")?)?;
+    assert_eq!(board.is_game_over(), Some(GameResult::Draw));
+    assert!(board.legal_moves().is_empty());
+    assert!(board.clone().apply(Move::from_str("e2e3")?).is_err());
+
+    // The claim is kept with the position
+    let fen = board.board().to_fen();
+    assert!(fen.ends_with(" 1/2-1/2"));
+    assert!(Board::from_fen(&fen).map_err(|_| ())?.is_drawn());
+
+    // Without a claim, the game is drawn after seventy-five moves by each player
+    *board.board_mut() = Board::from_fen("4k3/8/8/8/8/8/8/4K3 w - - 149 1").map_err(|_| ())?;
+    assert!(board.is_game_over().is_none());
+    board.apply(Move::from_str("e1e2")?)?;
+    assert_eq!(board.is_game_over(), Some(GameResult::Draw));
+    Ok(())
+}
+
+/// This tests that repricing pieces changes sector control and material alike.
+#[test]
+fn market_valuation() -> Result<(), ()> {
+    init();
+    let tile = |name: &str| Tile::from_str(name);
+    let mut board = StateCapitalistBoard::default();
+    *board.board_mut() = Board::from_fen("4k3/8/1r6/N7/8/8/8/4K3 w - - 0 1").map_err(|_| ())?;
+    let sector = tile("a5")?.get_sector();
+    assert_eq!(sector, tile("b6")?.get_sector());
+
+    // By default, the rook is worth more than the knight
+    let market = Market::default();
+    assert_eq!(market.who_controls_sector(board.board(), sector), Some(Color::Black));
+    assert!(board.material_view().market_value(Color::Black, &market) > board.material_view().market_value(Color::White, &market));
+
+    // Priced above the rook, the knight wins the sector and the material count
+    let cents = Currency::penny();
+    let market = market.with_knight_value(cents * 200).with_rook_value(cents * 50);
+    assert_eq!(market.who_controls_sector(board.board(), sector), Some(Color::White));
+    assert!(board.material_view().market_value(Color::White, &market) > board.material_view().market_value(Color::Black, &market));
+
+    // The banks are paid using their market's prices
+    let mut board = StateCapitalistBoard::new(market);
+    *board.board_mut() = Board::from_fen("4k3/8/1r6/N7/8/8/8/4K3 w - - 0 1").map_err(|_| ())?;
+    let balance = board.get_balance(Color::White);
+    board.run_census(Color::White);
+    assert_eq!(board.get_balance(Color::White), balance + market.get_sector_value(sector) * 2);
+    Ok(())
+}
+
+/// This tests finding the moves that stalemate the opponent.
+#[test]
+fn results_in_stalemate() -> Result<(), ()> {
+    init();
+    let fen = "7k/5K2/4Q3/8/8/8/8/8 w - - 0 1";
+    let stalemate = Move::from_str("e6g6")?;
+    let quiet = Move::from_str("e6e7")?;
+
+    let board = Board::from_fen(fen).map_err(|_| ())?;
+    assert!(board.results_in_stalemate(&stalemate));
+    assert!(!board.results_in_stalemate(&quiet));
+
+    // With the default market, black can still pass
+    let mut game = StateCapitalistBoard::default();
+    *game.board_mut() = board;
+    assert!(!game.results_in_stalemate(&stalemate));
+
+    // When black can't afford to pass or buy anything, it's a real stalemate
+    let market = Market::default().with_pass_value(Currency::doubloon() * 100);
+    let mut game = StateCapitalistBoard::new(market);
+    *game.board_mut() = board;
+    assert!(game.results_in_stalemate(&stalemate));
+    assert!(!game.results_in_stalemate(&quiet));
+    Ok(())
+}
+
+/// This tests that the game is over after a forced mate, and not before.
+#[test]
+fn is_game_over() -> Result<(), ()> {
+    init();
+    let mut board = StateCapitalistBoard::default();
+    assert_eq!(board.is_game_over(), None);
+
+    for player_move in ["f2f3", "e7e5", "g2g4"] {
+        board.apply(Move::from_str(player_move)?)?;
+        assert_eq!(board.is_game_over(), None);
+    }
+
+    board.apply(Move::from_str("d8h4")?)?;
+    assert_eq!(board.is_game_over(), Some(GameResult::Win(Color::Black)));
+
+    // A player with no piece moves isn't stalemated while they can pass or buy a piece
+    let mut board = StateCapitalistBoard::default();
+    *board.board_mut() = Board::from_fen("7k/5K2/4Q3/8/8/8/8/8 w - - 0 1").map_err(|_| ())?;
+    board.apply(Move::from_str("e6g6")?)?;
+    assert!(board.board().is_stalemate());
+    assert!(!board.legal_moves().is_empty());
+    assert_eq!(board.is_game_over(), None);
+
+    // Once they can't afford either, it's a stalemate
+    let expensive = Currency::doubloon() * 1000;
+    let market = Market::default()
+        .with_pass_value(expensive)
+        .with_piece_values(expensive, expensive, expensive, expensive, expensive, expensive);
+    let mut board = StateCapitalistBoard::new(market);
+    *board.board_mut() = Board::from_fen("7k/5K2/4Q3/8/8/8/8/8 w - - 0 1").map_err(|_| ())?;
+    board.apply(Move::from_str("e6g6")?)?;
+    assert!(board.legal_moves().is_empty());
+    assert_eq!(board.is_game_over(), Some(GameResult::Draw));
+    Ok(())
+}
+
+/// This tests buying an extra move to make two board moves in one turn.
+#[test]
+fn extra_moves() -> Result<(), ()> {
+    init();
+    let market = Market::default().with_interest_rate(3.0);
+    let mut board = StateCapitalistBoard::new(market);
+    let balance = board.get_balance(Color::White);
+
+    let first = Move::from_str("e2e4")?;
+    let second = Move::from_str("g1f3")?;
+    let turn = Move::Many(vec![first.clone(), second.clone()]);
+
+    // The second move costs the interest rate on top of the first
+    let base = market.get_move_value(&first);
+    assert_eq!(market.get_action_value(&second, 0), base);
+    assert_eq!(market.get_action_value(&second, 1), base * 3);
+    assert_eq!(market.get_move_value(&turn), base * 4);
+
+    board.apply(turn)?;
+    assert_eq!(board.get_balance(Color::White), balance - base * 4);
+    assert_eq!(board.get_piece(Tile::from_str("e4")?), Some(Piece::new(PieceType::Pawn, Color::White)));
+    assert_eq!(board.get_piece(Tile::from_str("f3")?), Some(Piece::new(PieceType::Knight, Color::White)));
+    assert_eq!(board.whose_turn(), Color::Black);
+
+    // A pass isn't an extra action
+    let mut board = StateCapitalistBoard::new(market);
+    assert!(!board.is_legal_move(&Move::Many(vec![first, Move::Pass])));
+
+    // A player who can't afford the interest can't make the extra move
+    let market = Market::default().with_interest_rate(1000.0);
+    board = StateCapitalistBoard::new(market);
+    assert!(!board.is_legal_move(&Move::Many(vec![Move::from_str("e2e4")?, second])));
+    Ok(())
+}
+
+/// This tests that a player's first capture pays double the normal bounty.
+#[test]
+fn first_capture_bonus() -> Result<(), ()> {
+    init();
+    let market = Market::default().with_capture_bounty_rate(0.5);
+    let pawn_bounty = market.get_capture_bounty(PieceType::Pawn, false);
+    assert!(pawn_bounty.is_surplus());
+    assert_eq!(market.get_capture_bounty(PieceType::Pawn, true), pawn_bounty * 2);
+
+    let mut board = StateCapitalistBoard::new(market);
+    let move_cost = market.get_base_move_cost();
+    for player_move in ["e2e4", "d7d5"] {
+        board.apply(Move::from_str(player_move)?)?;
+    }
+    assert!(!board.get_bank(Color::White).has_captured());
+
+    // White's first capture is paid double
+    let balance = board.get_balance(Color::White);
+    board.apply(Move::from_str("e4d5")?)?;
+    assert!(board.get_bank(Color::White).has_captured());
+    assert_eq!(board.get_balance(Color::White), balance - move_cost + pawn_bounty * 2);
+
+    // Every capture after that is paid the normal rate
+    board.apply(Move::from_str("c7c6")?)?;
+    let balance = board.get_balance(Color::White);
+    board.apply(Move::from_str("d5c6")?)?;
+    assert_eq!(board.get_balance(Color::White), balance - move_cost + pawn_bounty);
+
+    // Black hasn't captured yet, so their first capture is still paid double
+    let balance = board.get_balance(Color::Black);
+    board.apply(Move::from_str("b7c6")?)?;
+    assert_eq!(board.get_balance(Color::Black), balance - move_cost + pawn_bounty * 2);
+    Ok(())
+}
+
+/// This tests buying a piece into the reserve, and deploying it later.
+#[test]
+fn reserve_and_deploy() -> Result<(), ()> {
+    init();
+    let market = Market::default()
+        .with_purchase_into_reserve(true)
+        .with_knight_value(Currency::doubloon());
+    let mut board = StateCapitalistBoard::new(market);
+    for player_move in ["e2e4", "e7e5"] {
+        board.apply(Move::from_str(player_move)?)?;
+    }
+    let e2 = Tile::from_str("e2")?;
+
+    // Pieces can't be placed on the board directly
+    assert_eq!(board.validate_purchase(PieceType::Knight, e2), Err(PurchaseError::ReserveOnly));
+
+    // Reserving the knight pays for it, but leaves the board alone
+    let balance = board.get_balance(Color::White);
+    let before = *board.board();
+    board.apply(Move::from_str("$N")?)?;
+    assert_eq!(board.get_balance(Color::White), balance - market.get_piece_value(PieceType::Knight));
+    assert_eq!(board.get_bank(Color::White).get_reserve_count(PieceType::Knight), 1);
+    assert!(Tile::all().all(|tile| board.get_piece(tile) == before.get_piece(tile)));
+
+    board.apply(Move::from_str("d7d6")?)?;
+
+    // Only reserved pieces can be deployed
+    assert_eq!(board.validate_deploy(PieceType::Queen, e2), Err(PurchaseError::NotInReserve));
+    assert!(!board.is_legal_move(&Move::from_str("Q@e2")?));
+    assert!(board.legal_moves().contains(&Move::from_str("N@e2")?));
+
+    // Deploying the knight is free, and empties the reserve
+    let balance = board.get_balance(Color::White);
+    board.apply(Move::from_str("N@e2")?)?;
+    assert_eq!(board.get_piece(e2), Some(Piece::new(PieceType::Knight, Color::White)));
+    assert_eq!(board.get_balance(Color::White), balance);
+    assert_eq!(board.get_bank(Color::White).get_reserve_count(PieceType::Knight), 0);
+    Ok(())
+}
+
+/// This tests that a full reserve turns away another piece instead of overflowing.
+#[test]
+fn reserve_full() -> Result<(), ()> {
+    init();
+    let market = Market::default()
+        .with_purchase_into_reserve(true)
+        .with_pawn_value(Currency::zero())
+        .with_base_move_cost(Currency::penny());
+    let mut bank = Bank::new(Color::White, market);
+    for _ in 0..u8::MAX {
+        assert!(bank.add_to_reserve(PieceType::Pawn));
+    }
+    assert!(!bank.has_reserve_room(PieceType::Pawn));
+    assert!(!bank.add_to_reserve(PieceType::Pawn));
+    assert_eq!(bank.get_reserve_count(PieceType::Pawn), u8::MAX);
+
+    let mut board = StateCapitalistBoard::new(market);
+    for _ in 0..u8::MAX {
+        board.apply(Move::Reserve(PieceType::Pawn))?;
+        board.apply(Move::Pass)?;
+    }
+    assert_eq!(board.get_bank(Color::White).get_reserve_count(PieceType::Pawn), u8::MAX);
+    assert_eq!(board.validate_reserve(PieceType::Pawn), Err(PurchaseError::ReserveFull));
+    assert!(!board.is_legal_move(&Move::Reserve(PieceType::Pawn)));
+    assert!(board.apply(Move::Reserve(PieceType::Pawn)).is_err());
+    assert_eq!(board.validate_reserve(PieceType::Knight), Ok(()));
+    Ok(())
+}
+
+/// This tests that checkmate ends the game, and no more moves are accepted.
+#[test]
+fn checkmate_sets_winner() -> Result<(), ()> {
+    init();
+    let mut board = StateCapitalistBoard::default();
+    for player_move in ["f2f3", "e7e5", "g2g4"] {
+        board.apply(Move::from_str(player_move)?)?;
+        assert_eq!(board.get_winner(), None);
+    }
+
+    board.apply(Move::from_str("d8h4")?)?;
+    assert_eq!(board.whose_turn(), Color::White);
+    assert_eq!(board.get_winner(), Some(Color::Black));
+    assert_eq!(board.is_game_over(), Some(GameResult::Win(Color::Black)));
+
+    // The game is over, so white can't keep playing
+    assert!(board.legal_moves().is_empty());
+    assert!(!board.is_legal_move(&Move::from_str("a2a3")?));
+    assert!(board.apply(Move::from_str("a2a3")?).is_err());
+
+    // A player who can only escape check with a promotion they can't afford is checkmated too
+    let market = Market::default().with_promotion_surcharge_rate(100.0);
+    let mut board = StateCapitalistBoard::new(market);
+    *board.board_mut() = Board::from_fen("r6k/1P6/8/8/8/1r6/8/K7 w - - 0 1").map_err(|_| ())?;
+    assert!(!board.board().is_in_checkmate(Color::White));
+    assert!(board.is_in_checkmate(Color::White));
+    assert_eq!(board.is_game_over(), Some(GameResult::Win(Color::Black)));
+    Ok(())
+}
+
+/// This tests that taking control of a sector pays the capture bonus once.
+#[test]
+fn sector_capture_bonus() -> Result<(), ()> {
+    init();
+    let bonus = Currency::doubloon() * 5;
+    let mut plain = StateCapitalistBoard::default();
+    let mut board = StateCapitalistBoard::new(Market::default().with_sector_capture_bonus(bonus));
+
+    // White takes the center sector with e4, and is paid at their next census
+    for (player_move, bonuses) in [("e2e4", 0), ("a7a6", 1), ("g1f3", 1), ("a6a5", 1), ("f3g1", 1), ("h7h6", 1)] {
+        plain.apply(Move::from_str(player_move)?)?;
+        board.apply(Move::from_str(player_move)?)?;
+        assert_eq!(board.get_balance(Color::White), plain.get_balance(Color::White) + bonus * bonuses);
+    }
+    Ok(())
+}
+
+/// This tests that a sector never pays more than the cap at a census.
+#[test]
+fn max_income_per_sector() -> Result<(), ()> {
+    init();
+    let cap = Currency::doubloon() * 3;
+    let market = Market::default()
+        .with_sector_capture_bonus(Currency::doubloon() * 5)
+        .with_max_income_per_sector(cap);
+    let center = Tile::from_str("e4")?.get_sector();
+    let outer = Tile::from_str("a1")?.get_sector();
+    assert_eq!(market.get_sector_income(center, true), cap);
+    assert_eq!(market.get_sector_income(center, false), market.get_sector_value(center));
+    assert_eq!(market.get_sector_income(outer, true), cap);
+    assert_eq!(market.get_sector_income(outer, false), market.get_sector_value(outer));
+
+    // Taking the center sector pays the capped bonus on top of the usual income
+    let mut plain = StateCapitalistBoard::default();
+    let mut board = StateCapitalistBoard::new(market);
+    for (player_move, extra) in [("e2e4", 0), ("a7a6", 1), ("g1f3", 1), ("a6a5", 1)] {
+        plain.apply(Move::from_str(player_move)?)?;
+        board.apply(Move::from_str(player_move)?)?;
+        let expected_extra = (cap - market.get_sector_value(center)) * extra;
+        assert_eq!(board.get_balance(Color::White), plain.get_balance(Color::White) + expected_extra);
+    }
+
+    assert_eq!(Market::from_config_string(&market.to_config_string()), Ok(market));
+    Ok(())
+}
+
+/// This tests forbidding a piece from acting twice in one turn.
+#[test]
+fn piece_may_act_once() -> Result<(), ()> {
+    init();
+    let double_hop = Move::from_str("g1f3 f3g5")?;
+    let two_pieces = Move::from_str("g1f3 b1c3")?;
+
+    let board = StateCapitalistBoard::default();
+    assert!(board.is_legal_move(&double_hop));
+    assert!(board.is_legal_move(&two_pieces));
+
+    let mut board = StateCapitalistBoard::new(Market::default().with_piece_may_act_once(true));
+    assert!(!board.is_legal_move(&double_hop));
+    assert!(!board.is_legal_move(&Move::from_str("e2e3 e3e4")?));
+    assert!(board.is_legal_move(&two_pieces));
+    board.apply(two_pieces)?;
+    assert_eq!(board.get_piece(Tile::from_str("c3")?), Some(Piece::new(PieceType::Knight, Color::White)));
+    Ok(())
+}
+
+/// This tests writing amounts of currency, including debt and custom symbols.
+#[test]
+fn currency_format() {
+    init();
+    let debt = Currency::penny() * -5i32;
+    assert_eq!(format!("{}", Currency::penny() * 5), "5¢");
+    assert_eq!(format!("{}", debt), "-5¢");
+    assert_eq!(format!("{:?}", Currency::zero()), "0¢");
+    assert_eq!(format!("{}", debt.with_symbol("¢").symbol_first()), "-¢5");
+
+    assert_eq!(format!("{}", Currency::doubloon().with_symbol("$").symbol_first()), "$10");
+    assert_eq!(format!("{}", debt.with_symbol("$").symbol_first()), "-$5");
+    assert_eq!(format!("{}", Currency::doubloon().with_symbol("⛁")), "10⛁");
+    assert_eq!(format!("{}", debt.with_symbol(" coins")), "-5 coins");
+
+    // Widths line amounts up in tables
+    assert_eq!(format!("{:3}|", Currency::penny() * 50i32), "  50¢|");
+    assert_eq!(format!("{:3}|", Currency::penny() * 50i32), format!("{:3}|", (Currency::penny() * 50i32).with_symbol("¢")));
+}
+
+/// This tests that the economy layer rejects purchases in the opponent's home,
+/// even though the board alone allows them.
+#[test]
+fn purchase_in_enemy_home() -> Result<(), ()> {
+    init();
+    let mut board = StateCapitalistBoard::default();
+    *board.board_mut() = Board::from_fen("4k3/8/8/8/8/8/8/4K3 w - - 0 1").map_err(|_| ())?;
+
+    for to in ["a7", "h8", "e7"] {
+        let purchase = Move::Purchase { piece: PieceType::Knight, to: Tile::from_str(to)? };
+        assert!(board.board().is_legal_move(&purchase));
+        assert!(!board.is_legal_move(&purchase));
+        assert_eq!(board.validate_purchase(PieceType::Knight, Tile::from_str(to)?), Err(PurchaseError::NotPurchasableSector));
+        assert!(board.clone().apply(purchase).is_err());
+    }
+
+    // The player's own home is fine
+    assert_eq!(board.validate_purchase(PieceType::Pawn, Tile::from_str("a2")?), Ok(()));
+    assert!(board.is_legal_move(&Move::Purchase { piece: PieceType::Pawn, to: Tile::from_str("a2")? }));
+    Ok(())
+}
+
+/// This tests creating a board without paying either player for their starting territory.
+#[test]
+fn new_without_census() -> Result<(), ()> {
+    init();
+    for timing in [IncomeTiming::TurnStart, IncomeTiming::AfterMove] {
+        let market = Market::default().with_income_timing(timing);
+        let board = StateCapitalistBoard::new_without_census(market);
+        assert_eq!(board.get_balance(Color::White), Currency::zero());
+        assert_eq!(board.get_balance(Color::Black), Currency::zero());
+        assert_eq!(board.board(), StateCapitalistBoard::new(market).board());
+
+        // The first census pays the usual income, without any capture bonus
+        let mut board = board;
+        board.run_census(Color::White);
+        assert_eq!(board.get_balance(Color::White), StateCapitalistBoard::new(market).get_balance(Color::White));
+    }
+
+    // The default board is paid for its starting territory
+    assert!(StateCapitalistBoard::default().get_balance(Color::White).is_surplus());
+    assert!(StateCapitalistBoard::try_new_without_census(Market::default().with_rook_value(Currency::penny() * -1i32)).is_err());
+    Ok(())
+}
+
+/// This tests limiting the piece types the market sells.
+#[test]
+fn purchasable_pieces() -> Result<(), ()> {
+    init();
+    let cents = Currency::penny();
+    let default = Market::default();
+    assert!(!default.can_purchase_piece(PieceType::King));
+    assert_eq!(default.get_purchasable_pieces(), vec![PieceType::Pawn, PieceType::Knight, PieceType::Bishop, PieceType::Rook, PieceType::Queen]);
+
+    // Make every piece cheap, so only the market's rules keep rooks out
+    let market = Market::default()
+        .with_piece_values(cents, cents, cents, cents, cents, cents)
+        .with_purchasable_pieces(&[PieceType::Knight, PieceType::Pawn]);
+    let mut board = StateCapitalistBoard::new(market);
+    *board.board_mut() = Board::from_fen("4k3/8/8/8/8/8/8/4K3 w - - 0 1").map_err(|_| ())?;
+    let purchases = Move::legal_purchases(board.board(), board.get_bank(Color::White));
+    assert!(!purchases.is_empty());
+    assert!(purchases.iter().all(|player_move| matches!(player_move,
+        Move::Purchase { piece: PieceType::Knight | PieceType::Pawn, .. })));
+    assert_eq!(board.affordable_pieces(Color::White), vec![PieceType::Pawn, PieceType::Knight]);
+
+    let rook = Move::Purchase { piece: PieceType::Rook, to: Tile::from_str("a2")? };
+    assert!(!board.legal_moves().contains(&rook));
+    assert!(!board.is_legal_move(&Move::from_str("$Ra2")?));
+    assert_eq!(board.validate_purchase(PieceType::Rook, Tile::from_str("a2")?), Err(PurchaseError::NotPurchasablePiece));
+
+    // The reserve only takes the pieces the market sells, too
+    let board = StateCapitalistBoard::new(market.with_purchase_into_reserve(true));
+    assert_eq!(board.validate_reserve(PieceType::Rook), Err(PurchaseError::NotPurchasablePiece));
+    assert!(board.is_legal_move(&Move::Reserve(PieceType::Knight)));
+
+    // The setting survives a round trip through a config string
+    let config = market.to_config_string();
+    assert!(config.contains("purchasable_pieces=PN\n"));
+    assert_eq!(Market::from_config_string(&config), Ok(market));
+    assert_eq!(Market::from_config_string("purchasable_pieces=PX"), Err(MarketConfigError::InvalidValue(1)));
+    Ok(())
+}
+
+/// This tests saving a game in the middle, and resuming it with the same economy.
+#[test]
+fn save_and_resume() -> Result<(), ()> {
+    init();
+    let market = Market::default().with_purchase_into_reserve(true).with_interest_rate(1.5);
+    let mut board = StateCapitalistBoard::new(market);
+    for player_move in ["e2e4", "d7d5", "e4d5", "d8d5", "$P"] {
+        board.apply(Move::from_str(player_move)?)?;
+    }
+    assert!(board.get_bank(Color::White).has_captured());
+    assert_eq!(board.get_bank(Color::White).get_reserve_count(PieceType::Pawn), 1);
+
+    let repr = board.to_string_repr();
+    let resumed = StateCapitalistBoard::from_string_repr(&repr).map_err(|_| ())?;
+    assert_eq!(resumed, board);
+    assert_eq!(resumed.to_string_repr(), repr);
+    assert_eq!(resumed.whose_turn(), Color::Black);
+    assert_eq!(resumed.get_balance(Color::White), board.get_balance(Color::White));
+    assert_eq!(resumed.get_balance(Color::Black), board.get_balance(Color::Black));
+    assert_eq!(resumed.legal_moves(), board.legal_moves());
+    assert_eq!(resumed.get_plies(), 5);
+    assert!(!resumed.sector_control_changes().is_empty());
+    assert_eq!(resumed.sector_control_changes(), board.sector_control_changes());
+
+    // Playing on gives both games the same balances
+    let (mut board, mut resumed) = (board, resumed);
+    board.apply(Move::from_str("d5a2")?)?;
+    resumed.apply(Move::from_str("d5a2")?)?;
+    assert_eq!(resumed, board);
+
+    assert_eq!(StateCapitalistBoard::from_string_repr("white_balance=40"), Err(StateReprError::MissingFen));
+    let bad_bank = repr.replace("white_sectors=", "white_sectors=2");
+    assert_eq!(StateCapitalistBoard::from_string_repr(&bad_bank), Err(StateReprError::InvalidBank(3)));
+    let bad_key = repr.replace("black_reserve=", "black_savings=");
+    assert_eq!(StateCapitalistBoard::from_string_repr(&bad_key), Err(StateReprError::InvalidMarket(MarketConfigError::UnknownKey(9))));
+    let line = repr.lines().position(|line| line.starts_with("sector_changes=")).ok_or(())? + 1;
+    let bad_changes = repr.replace("sector_changes=", "sector_changes=1:16:w,");
+    assert_eq!(StateCapitalistBoard::from_string_repr(&bad_changes), Err(StateReprError::InvalidSectorChanges(line)));
+
+    // A game where a player bought a second king can be saved and resumed, too
+    let market = Market::default()
+        .with_purchasable_pieces(&PieceType::ALL)
+        .with_king_value(Currency::penny());
+    let mut board = StateCapitalistBoard::new(market);
+    for player_move in ["e2e4", "e7e5", "$Ke2"] {
+        board.apply(Move::from_str(player_move)?)?;
+    }
+    assert_eq!(board.get_piece(Tile::from_str("e2")?), Some(Piece::new(PieceType::King, Color::White)));
+    let repr = board.to_string_repr();
+    let resumed = StateCapitalistBoard::from_string_repr(&repr).map_err(|_| ())?;
+    assert_eq!(resumed, board);
+    assert_eq!(resumed.to_string_repr(), repr);
+    assert_eq!(resumed.legal_moves(), board.legal_moves());
+    Ok(())
+}
+
+/// This tests the board shown at the REPL.
+#[test]
+fn pretty_print() -> Result<(), ()> {
+    init();
+    let mut board = StateCapitalistBoard::default();
+    board.apply(Move::from_str("e2e4")?)?;
+    let output = board.pretty_print();
+
+    assert!(output.contains(&format!("{}", board.get_balance(Color::White))));
+    assert!(output.contains(&format!("{}", board.get_balance(Color::Black))));
+    assert!(output.contains("Black to move"));
+    assert!(output.contains("  7-8     B   B   B   B\n"));
+    assert!(output.contains("  1-2     W   W   W   W\n"));
+    assert!(output.contains("  3-4     .   .   W   .\n"));
+    assert!(output.contains("║ White     30¢ ║"));
+    Ok(())
+}
+
+/// This tests sending a game and its moves over JSON.
+#[cfg(feature = "serde")]
+#[test]
+fn serde_round_trip() -> Result<(), ()> {
+    init();
+    let mut board = StateCapitalistBoard::new(Market::default().with_purchase_into_reserve(true));
+    for player_move in ["e2e4", "d7d5", "e4d5", "d8d5", "$P"] {
+        board.apply(Move::from_str(player_move)?)?;
+    }
+
+    let json = serde_json::to_string(&board).map_err(|_| ())?;
+    assert_eq!(serde_json::from_str::<StateCapitalistBoard>(&json).map_err(|_| ())?, board);
+
+    // Tiles are written by name, and money as a plain number of pennies
+    assert_eq!(serde_json::to_string(&Tile::from_str("e4")?).map_err(|_| ())?, "\"e4\"");
+    assert_eq!(serde_json::to_string(&(Currency::doubloon() * 3)).map_err(|_| ())?, "30");
+    assert!(serde_json::from_str::<Tile>("\"z9\"").is_err());
+
+    // Moves are written as they're played
+    let player_move = Move::from_str("e2e4 $Nc3")?;
+    let json = serde_json::to_string(&player_move).map_err(|_| ())?;
+    assert_eq!(json, "\"e2e4 $Nc3\"");
+    assert_eq!(serde_json::from_str::<Move>(&json).map_err(|_| ())?, player_move);
+    assert!(serde_json::from_str::<Move>("\"e2e9\"").is_err());
+    Ok(())
+}
+
+/// This tests listing the purchases a player can make, with their prices.
+#[test]
+fn purchase_options() -> Result<(), ()> {
+    init();
+    let cents = Currency::penny();
+    let market = Market::default().with_piece_values(cents * 10, cents * 30, cents * 20, cents * 50, cents * 90, cents * 200);
+    let mut board = StateCapitalistBoard::new(market);
+    *board.board_mut() = Board::from_fen("4k3/8/8/8/8/8/8/4K3 w - - 0 1").map_err(|_| ())?;
+
+    let options = board.purchase_options(Color::White);
+    assert!(!options.is_empty());
+    for (player_move, cost) in &options {
+        assert!(board.is_legal_move(player_move), "{player_move} is illegal");
+        let Move::Purchase { piece, .. } = player_move else {
+            panic!("{player_move} is not a purchase");
+        };
+        assert_eq!(*cost, market.get_piece_value(*piece));
+        assert!(*cost <= board.get_balance(Color::White));
+    }
+
+    // Cheapest first, and nothing the player can't afford
+    assert!(options.windows(2).all(|pair| pair[0].1 <= pair[1].1));
+    assert_eq!(options.first().map(|(_, cost)| *cost), Some(cents * 10));
+    assert_eq!(options.last().map(|(_, cost)| *cost), Some(cents * 30));
+    assert!(options.iter().any(|(player_move, _)| *player_move == Move::from_str("$Ba1").unwrap()));
+
+    // Black has nothing to spend yet
+    assert!(board.purchase_options(Color::Black).is_empty());
+    Ok(())
+}
+
+/// This tests drawing the game when the same position comes up three times.
+#[test]
+fn threefold_repetition() -> Result<(), ()> {
+    init();
+    let mut board = StateCapitalistBoard::default();
+    let shuffle = ["g1f3", "g8f6", "f3g1", "f6g8"];
+
+    // The starting position comes back after each shuffle
+    for round in 0..2 {
+        for player_move in shuffle {
+            assert!(!board.is_threefold_repetition(), "before {player_move} in round {round}");
+            assert_eq!(board.is_game_over(), None);
+            board.apply(Move::from_str(player_move)?)?;
+        }
+    }
+    assert!(board.is_threefold_repetition());
+    assert_eq!(board.is_game_over(), Some(GameResult::Draw));
+
+    // A saved game remembers the positions that came before it
+    let resumed = StateCapitalistBoard::from_string_repr(&board.to_string_repr()).map_err(|_| ())?;
+    assert_eq!(resumed, board);
+    assert!(resumed.is_threefold_repetition());
+
+    // A pawn move can't be undone, so the earlier positions can't repeat
+    let mut board = StateCapitalistBoard::default();
+    for player_move in ["g1f3", "g8f6", "f3g1", "f6g8", "e2e4", "e7e5"].into_iter().chain(shuffle) {
+        board.apply(Move::from_str(player_move)?)?;
+    }
+    assert!(!board.is_threefold_repetition());
+    for player_move in shuffle {
+        board.apply(Move::from_str(player_move)?)?;
+    }
+    assert!(board.is_threefold_repetition());
+    Ok(())
+}
+
+/// This tests recording a game in a log that can be read back.
+#[test]
+fn game_log() -> Result<(), ()> {
+    init();
+    let cents = Currency::penny();
+    let market = Market::default().with_piece_values(cents * 10, cents * 30, cents * 20, cents * 50, cents * 90, cents * 200);
+    let mut board = StateCapitalistBoard::new(market);
+    *board.board_mut() = Board::from_fen("4k3/p7/8/8/8/8/8/4K2R w K - 0 1").map_err(|_| ())?;
+    let mut log = GameLog::new();
+    let mut played = board;
+    assert!(log.is_empty());
+
+    for player_move in ["$Nb1", "a7a6", "O-O", "a6a5", "$Pc2 g1h1", "e8d8"] {
+        log.apply(&mut played, Move::from_str(player_move)?).map_err(|_| ())?;
+    }
+    // Illegal moves aren't recorded
+    assert_eq!(log.apply(&mut played, Move::from_str("a1a8")?), Err(MoveError::Illegal));
+    assert_eq!(log.len(), 6);
+    let text = log.to_string();
+    let lines: Vec<&str> = text.lines().collect();
+    assert_eq!(lines.len(), 6);
+    assert!(lines[0].starts_with("1. White: $Nb1 (White "), "{}", lines[0]);
+    assert!(lines[2].starts_with("3. White: O-O "), "{}", lines[2]);
+    assert!(lines[4].starts_with("5. White: $Pc2 g1h1 "), "{}", lines[4]);
+
+    // The balances after each move are recorded
+    let last = &log.get_entries()[5];
+    assert_eq!(last.color, Color::Black);
+    assert_eq!(last.white_balance, played.get_balance(Color::White));
+    assert_eq!(last.black_balance, played.get_balance(Color::Black));
+
+    // The log reads back as it was written, and replays to the same board
+    let parsed = GameLog::from_str(&text).map_err(|_| ())?;
+    assert_eq!(parsed, log);
+    let mut replayed = board;
+    for entry in parsed.get_entries() {
+        replayed.apply(entry.player_move.clone())?;
+    }
+    assert_eq!(replayed, played);
+
+    // A board can keep its own log, and still be copied freely
+    let mut logged = board.with_logging();
+    for entry in log.get_entries() {
+        logged.apply(entry.player_move.clone()).map_err(|_| ())?;
+    }
+    assert_eq!(logged.game_log(), &log);
+    assert_eq!(*logged.get_board(), played);
+    assert_eq!(logged.whose_turn(), Color::White);
+    assert_eq!(GameLog::from_str("1. White e2e4"), Err(GameLogError::InvalidLine(1)));
+    Ok(())
+}
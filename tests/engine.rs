@@ -0,0 +1,632 @@
+/*
+ * This is a test of the engines.
+ * It sets up positions and confirms the engines evaluate
+ * and search them sensibly.
+ */
+
+use capitalist_chess::*;
+use std::str::FromStr;
+
+static mut ALREADY_INIT: bool = false;
+
+fn init() {
+    unsafe {
+        if ALREADY_INIT {
+            return;
+        }
+        ALREADY_INIT = true;
+    }
+    let _ = env_logger::builder().is_test(true).try_init();
+}
+
+/// Play the fool's mate, where black checkmates white in two moves.
+fn fools_mate() -> Result<StateCapitalistBoard, ()> {
+    let mut board = StateCapitalistBoard::default();
+    for player_move in ["f2f3", "e7e5", "g2g4", "d8h4"] {
+        board.apply(Move::from_str(player_move)?)?;
+    }
+    Ok(board)
+}
+
+/// A market where no piece is affordable and each turn is a single action,
+/// so the engines only search moves on the board.
+fn expensive_pieces() -> Market {
+    let doubloons = Currency::doubloon();
+    Market::default()
+        .with_piece_values(doubloons * 100, doubloons * 300, doubloons * 300, doubloons * 500, doubloons * 900, doubloons * 1000)
+        .with_max_actions_per_turn(1)
+}
+
+/// Set up the given position with the `expensive_pieces` market, to keep the search small.
+fn expensive_board(fen: &str) -> Result<StateCapitalistBoard, ()> {
+    let mut board = StateCapitalistBoard::new(expensive_pieces());
+    *board.board_mut() = Board::from_fen(fen).map_err(|_| ())?;
+    Ok(board)
+}
+
+/// A position where the white rook can take the undefended black queen.
+fn rook_takes_queen() -> Result<StateCapitalistBoard, ()> {
+    expensive_board("4k3/8/8/3q4/8/8/3R4/4K3 w - - 0 1")
+}
+
+/// This tests that a checkmated leaf is scored as a loss regardless of material.
+#[test]
+fn leaf_eval_checkmate() -> Result<(), ()> {
+    init();
+    let board = fools_mate()?;
+    assert!(board.is_in_checkmate(Color::White));
+
+    // Material is even, but white has lost
+    assert!(SimpleEngine.evaluate(&board, Color::White).abs() < MATE_SCORE);
+    assert_eq!(SimpleEngine.leaf_eval(&board, Color::White), -MATE_SCORE);
+    assert_eq!(SimpleEngine.leaf_eval(&board, Color::Black), MATE_SCORE);
+    assert_eq!(RandomEngine.leaf_eval(&board, Color::White), -MATE_SCORE);
+
+    Ok(())
+}
+
+/// This tests that the simple engine won't buy a queen where it can be taken for free.
+#[test]
+fn skip_hanging_purchases() -> Result<(), ()> {
+    init();
+    let cents = Currency::penny();
+    let market = Market::default()
+        .with_base_move_cost(cents)
+        .with_piece_values(cents, cents * 3, cents * 3, cents * 5, cents * 9, cents * 10000);
+    let mut board = StateCapitalistBoard::new(market);
+
+    // The knight on g4 attacks f2, which is only defended by the king
+    for player_move in ["e2e4", "g8f6", "e4e5", "f6g4", "f2f4", "a7a6"] {
+        board.apply(Move::from_str(player_move)?)?;
+    }
+
+    let hanging = Move::Purchase { piece: PieceType::Queen, to: Tile::from_str("f2")? };
+    let safe = Move::Purchase { piece: PieceType::Queen, to: Tile::from_str("e2")? };
+    assert!(board.legal_moves().contains(&hanging));
+    assert!(board.legal_moves().contains(&safe));
+
+    let moves = SimpleEngine.legal_moves(&board);
+    assert!(!moves.contains(&hanging));
+    assert!(moves.contains(&safe));
+
+    // The knight wins a queen for itself
+    let mut after = *board.board();
+    after.spawn(PieceType::Queen, Tile::from_str("f2")?);
+    assert_eq!(after.see(Tile::from_str("f2")?, Color::Black, &market), cents * 6);
+    assert_eq!(after.see(Tile::from_str("e5")?, Color::Black, &market), Currency::zero());
+
+    Ok(())
+}
+
+/// An engine that always plays its first legal move.
+struct FirstMoveEngine;
+
+impl Engine for FirstMoveEngine {
+    fn name(&self) -> &str {
+        "First Move Engine"
+    }
+
+    fn evaluate(&self, _board: &StateCapitalistBoard, _color: Color) -> f64 {
+        0.0
+    }
+
+    fn best_move(&self, board: &StateCapitalistBoard) -> Option<Move> {
+        self.legal_moves(board).first().cloned()
+    }
+}
+
+/// This tests that a handicapped engine blunders according to its skill.
+#[test]
+fn handicap_engine() {
+    init();
+    let board = StateCapitalistBoard::new(Market::default().with_max_actions_per_turn(1));
+    let best_move = FirstMoveEngine.best_move(&board);
+    assert!(best_move.is_some());
+
+    let expert = HandicapEngine::new(u8::MAX, Box::new(FirstMoveEngine)).with_seed(1);
+    assert_eq!(expert.blunder_chance(), 0.0);
+    for _ in 0..100 {
+        assert_eq!(expert.best_move(&board), best_move);
+    }
+
+    let beginner = HandicapEngine::new(0, Box::new(FirstMoveEngine)).with_seed(1);
+    let blunders = (0..100)
+        .filter(|_| beginner.best_move(&board) != best_move)
+        .count();
+    assert!(blunders > 25, "only blundered {blunders} times");
+}
+
+/// This tests that engines search single actions, even though the market allows turns of several.
+#[test]
+fn engine_searches_single_actions() -> Result<(), ()> {
+    init();
+    let board = StateCapitalistBoard::default();
+    assert!(board.is_legal_move(&Move::from_str("e2e4 g1f3")?));
+    assert!(board.legal_moves().iter().any(|player_move| player_move.action_count() > 1));
+
+    let moves = SimpleEngine.legal_moves(&board);
+    assert!(moves.iter().all(|player_move| player_move.action_count() == 1 || *player_move == Move::Pass));
+    assert_eq!(moves.len(), board.legal_moves_up_to(1).len());
+    assert_eq!(SimpleEngine.principal_variation(&board, 2).len(), 2);
+
+    // A market that requires several actions is searched with the fewest it allows
+    let board = StateCapitalistBoard::new(Market::default().with_min_actions_per_turn(2));
+    let moves = board.legal_moves_up_to(1);
+    assert!(!moves.is_empty());
+    assert!(moves.iter().all(|player_move| player_move.action_count() == 2 || *player_move == Move::Pass));
+    Ok(())
+}
+
+/// This tests that the engine passes when moving costs more than it's worth.
+#[test]
+fn engine_passes() -> Result<(), ()> {
+    init();
+    let market = Market::default()
+        .with_base_move_cost(Currency::doubloon() * 2)
+        .with_center_sector_income_value(Currency::zero())
+        .with_max_actions_per_turn(1);
+    let board = StateCapitalistBoard::new(market);
+    assert!(board.legal_moves().contains(&Move::Pass));
+    assert!(board.legal_moves().contains(&Move::from_str("e2e4")?));
+
+    // Any move spends money without gaining anything, so banking the income is best
+    let line = SimpleEngine.principal_variation(&board, 2);
+    assert_eq!(line.first(), Some(&Move::Pass));
+
+    // A queen costs more than White has, but not more than they'll have after collecting income
+    let market = Market::default()
+        .with_purchasable_pieces(&[PieceType::Queen])
+        .with_queen_value(Currency::penny() * 45)
+        .with_base_move_cost(Currency::doubloon() * 2)
+        .with_max_actions_per_turn(1);
+    let mut board = StateCapitalistBoard::new(market);
+    *board.board_mut() = Board::from_fen("4k3/8/8/8/8/8/8/4K3 w - - 0 1").map_err(|_| ())?;
+    assert!(!board.get_bank(Color::White).can_afford_piece(PieceType::Queen));
+
+    // Banking the income to buy the queen next turn is the best line
+    let line = SimpleEngine.principal_variation(&board, 3);
+    assert_eq!(line.len(), 3);
+    assert_eq!(line[0], Move::Pass);
+    assert!(matches!(line[2], Move::Purchase { piece: PieceType::Queen, .. }), "expected a queen purchase, found {:?}", line[2]);
+    board.apply(line[0].clone())?;
+    board.apply(line[1].clone())?;
+    assert!(board.get_bank(Color::White).can_afford_piece(PieceType::Queen));
+    let best_move = SimpleEngine.best_move(&board).ok_or(())?;
+    assert!(matches!(best_move, Move::Purchase { piece: PieceType::Queen, .. }));
+
+    // A player in check can't pass
+    let board = fools_mate()?;
+    assert!(!board.is_legal_move(&Move::Pass));
+    Ok(())
+}
+
+/// This tests that cached evaluations match fresh ones, and get reused in a search.
+#[test]
+fn cached_engine() -> Result<(), ()> {
+    init();
+    let board = expensive_board("4k3/8/8/8/8/8/8/R3K2R w KQ - 0 1")?;
+
+    let engine = CachedEngine::new(SimpleEngine);
+    for color in [Color::White, Color::Black] {
+        assert_eq!(engine.evaluate(&board, color), SimpleEngine.evaluate(&board, color));
+        assert_eq!(engine.evaluate(&board, color), SimpleEngine.evaluate(&board, color));
+    }
+    assert_eq!((engine.hits(), engine.misses()), (2, 2));
+
+    // The same position under another market is evaluated again
+    let mut repriced = StateCapitalistBoard::new(expensive_pieces().with_cash_discount(0.5));
+    *repriced.board_mut() = *board.board();
+    assert_eq!(engine.evaluate(&repriced, Color::White), SimpleEngine.evaluate(&repriced, Color::White));
+    assert_eq!((engine.hits(), engine.misses()), (2, 3));
+
+    // Moves played in a different order reach the same positions
+    engine.clear();
+    let (score, _) = engine.minimax(&board, 3, Color::White, None);
+    assert!(engine.hits() > 0);
+    assert_eq!(score, SimpleEngine.minimax(&board, 3, Color::White, None).0);
+    Ok(())
+}
+
+/// This tests that material on the board is preferred over the same value in cash.
+#[test]
+fn cash_discount() -> Result<(), ()> {
+    init();
+    let with_cash = |market: Market| -> Result<StateCapitalistBoard, ()> {
+        let mut board = StateCapitalistBoard::new(market);
+        *board.board_mut() = Board::from_fen("4k3/8/8/8/8/8/8/4K3 w - - 0 1").map_err(|_| ())?;
+        Ok(board)
+    };
+
+    // Price the knight at exactly one census of income
+    let mut probe = with_cash(Market::default())?;
+    let balance = probe.get_balance(Color::White);
+    probe.run_census(Color::White);
+    let income = probe.get_balance(Color::White) - balance;
+    assert!(income.is_surplus());
+
+    for (cash_discount, prefers_material) in [(0.75, true), (0.0, false)] {
+        let market = Market::default()
+            .with_knight_value(income)
+            .with_cash_discount(cash_discount);
+        let mut cash = with_cash(market)?;
+        cash.run_census(Color::White);
+        let mut material = StateCapitalistBoard::new(market);
+        *material.board_mut() = Board::from_fen("4k3/8/8/8/8/8/8/1N2K3 w - - 0 1").map_err(|_| ())?;
+
+        let material_score = SimpleEngine.evaluate(&material, Color::White);
+        let cash_score = SimpleEngine.evaluate(&cash, Color::White);
+        assert_eq!(material_score > cash_score, prefers_material);
+        if !prefers_material {
+            assert_eq!(material_score, cash_score);
+        }
+    }
+
+    assert_eq!(Market::default().with_cash_discount(1.5).validate(), Err(MarketError::InvalidCashDiscount));
+    Ok(())
+}
+
+/// This tests that pruning underpromotions keeps a winning knight promotion.
+#[test]
+fn promotion_pruning() -> Result<(), ()> {
+    init();
+    // Promoting to a knight smothers the black king, but a queen doesn't even give check
+    let mut board = expensive_board("6br/5Ppk/6pp/8/8/8/8/K7 w - - 0 1")?;
+
+    let promote = |piece| Move::from_str("f7f8").map(|player_move| match player_move {
+        Move::FromTo { from, to, .. } => Move::new(from, to, Some(piece)),
+        _ => unreachable!(),
+    });
+    let engine = PromotionPruningEngine::new(SimpleEngine);
+    let moves = engine.legal_moves(&board);
+    assert!(moves.contains(&promote(PieceType::Queen)?));
+    assert!(moves.contains(&promote(PieceType::Knight)?));
+    assert!(!moves.contains(&promote(PieceType::Rook)?));
+    assert!(!moves.contains(&promote(PieceType::Bishop)?));
+
+    let (_, best_move) = engine.minimax(&board, 2, Color::White, None);
+    assert_eq!(best_move, promote(PieceType::Knight)?);
+    board.apply(best_move)?;
+    assert!(board.is_in_checkmate(Color::Black));
+
+    // A rook promotion that gives check is still searched
+    let board = expensive_board("7k/5P2/8/8/8/8/8/K7 w - - 0 1")?;
+    assert!(engine.legal_moves(&board).contains(&promote(PieceType::Rook)?));
+    assert!(!engine.legal_moves(&board).contains(&promote(PieceType::Bishop)?));
+    Ok(())
+}
+
+/// This tests that the engine spends idle cash on pieces that contest sectors.
+#[test]
+fn purchase_placement() -> Result<(), ()> {
+    init();
+    let mut board = StateCapitalistBoard::default();
+    *board.board_mut() = Board::from_fen("4k3/8/8/8/8/8/8/4K3 w - - 0 1").map_err(|_| ())?;
+
+    // Purchases that take an empty sector are searched first, closest to the center
+    let moves = SimpleEngine.legal_moves(&board);
+    assert_eq!(moves.first(), Some(&Move::from_str("$Pd2")?));
+    let corner = Move::from_str("$Pa1")?;
+    let position = |player_move| moves.iter().position(|m| *m == player_move);
+    assert!(position(Move::from_str("$Pb2")?) < position(corner.clone()));
+    assert!(position(corner) < position(Move::from_str("$Pe2")?));
+
+    let (_, best_move) = SimpleEngine.minimax(&board, 2, Color::White, None);
+    let Move::Purchase { to, .. } = best_move else {
+        panic!("expected a purchase, found {best_move:?}");
+    };
+    let sector = to.get_sector();
+    assert_eq!(board.get_market().who_controls_sector(board.board(), sector), None);
+    assert_ne!(to.center_distance(), Tile::from_str("a1")?.center_distance());
+    Ok(())
+}
+
+/// This tests that turns of several moves are priced with interest on each extra move.
+#[test]
+fn affordable_combinations_interest() -> Result<(), ()> {
+    init();
+    let three = Move::from_str("a1a2 a2a3 a3a4")?;
+    let market = Market::default()
+        .with_purchasable_pieces(&[])
+        .with_min_actions_per_turn(3)
+        .with_max_actions_per_turn(3);
+
+    // Three moves at the base cost fit in the balance, but not with interest
+    let mut board = StateCapitalistBoard::new(market);
+    *board.board_mut() = Board::from_fen("4k3/8/8/8/8/8/8/K7 w - - 0 1").map_err(|_| ())?;
+    let balance = board.get_balance(Color::White);
+    assert!(market.get_base_move_cost() * 3i32 <= balance);
+    assert!(market.get_move_value(&three) > balance);
+    let moves = board.legal_moves();
+    assert!(!moves.contains(&three));
+    assert!(moves.iter().all(|player_move| board.get_bank(Color::White).can_afford(player_move)));
+
+    // With cheap moves, the three move turn is affordable too
+    let mut board = StateCapitalistBoard::new(market.with_base_move_cost(Currency::penny()));
+    *board.board_mut() = Board::from_fen("4k3/8/8/8/8/8/8/K7 w - - 0 1").map_err(|_| ())?;
+    assert!(board.legal_moves().contains(&three));
+    Ok(())
+}
+
+/// This tests that the search scores a repeated position as a draw.
+#[test]
+fn repetition_is_a_draw() -> Result<(), ()> {
+    init();
+    let mut board = StateCapitalistBoard::default();
+    for _ in 0..2 {
+        for player_move in ["g1f3", "g8f6", "f3g1", "f6g8"] {
+            board.apply(Move::from_str(player_move)?)?;
+        }
+    }
+    assert!(board.is_threefold_repetition());
+
+    let shuffle = Move::from_str("g1f3")?;
+    assert_eq!(SimpleEngine.minimax(&board, 2, Color::White, Some(shuffle.clone())), (0.0, shuffle));
+    Ok(())
+}
+
+/// An engine that counts the positions it evaluates.
+struct CountingEngine(std::sync::atomic::AtomicUsize);
+
+impl Engine for CountingEngine {
+    fn name(&self) -> &str {
+        "Counting Engine"
+    }
+
+    fn evaluate(&self, board: &StateCapitalistBoard, color: Color) -> f64 {
+        self.0.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+        SimpleEngine.evaluate(board, color)
+    }
+}
+
+/// Search every move, without pruning, the way `minimax` is defined.
+fn full_minimax(engine: &dyn Engine, board: &StateCapitalistBoard, depth: u32, color: Color, original_move: Option<Move>) -> (f64, Move) {
+    if depth == 0 {
+        return (engine.leaf_eval(board, color), original_move.unwrap());
+    }
+    let mut best: Option<(f64, Move)> = None;
+    for legal_move in engine.legal_moves(board) {
+        let mut copy = *board;
+        copy.apply(legal_move.clone()).unwrap();
+        let score = -full_minimax(engine, &copy, depth - 1, color, Some(original_move.clone().unwrap_or(legal_move.clone()))).0;
+        if best.as_ref().is_none_or(|(best_score, _)| score > *best_score) {
+            best = Some((score, legal_move));
+        }
+    }
+    best.unwrap_or((f64::NEG_INFINITY, Move::Pass))
+}
+
+/// This tests that pruning finds the same move as searching every move, with fewer evaluations.
+#[test]
+fn alpha_beta_pruning() -> Result<(), ()> {
+    init();
+    let board = rook_takes_queen()?;
+
+    let engine = CountingEngine(Default::default());
+    let full = full_minimax(&engine, &board, 2, Color::White, None);
+    let full_evaluations = engine.0.swap(0, std::sync::atomic::Ordering::Relaxed);
+    let pruned = engine.minimax(&board, 2, Color::White, None);
+    let pruned_evaluations = engine.0.load(std::sync::atomic::Ordering::Relaxed);
+
+    assert_eq!(pruned, full);
+    assert_eq!(pruned.1, Move::from_str("d2d5")?);
+    assert!(pruned_evaluations * 2 < full_evaluations, "{pruned_evaluations} evaluations with pruning, {full_evaluations} without");
+    Ok(())
+}
+
+/// This tests that the transposition table makes searching the same position again faster.
+#[test]
+fn transposition_table() -> Result<(), ()> {
+    init();
+    let board = rook_takes_queen()?;
+
+    let engine = AlphaBetaEngine::new(CountingEngine(Default::default()));
+    let first = engine.best_move(&board);
+    let first_evaluations = engine.get_inner().0.swap(0, std::sync::atomic::Ordering::Relaxed);
+
+    let second = engine.best_move(&board);
+    let second_evaluations = engine.get_inner().0.load(std::sync::atomic::Ordering::Relaxed);
+
+    assert_eq!(first, second);
+    assert_eq!(first, SimpleEngine.best_move(&board));
+    assert!(engine.hits() > 0);
+    assert!(second_evaluations < first_evaluations, "{second_evaluations} evaluations the second time, {first_evaluations} the first");
+    Ok(())
+}
+
+/// An engine that evaluates like the simple engine until it has used up its
+/// evaluations, and then sleeps through the whole time budget on its last one.
+/// The search started before that evaluation, so this runs it out of time
+/// right after a set number of evaluations, however fast the machine is.
+struct DeadlineEngine {
+    evaluations: std::sync::atomic::AtomicUsize,
+    budget: usize,
+    time: std::time::Duration,
+}
+
+impl DeadlineEngine {
+    fn new(budget: usize, time: std::time::Duration) -> Self {
+        Self {
+            evaluations: Default::default(),
+            budget,
+            time,
+        }
+    }
+
+    fn evaluations(&self) -> usize {
+        self.evaluations.load(std::sync::atomic::Ordering::Relaxed)
+    }
+}
+
+impl Engine for DeadlineEngine {
+    fn name(&self) -> &str {
+        "Deadline Engine"
+    }
+
+    fn evaluate(&self, board: &StateCapitalistBoard, color: Color) -> f64 {
+        if self.evaluations.fetch_add(1, std::sync::atomic::Ordering::Relaxed) + 1 >= self.budget {
+            std::thread::sleep(self.time);
+        }
+        SimpleEngine.evaluate(board, color)
+    }
+}
+
+/// This tests that a timed search plays a legal move within its budget, however short.
+#[test]
+fn iterative_deepening() -> Result<(), ()> {
+    init();
+    let board = rook_takes_queen()?;
+
+    // Without any time, the engine still plays a legal move, without evaluating anything
+    let engine = CountingEngine(Default::default());
+    let player_move = engine.best_move_timed(&board, std::time::Duration::ZERO).ok_or(())?;
+    assert!(engine.legal_moves(&board).contains(&player_move));
+    assert_eq!(engine.0.load(std::sync::atomic::Ordering::Relaxed), 0);
+
+    // The search runs out of time after a hundred evaluations, which is deep enough to win the queen
+    let time = std::time::Duration::from_millis(500);
+    let engine = DeadlineEngine::new(100, time);
+    let player_move = engine.best_move_timed(&board, time);
+    assert_eq!(player_move, Some(Move::from_str("d2d5")?));
+    assert_eq!(engine.evaluations(), 100);
+
+    // The time is checked before every position, so the search stops right after the evaluation that ran it out of time
+    let time = std::time::Duration::from_millis(50);
+    let engine = DeadlineEngine::new(1, time);
+    let player_move = engine.best_move_timed(&board, time).ok_or(())?;
+    assert!(engine.legal_moves(&board).contains(&player_move));
+    assert!(engine.evaluations() <= 1);
+    Ok(())
+}
+
+/// This tests that the search plays out captures past its depth,
+/// so it doesn't take a defended pawn with its queen.
+#[test]
+fn quiescence_search() -> Result<(), ()> {
+    init();
+    // The pawn on d5 is defended by the pawn on e6
+    let board = expensive_board("6k1/8/4p3/3p4/8/8/8/3QK3 w - - 0 1")?;
+    let greedy = Move::from_str("d1d5")?;
+    assert!(greedy.is_capture(board.board()));
+    assert!(!Move::from_str("d1d4")?.is_capture(board.board()));
+
+    // Searching one ply scores the leaves for the opponent, who moves next
+    let (_, naive_move) = full_minimax(&SimpleEngine, &board, 1, Color::Black, None);
+    assert_eq!(naive_move, greedy);
+
+    let (score, best_move) = SimpleEngine.minimax(&board, 1, Color::Black, None);
+    assert_ne!(best_move, greedy);
+    let mut after = board;
+    after.apply(greedy.clone())?;
+    assert!(SimpleEngine.quiescence(&after, Color::Black, f64::NEG_INFINITY, f64::INFINITY) > -score);
+    Ok(())
+}
+
+/// This tests that searching captures first finds the same move with fewer evaluations.
+#[test]
+fn capture_ordering() -> Result<(), ()> {
+    init();
+    // The pawn, knight, and rook can all take the queen, and the rook can take the knight
+    let board = expensive_board("4k3/8/2n5/3q4/2P5/1N6/3R4/4K3 w - - 0 1")?;
+    let market = expensive_pieces();
+
+    let pawn_takes_queen = Move::from_str("c4d5")?;
+    let rook_takes_queen = Move::from_str("d2d5")?;
+    assert!(pawn_takes_queen.capture_priority(board.board(), &market) > rook_takes_queen.capture_priority(board.board(), &market));
+    assert!(rook_takes_queen.capture_priority(board.board(), &market) > Move::from_str("b3d4")?.capture_priority(board.board(), &market));
+    assert!(Move::from_str("$Qa1")?.capture_priority(board.board(), &market) > Move::from_str("$Pa2")?.capture_priority(board.board(), &market));
+    // The attackers are priced by the market, so a cheap rook captures first
+    let cheap_rooks = market.with_rook_value(Currency::penny());
+    assert!(rook_takes_queen.capture_priority(board.board(), &cheap_rooks) > pawn_takes_queen.capture_priority(board.board(), &cheap_rooks));
+
+    let ordered = MoveOrderingEngine::new(CountingEngine(Default::default()));
+    assert_eq!(ordered.legal_moves(&board).first(), Some(&pawn_takes_queen));
+    let (ordered_score, ordered_move) = ordered.minimax(&board, 2, Color::White, None);
+    let ordered_evaluations = ordered.get_inner().0.load(std::sync::atomic::Ordering::Relaxed);
+
+    let unordered = CountingEngine(Default::default());
+    let (unordered_score, unordered_move) = unordered.minimax(&board, 2, Color::White, None);
+    let unordered_evaluations = unordered.0.load(std::sync::atomic::Ordering::Relaxed);
+
+    assert_eq!(ordered_score, unordered_score);
+    assert!(ordered_move.is_capture(board.board()) && unordered_move.is_capture(board.board()));
+    assert!(ordered_evaluations < unordered_evaluations, "{ordered_evaluations} evaluations in order, {unordered_evaluations} without");
+    Ok(())
+}
+
+/// This tests playing openings from a book, and searching once the book runs out.
+#[test]
+fn opening_book() -> Result<(), ()> {
+    init();
+    let board = StateCapitalistBoard::default();
+    let start = board.board().zobrist_hash();
+    let text = format!("# The starting position\n{start:016x} e2e4 3\n{start:016x} d2d4\n{start:016x} e2e5 100\n");
+    let book = OpeningBook::from_text(&text).map_err(|_| ())?;
+    assert_eq!(book.len(), 1);
+    assert_eq!(book.get_moves(start).len(), 3);
+
+    // The illegal move is never played, however heavily it's weighted
+    let book_moves = [Move::from_str("e2e4")?, Move::from_str("d2d4")?];
+    for _ in 0..20 {
+        let player_move = RandomEngine.best_move_with_book(&board, &book).ok_or(())?;
+        assert!(book_moves.contains(&player_move), "{player_move:?} is not in the book");
+    }
+
+    // Passing back to a position in the book finds it again,
+    // but a purchase leaves a position no book has
+    let mut open = board;
+    open.apply(Move::from_str("e2e4")?)?;
+    open.apply(Move::from_str("e7e5")?)?;
+    let book = OpeningBook::from_text(&format!("{:016x} g1f3", open.board().zobrist_hash())).map_err(|_| ())?;
+    let mut passed = open;
+    passed.apply(Move::Pass)?;
+    passed.apply(Move::Pass)?;
+    assert_eq!(book.choose(&passed), Some(Move::from_str("g1f3")?));
+    let mut purchased = open;
+    let (purchase, _) = purchased.purchase_options(Color::White).first().cloned().ok_or(())?;
+    purchased.apply(purchase)?;
+    purchased.apply(Move::Pass)?;
+    assert_eq!(book.choose(&purchased), None);
+    let player_move = RandomEngine.best_move_with_book(&purchased, &book).ok_or(())?;
+    assert!(purchased.is_legal_move(&player_move));
+
+    // Weights that add up past a u32 are still picked from
+    let heavy = format!("{start:016x} e2e4 {max}\n{start:016x} d2d4 {max}\n", max = u32::MAX);
+    let book = OpeningBook::from_text(&heavy).map_err(|_| ())?;
+    let player_move = book.choose(&board).ok_or(())?;
+    assert!(book_moves.contains(&player_move));
+
+    assert_eq!(OpeningBook::from_text("abc"), Err(BookError::InvalidLine(1)));
+    assert_eq!(OpeningBook::from_text("\nxyz e2e4"), Err(BookError::InvalidHash(2)));
+    assert_eq!(OpeningBook::from_text("abc $Pc3"), Err(BookError::InvalidMove(1)));
+    assert_eq!(OpeningBook::from_text("abc e2e4 0"), Err(BookError::InvalidWeight(1)));
+    Ok(())
+}
+
+/// This tests getting the line of moves the search expects both players to play.
+#[test]
+fn principal_variation() -> Result<(), ()> {
+    init();
+    let mut board = rook_takes_queen()?;
+
+    let line = SimpleEngine.principal_variation(&board, 2);
+    assert_eq!(line.len(), 2);
+    assert_eq!(line[0], SimpleEngine.minimax(&board, 2, Color::White, None).1);
+
+    // The line comes from one search, so it takes as many evaluations as finding the best move
+    let engine = CountingEngine(Default::default());
+    assert_eq!(engine.principal_variation(&board, 2), line);
+    let line_evaluations = engine.0.swap(0, std::sync::atomic::Ordering::Relaxed);
+    engine.minimax(&board, 2, Color::White, None);
+    assert_eq!(line_evaluations, engine.0.load(std::sync::atomic::Ordering::Relaxed));
+    let mut after = board;
+    for player_move in &line {
+        assert!(after.is_legal_move(player_move), "{player_move:?} is illegal");
+        after.apply(player_move.clone())?;
+    }
+
+    // The line ends once the game does
+    *board.board_mut() = Board::from_fen("6br/5Ppk/6pp/8/8/8/8/K7 w - - 0 1").map_err(|_| ())?;
+    let line = PromotionPruningEngine::new(SimpleEngine).principal_variation(&board, 2);
+    assert_eq!(line, vec![Move::new(Tile::from_str("f7")?, Tile::from_str("f8")?, Some(PieceType::Knight))]);
+    Ok(())
+}
@@ -0,0 +1,174 @@
+/*
+ * This is a test of the game runner.
+ * It plays engines against each other headlessly,
+ * and confirms the results are tallied correctly.
+ */
+
+use capitalist_chess::*;
+use std::{str::FromStr, sync::Mutex, thread, time::Duration};
+
+static mut ALREADY_INIT: bool = false;
+
+fn init() {
+    unsafe {
+        if ALREADY_INIT {
+            return;
+        }
+        ALREADY_INIT = true;
+    }
+    let _ = env_logger::builder().is_test(true).try_init();
+}
+
+/// An engine that opens with e2e4 as white, and resigns as black.
+struct BlackResignsEngine;
+
+impl Engine for BlackResignsEngine {
+    fn name(&self) -> &str {
+        "Black Resigns Engine"
+    }
+
+    fn evaluate(&self, _board: &StateCapitalistBoard, _color: Color) -> f64 {
+        0.0
+    }
+
+    fn best_move(&self, board: &StateCapitalistBoard) -> Option<Move> {
+        match board.whose_turn() {
+            Color::White => Move::from_str("e2e4").ok(),
+            Color::Black => Some(Move::Resign),
+        }
+    }
+}
+
+/// This tests a short match between two random engines.
+#[test]
+fn random_match() {
+    init();
+    // Single actions keep the random engines from generating every combination of moves
+    let market = Market::default().with_max_actions_per_turn(1);
+    let result = Match::new(2).with_market(market).with_max_plies(20).play(&RandomEngine, &RandomEngine);
+    assert_eq!(result.completed() + result.unfinished, 2);
+    assert!(result.score() <= result.completed() as f64);
+}
+
+/// This tests that a match alternates colors, and tallies the results from the first engine's side.
+#[test]
+fn match_alternates_colors() {
+    init();
+    let result = Match::new(3).with_max_plies(20).play(&BlackResignsEngine, &BlackResignsEngine);
+    assert_eq!(
+        result,
+        MatchResult {
+            wins: 2,
+            draws: 0,
+            losses: 1,
+            unfinished: 0,
+        }
+    );
+    assert_eq!(result.score(), 2.0);
+}
+
+/// An engine that plays the given moves in order.
+struct ScriptedEngine(Mutex<Vec<Move>>);
+
+impl ScriptedEngine {
+    fn new(moves: &[&str]) -> Self {
+        Self(Mutex::new(moves.iter().rev().map(|m| Move::from_str(m).unwrap()).collect()))
+    }
+}
+
+impl Engine for ScriptedEngine {
+    fn name(&self) -> &str {
+        "Scripted Engine"
+    }
+
+    fn evaluate(&self, _board: &StateCapitalistBoard, _color: Color) -> f64 {
+        0.0
+    }
+
+    fn best_move(&self, _board: &StateCapitalistBoard) -> Option<Move> {
+        self.0.lock().unwrap().pop()
+    }
+}
+
+/// This tests that a capture flipping a center sector is recorded.
+#[test]
+fn sector_control_changes() -> Result<(), ()> {
+    init();
+    let engine = ScriptedEngine::new(&["e2e4", "d7d5", "e4d5"]);
+    let mut game = Game::new(Market::default(), &engine, &engine).with_max_plies(3);
+    assert_eq!(game.play(), None);
+
+    let e4 = Tile::from_str("e4")?.get_sector();
+    let d5 = Tile::from_str("d5")?.get_sector();
+    assert!(e4.is_center() && d5.is_center());
+    assert_eq!(game.get_board().sector_control_changes(), vec![
+        (1, e4, Some(Color::White)),
+        (2, d5, Some(Color::Black)),
+        (3, e4, None),
+        (3, d5, Some(Color::White)),
+    ]);
+    Ok(())
+}
+
+/// This tests that a logged game records every move its engines play.
+#[test]
+fn game_log() -> Result<(), ()> {
+    init();
+    let engine = ScriptedEngine::new(&["e2e4", "d7d5", "e4d5"]);
+    let mut game = Game::new(Market::default(), &engine, &engine).with_max_plies(3).with_logging();
+    assert!(game.game_log().ok_or(())?.is_empty());
+    assert_eq!(game.play(), None);
+
+    let log = game.game_log().ok_or(())?;
+    let moves: Vec<String> = log.get_entries().iter().map(|entry| entry.player_move.to_string()).collect();
+    assert_eq!(moves, ["e2e4", "d7d5", "e4d5"]);
+    assert_eq!(log.get_entries()[2].white_balance, game.get_board().get_balance(Color::White));
+
+    // Games aren't logged unless asked to be
+    let engine = ScriptedEngine::new(&["e2e4"]);
+    let mut game = Game::new(Market::default(), &engine, &engine).with_max_plies(1);
+    assert_eq!(game.play(), None);
+    assert!(game.game_log().is_none());
+    Ok(())
+}
+
+/// An engine that takes the given time to play a random move.
+struct SlowEngine(Duration);
+
+impl Engine for SlowEngine {
+    fn name(&self) -> &str {
+        "Slow Engine"
+    }
+
+    fn evaluate(&self, _board: &StateCapitalistBoard, _color: Color) -> f64 {
+        0.0
+    }
+
+    fn best_move(&self, board: &StateCapitalistBoard) -> Option<Move> {
+        thread::sleep(self.0);
+        RandomEngine.best_move(board)
+    }
+
+    fn best_move_timed(&self, board: &StateCapitalistBoard, _budget: Duration) -> Option<Move> {
+        self.best_move(board)
+    }
+}
+
+/// This tests that a player who runs out of time loses the game.
+#[test]
+fn timeout() {
+    init();
+    let mut clock = Clock::new(Duration::from_secs(3), Duration::from_secs(1));
+    assert!(clock.spend(Color::White, Duration::from_secs(2)));
+    assert_eq!(clock.remaining(Color::White), Duration::from_secs(2));
+    assert!(!clock.spend(Color::White, Duration::from_secs(5)));
+    assert_eq!(clock.remaining(Color::White), Duration::ZERO);
+    assert_eq!(clock.remaining(Color::Black), Duration::from_secs(3));
+
+    // White takes longer to move than it has left
+    let white = SlowEngine(Duration::from_millis(50));
+    let clock = Clock::new(Duration::from_millis(10), Duration::ZERO);
+    let mut game = Game::new(Market::default(), &white, &RandomEngine).with_clock(clock);
+    assert_eq!(game.play(), Some(GameResult::Timeout(Color::White)));
+    assert_eq!(game.get_plies(), 0);
+}